@@ -0,0 +1,134 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `chpasswd` updates the passwords of users in batch, reading `user:password` lines from the
+//! standard input.
+//!
+//! If any line is invalid, no change is made.
+
+use std::env::ArgsOs;
+use std::io;
+use std::io::Read;
+use std::process::exit;
+use utils::auth::Files;
+use utils::error;
+use utils::nss::{Nss, Source};
+use utils::pwquality::Policy;
+use utils::user::{PASSWD_PATH, hash_password, is_valid_field};
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" chpasswd [-e]");
+    println!();
+    println!("Updates passwords from `user:password` lines read on the standard input.");
+    println!();
+    println!("Options:");
+    println!(" -e, --encrypted\tthe passwords are already hashed");
+}
+
+pub fn main(args: ArgsOs) {
+    let mut encrypted = false;
+    for arg in args {
+        match arg.to_str() {
+            Some("-e" | "--encrypted") => encrypted = true,
+            Some("-h" | "--help") => {
+                print_usage();
+                exit(0);
+            }
+            _ => {
+                print_usage();
+                exit(1);
+            }
+        }
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap_or_else(|e| {
+        error("chpasswd", format_args!("cannot read input: {e}"));
+    });
+    let policy = Policy::load().unwrap_or_else(|e| {
+        error("chpasswd", format_args!("cannot read password policy: {e}"));
+    });
+    let passwd = Nss::load()
+        .and_then(|nss| nss.passwd())
+        .unwrap_or_else(|e| error("chpasswd", format_args!("cannot read passwd database: {e}")));
+    // Parse input and hash passwords before locking, since hashing is slow
+    let mut failed = false;
+    let changes: Vec<_> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .filter_map(|(i, line)| {
+            let Some((name, pass)) = line.split_once(':') else {
+                eprintln!("chpasswd: line {}: missing new password", i + 1);
+                failed = true;
+                return None;
+            };
+            let Some(user) = passwd.by_name(name) else {
+                eprintln!("chpasswd: line {}: user '{name}' does not exist", i + 1);
+                failed = true;
+                return None;
+            };
+            // Only the passwords stored in the files can be changed
+            if passwd.source_of(name) != Some(Source::Files) {
+                eprintln!(
+                    "chpasswd: line {}: user '{name}' is not stored in {PASSWD_PATH}",
+                    i + 1
+                );
+                failed = true;
+                return None;
+            }
+            if !encrypted && let Err(e) = policy.check(pass, None, name, user.comment) {
+                if policy.is_enforced() {
+                    eprintln!("chpasswd: line {}: {e}", i + 1);
+                    failed = true;
+                    return None;
+                }
+                eprintln!("chpasswd: line {}: warning: {e}", i + 1);
+            }
+            if encrypted && !is_valid_field(pass) {
+                eprintln!("chpasswd: line {}: invalid password hash", i + 1);
+                failed = true;
+                return None;
+            }
+            let hash = if encrypted {
+                pass.to_owned()
+            } else {
+                match hash_password(pass) {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        eprintln!("chpasswd: line {}: cannot hash password: {e}", i + 1);
+                        failed = true;
+                        return None;
+                    }
+                }
+            };
+            Some((name, hash))
+        })
+        .collect();
+    if failed {
+        error("chpasswd", "errors detected, changes ignored");
+    }
+    let hashes: Vec<_> = changes
+        .iter()
+        .map(|(name, hash)| (*name, hash.as_str()))
+        .collect();
+    Files::new()
+        .set_hashes(&hashes)
+        .unwrap_or_else(|e| error("chpasswd", format_args!("cannot change passwords: {e}")));
+}
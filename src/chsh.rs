@@ -0,0 +1,324 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `chsh` changes the login shell of a user, and `chfn` changes the comment (GECOS) field of a
+//! user.
+//!
+//! Unless run by root, both commands only allow a user to modify their own entry, after
+//! authenticating with their password.
+
+use std::env::ArgsOs;
+use std::ffi::OsString;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::process::exit;
+use std::time::Duration;
+use std::{fmt, fs};
+use utils::auth::{Backend, NameService};
+use utils::nss::Nss;
+use utils::prompt::prompt;
+use utils::user::{Lock, PASSWD_PATH, User, get_uid, is_valid_field, read_shells};
+use utils::{error, util};
+
+/// The fields of the comment (GECOS) field of a user, which are comma-separated.
+struct Gecos {
+    /// The user's full name.
+    full_name: String,
+    /// The user's room number.
+    room: String,
+    /// The user's work phone number.
+    work_phone: String,
+    /// The user's home phone number.
+    home_phone: String,
+    /// Other information.
+    other: String,
+}
+
+impl Gecos {
+    /// Parses the given comment field.
+    fn parse(comment: &str) -> Self {
+        let mut fields = comment.splitn(5, ',').map(str::to_owned);
+        Self {
+            full_name: fields.next().unwrap_or_default(),
+            room: fields.next().unwrap_or_default(),
+            work_phone: fields.next().unwrap_or_default(),
+            home_phone: fields.next().unwrap_or_default(),
+            other: fields.next().unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for Gecos {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let fields = [
+            self.full_name.as_str(),
+            &self.room,
+            &self.work_phone,
+            &self.home_phone,
+            &self.other,
+        ];
+        // Do not write trailing empty fields
+        let len = fields
+            .iter()
+            .rposition(|f| !f.is_empty())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        write!(f, "{}", fields[..len].join(","))
+    }
+}
+
+/// The command's arguments.
+#[derive(Default)]
+struct Args {
+    /// The user to modify. If `None`, the current user is used.
+    user: Option<String>,
+
+    /// The new shell (`chsh`).
+    shell: Option<String>,
+
+    /// The new full name (`chfn`).
+    full_name: Option<String>,
+    /// The new room number (`chfn`).
+    room: Option<String>,
+    /// The new work phone number (`chfn`).
+    work_phone: Option<String>,
+    /// The new home phone number (`chfn`).
+    home_phone: Option<String>,
+    /// The new other information (`chfn`).
+    other: Option<String>,
+}
+
+impl Args {
+    /// Tells whether a new value has been given for at least one field.
+    fn has_value(&self) -> bool {
+        self.shell.is_some()
+            || self.full_name.is_some()
+            || self.room.is_some()
+            || self.work_phone.is_some()
+            || self.home_phone.is_some()
+            || self.other.is_some()
+    }
+}
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    println!("Usage:");
+    match bin {
+        "chsh" => {
+            println!(" chsh [-s shell] [user]");
+            println!();
+            println!("Changes the login shell of a user.");
+            println!();
+            println!("Options:");
+            println!(" -s, --shell <shell>\tthe new login shell");
+        }
+        _ => {
+            println!(" chfn [options] [user]");
+            println!();
+            println!("Changes the comment field of a user.");
+            println!();
+            println!("Options:");
+            println!(" -f, --full-name <name>\tthe new full name");
+            println!(" -r, --room <room>\tthe new room number");
+            println!(" -w, --work-phone <phone>\tthe new work phone number");
+            println!(" -h, --home-phone <phone>\tthe new home phone number");
+            println!(" -o, --other <other>\tthe new other information");
+        }
+    }
+}
+
+/// Converts the given argument into a [`String`], exiting on failure.
+fn arg_to_string(bin: &str, arg: OsString) -> String {
+    arg.into_string()
+        .unwrap_or_else(|arg| error(bin, format_args!("invalid argument `{}`", arg.display())))
+}
+
+/// Parses the given CLI arguments `args` and returns their representation in the [`Args`]
+/// structure.
+fn parse_args(bin: &str, args: ArgsOs) -> Args {
+    let mut res = Args::default();
+    let mut args = args.map(|arg| arg_to_string(bin, arg));
+    while let Some(arg) = args.next() {
+        let field = match (bin, arg.as_str()) {
+            ("chsh", "-s" | "--shell") => &mut res.shell,
+            ("chfn", "-f" | "--full-name") => &mut res.full_name,
+            ("chfn", "-r" | "--room") => &mut res.room,
+            ("chfn", "-w" | "--work-phone") => &mut res.work_phone,
+            ("chfn", "-h" | "--home-phone") => &mut res.home_phone,
+            ("chfn", "-o" | "--other") => &mut res.other,
+            (_, "--help") => {
+                print_usage(bin);
+                exit(0);
+            }
+            (_, a) if a.starts_with('-') => {
+                error(bin, format_args!("invalid option `{a}`"));
+            }
+            _ => {
+                if res.user.is_some() {
+                    print_usage(bin);
+                    exit(1);
+                }
+                res.user = Some(arg);
+                continue;
+            }
+        };
+        let Some(val) = args.next() else {
+            error(bin, format_args!("option `{arg}` requires a value"));
+        };
+        *field = Some(val);
+    }
+    res
+}
+
+/// Prompts for a new value for the field with the given `name`, returning `current` if nothing
+/// is entered.
+fn prompt_field(name: &str, current: &str) -> String {
+    let val = prompt(format_args!("{name} [{current}]: "), false).unwrap_or_else(|| exit(1));
+    let val = val.trim();
+    if val.is_empty() {
+        current.to_owned()
+    } else {
+        val.to_owned()
+    }
+}
+
+/// Checks the validity of the new shell `shell`.
+fn check_shell(shell: &str) {
+    if !shell.starts_with('/') {
+        error("chsh", format_args!("{shell} is not an absolute path"));
+    }
+    if !is_valid_field(shell) {
+        error("chsh", format_args!("{shell} contains invalid characters"));
+    }
+    let shells = read_shells().unwrap_or_else(|e| {
+        error("chsh", format_args!("cannot read shells list: {e}"));
+    });
+    if !shells.iter().any(|s| s == shell) {
+        error("chsh", format_args!("{shell} is not listed in /etc/shells"));
+    }
+}
+
+/// Checks the validity of the new GECOS field value `val` for the field with the given `name`.
+///
+/// `last` tells whether the field is the last one, in which case commas are allowed.
+fn check_gecos_field(name: &str, val: &str, last: bool) {
+    if !is_valid_field(val) || (!last && val.contains(',')) {
+        error("chfn", format_args!("{name}: invalid characters"));
+    }
+}
+
+/// Computes the new comment field from the current one `comment`, using the values given as
+/// arguments or prompting for them.
+fn new_comment(args: Args, comment: &str) -> String {
+    let mut gecos = Gecos::parse(comment);
+    if args.has_value() {
+        let fields = [
+            ("Full Name", args.full_name, &mut gecos.full_name),
+            ("Room Number", args.room, &mut gecos.room),
+            ("Work Phone", args.work_phone, &mut gecos.work_phone),
+            ("Home Phone", args.home_phone, &mut gecos.home_phone),
+            ("Other", args.other, &mut gecos.other),
+        ];
+        for (i, (name, val, field)) in fields.into_iter().enumerate() {
+            if let Some(val) = val {
+                check_gecos_field(name, &val, i == 4);
+                *field = val;
+            }
+        }
+    } else {
+        println!("Enter the new value, or press ENTER for the default");
+        let fields = [
+            ("Full Name", &mut gecos.full_name),
+            ("Room Number", &mut gecos.room),
+            ("Work Phone", &mut gecos.work_phone),
+            ("Home Phone", &mut gecos.home_phone),
+        ];
+        for (name, field) in fields {
+            let val = prompt_field(name, field);
+            check_gecos_field(name, &val, false);
+            *field = val;
+        }
+    }
+    gecos.to_string()
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    let args = parse_args(bin, args);
+    let uid = get_uid();
+    // Get the entry to modify
+    let passwd = Nss::load()
+        .and_then(|nss| nss.passwd())
+        .unwrap_or_else(|e| error(bin, format_args!("cannot read passwd database: {e}")));
+    let user = match &args.user {
+        Some(name) => passwd.by_name(name),
+        None => passwd.by_uid(uid),
+    };
+    let Some(user) = user else {
+        match &args.user {
+            Some(name) => error(bin, format_args!("user `{name}` does not exist")),
+            None => error(bin, format_args!("cannot find user with UID {uid}")),
+        }
+    };
+    // Check permissions
+    if uid != 0 {
+        if user.uid != uid {
+            error(bin, "permission denied");
+        }
+        let pass = prompt("Password: ", true).unwrap_or_else(|| exit(1));
+        let correct = util::exec_wait(Duration::from_millis(1000), || {
+            NameService::new().authenticate(user.login_name, &pass)
+        })
+        .unwrap_or_else(|e| error(bin, format_args!("cannot read account files: {e}")));
+        if !correct {
+            error(bin, "Authentication failure");
+        }
+    }
+    println!("Changing the user information for {}", user.login_name);
+    let login_name = user.login_name.to_owned();
+    // Compute the new value
+    let new_val = match bin {
+        "chsh" => {
+            let shell = match args.shell {
+                Some(shell) => shell,
+                None => prompt_field("Login Shell", user.interpreter),
+            };
+            check_shell(&shell);
+            shell
+        }
+        _ => new_comment(args, user.comment),
+    };
+    // Write back
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot lock passwd file: {e}"));
+    });
+    let users_buff = fs::read_to_string(PASSWD_PATH).unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read passwd file: {e}"));
+    });
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error(bin, format_args!("{PASSWD_PATH}: {e}")));
+    let Some(user) = users.iter_mut().find(|u| u.login_name == login_name) else {
+        error(bin, format_args!("user `{login_name}` does not exist"));
+    };
+    match bin {
+        "chsh" => user.interpreter = &new_val,
+        _ => user.comment = &new_val,
+    }
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error(bin, format_args!("cannot write passwd file: {e}")));
+}
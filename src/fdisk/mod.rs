@@ -25,15 +25,34 @@ use std::env::ArgsOs;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io;
-use std::io::Write;
+use std::io::{ErrorKind, Read, Write};
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
 use utils::disk::Disk;
 use utils::error;
-use utils::partition::PartitionTable;
-use utils::prompt::prompt;
+use utils::partition::{
+    Change, LabelId, PartitionAttrs, PartitionTable, PartitionTableType, PartitionType,
+};
+use utils::prompt::{Editor, confirm, number_in_range, prompt};
+use utils::util::ByteSize;
+use uuid::Uuid;
+
+/// An operation of `sfdisk` on a single partition.
+#[derive(Clone, Copy)]
+enum Operation {
+    /// Delete partitions.
+    Delete,
+    /// Get or set the type of a partition.
+    Type,
+    /// Get or set the UUID of a partition.
+    Uuid,
+    /// Get or set the name of a partition.
+    Label,
+    /// Get or set the attribute flags of a partition.
+    Attrs,
+}
 
 /// Structure storing command line arguments.
 #[derive(Default)]
@@ -42,7 +61,19 @@ struct Args {
     help: bool,
     /// If true, list partitions instead of modifying the table.
     list: bool,
+    /// If true, dump the partition table as a script instead of modifying it.
+    dump: bool,
+    /// If true, dump the partition table as JSON instead of modifying it.
+    json: bool,
+    /// If true, do everything except writing to the disk.
+    no_act: bool,
+    /// If true, add the partitions of the script instead of replacing the table.
+    append: bool,
+    /// The operation on a single partition to perform, if any.
+    operation: Option<Operation>,
     /// The list of disk devices.
+    ///
+    /// With an operation, these are the disk device, followed by the operation's arguments.
     disks: Vec<PathBuf>,
 }
 
@@ -52,6 +83,15 @@ fn parse_args(args: ArgsOs) -> Args {
         match arg.to_str() {
             Some("-h" | "--help") => res.help = true,
             Some("-l" | "--list") => res.list = true,
+            Some("-d" | "--dump") => res.dump = true,
+            Some("-J" | "--json") => res.json = true,
+            Some("-n" | "--no-act") => res.no_act = true,
+            Some("-a" | "--append") => res.append = true,
+            Some("--delete") => res.operation = Some(Operation::Delete),
+            Some("--part-type") => res.operation = Some(Operation::Type),
+            Some("--part-uuid") => res.operation = Some(Operation::Uuid),
+            Some("--part-label") => res.operation = Some(Operation::Label),
+            Some("--part-attrs") => res.operation = Some(Operation::Attrs),
             // TODO implement other options
             _ => res.disks.push(arg.into()),
         }
@@ -80,6 +120,19 @@ fn print_help(script: bool) {
     println!("Options:");
     println!(" -h, --help\tPrints help.");
     println!(" -l, --list\tLists partitions.");
+    if script {
+        println!(" -d, --dump\tDumps the partition table as a script.");
+        println!(" -J, --json\tDumps the partition table as JSON.");
+        println!(" -n, --no-act\tDoes everything except writing to the disk.");
+        println!(" -a, --append\tAppends partitions to the existing table.");
+        println!();
+        println!("Commands:");
+        println!(" --delete <dev> [<part>...]\tDeletes all or the given partitions.");
+        println!(" --part-type <dev> <part> [<type>]\tPrints or changes the partition type.");
+        println!(" --part-uuid <dev> <part> [<uuid>]\tPrints or changes the partition UUID.");
+        println!(" --part-label <dev> <part> [<name>]\tPrints or changes the partition name.");
+        println!(" --part-attrs <dev> <part> [<attrs>]\tPrints or changes the partition flags.");
+    }
 }
 
 /// Prints help for fdisk's internal commands.
@@ -102,6 +155,7 @@ fn print_cmd_help() {
     println!();
     println!("  Misc");
     println!("   m  print this menu");
+    println!("   u  undo the last change");
     println!();
     println!("  Script");
     println!("   I  load disk layout from sfdisk script file");
@@ -120,8 +174,20 @@ fn print_cmd_help() {
 /// Imports the script in the file at the given path and applies it to the given disk.
 fn import_script(disk: &mut Disk, path: &Path) -> io::Result<()> {
     let script = fs::read_to_string(path)?;
-    // TODO handle error
-    disk.partition_table = PartitionTable::from_str(&script).unwrap();
+    let sector_size = disk.sector_size() as u64;
+    let mut table =
+        PartitionTable::from_script(&script, disk.partition_table.table_type, sector_size)
+            .map_err(|(line, msg)| {
+                io::Error::new(ErrorKind::InvalidData, format!("line {line}: {msg}"))
+            })?;
+    table
+        .fill_defaults(disk.sectors_count(), sector_size)
+        .map_err(|msg| io::Error::new(ErrorKind::InvalidData, msg))?;
+    let problems = table.verify(disk.sectors_count(), sector_size);
+    if !problems.is_empty() {
+        return Err(io::Error::new(ErrorKind::InvalidData, problems.join(" ")));
+    }
+    disk.partition_table = table;
     Ok(())
 }
 
@@ -132,57 +198,566 @@ fn export_script(disk: &Disk, path: &Path) -> io::Result<()> {
         .write(true)
         .truncate(true)
         .open(path)?;
-    let serialized = disk.partition_table.serialize(path);
+    let serialized = disk.partition_table.serialize(disk.get_path());
     script_file.write_all(serialized.as_bytes())?;
     script_file.flush()?;
     Ok(())
 }
 
-/// TODO doc
+/// Returns the paths starting with `prefix`, for completion.
+///
+/// Directories are suffixed with a `/`.
+fn complete_path(prefix: &str) -> Vec<String> {
+    let (dir, name) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return vec![];
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let file_name = e.file_name().into_string().ok()?;
+            if !file_name.starts_with(name) || (name.is_empty() && file_name.starts_with('.')) {
+                return None;
+            }
+            let suffix = if e.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{file_name}{suffix}"))
+        })
+        .collect();
+    paths.sort_unstable();
+    paths
+}
+
+/// Prompts for a path, with completion.
+fn prompt_path(prompt: &str) -> Option<String> {
+    let mut editor = Editor::new();
+    editor.set_completer(complete_path);
+    editor.read_line(prompt)
+}
+
+/// Prompts for the number of a partition of `disk`, starting at `1`.
+///
+/// If the disk has only one partition, it is selected without prompting. If the disk has no
+/// partition, or on EOF, the function returns `None`.
+fn select_partition(disk: &Disk) -> Option<usize> {
+    let table = &disk.partition_table;
+    let last = table.partitions.len();
+    match table.count() {
+        0 => {
+            eprintln!("No partition is defined yet!");
+            None
+        }
+        1 => {
+            println!("Selected partition {last}");
+            Some(last)
+        }
+        _ => loop {
+            let n = number_in_range("Partition number", 1, last, last).ok()?;
+            if table.get(n).is_some() {
+                break Some(n);
+            }
+            eprintln!("Partition {n} does not exist yet!");
+        },
+    }
+}
+
+/// Returns the name of the type of the given partition, for display.
+fn type_name(part_type: &PartitionType) -> String {
+    match part_type.name() {
+        Some(name) => name.to_owned(),
+        None => format!("unknown ({part_type})"),
+    }
+}
+
+/// Prints the unpartitioned space of `disk`.
+fn print_free_space(disk: &Disk) {
+    let sector_size = disk.sector_size() as u64;
+    let free = disk
+        .partition_table
+        .free_ranges(disk.sectors_count(), sector_size);
+    let sectors: u64 = free.iter().map(|(start, end)| end - start + 1).sum();
+    println!(
+        "Unpartitioned space {}: {}, {} bytes, {sectors} sectors",
+        disk.get_path().display(),
+        ByteSize(sectors * sector_size),
+        sectors * sector_size
+    );
+    println!("Units: sectors of 1 * {sector_size} = {sector_size} bytes");
+    if free.is_empty() {
+        return;
+    }
+    println!();
+    println!("Start\tEnd\tSectors\tSize");
+    for (start, end) in free {
+        let sectors = end - start + 1;
+        println!(
+            "{start}\t{end}\t{sectors}\t{}",
+            ByteSize(sectors * sector_size)
+        );
+    }
+}
+
+/// Prints information about the partition with the number `n` of `disk`, starting at `1`.
+fn print_partition_info(disk: &Disk, n: usize) {
+    let Some(p) = disk.partition_table.get(n) else {
+        return;
+    };
+    println!("         Device: {}", disk.partition_path(n).display());
+    println!("          Start: {}", p.start);
+    println!("            End: {}", (p.start + p.size).saturating_sub(1));
+    println!("        Sectors: {}", p.size);
+    println!(
+        "           Size: {}",
+        ByteSize(p.size * disk.sector_size() as u64)
+    );
+    println!("           Type: {}", type_name(&p.part_type));
+    match &p.part_type {
+        PartitionType::Mbr(id) => println!("             Id: {id:02x}"),
+        PartitionType::Gpt(uuid) => println!("      Type-UUID: {uuid}"),
+    }
+    if let Some(uuid) = &p.uuid {
+        println!("           UUID: {uuid}");
+    }
+    if let Some(name) = &p.name {
+        println!("           Name: {name}");
+    }
+    if !p.attrs.is_empty() {
+        println!("          Attrs: {}", p.attrs);
+    }
+    if p.bootable {
+        println!("       Bootable: *");
+    }
+}
+
+/// Replaces the partition table of `disk` with an empty one of the given type.
+fn create_label(disk: &mut Disk, table_type: PartitionTableType) {
+    let uuid = Uuid::new_v4();
+    let id = match table_type {
+        PartitionTableType::Mbr => LabelId::Mbr(uuid.as_u128() as u32),
+        PartitionTableType::Gpt => LabelId::Gpt(uuid),
+    };
+    disk.partition_table = PartitionTable {
+        id: Some(id),
+        ..PartitionTable::new(table_type)
+    };
+    match table_type {
+        PartitionTableType::Mbr => println!("Created a new DOS disklabel (identifier: {id})."),
+        PartitionTableType::Gpt => println!("Created a new GPT disklabel (GUID: {id})."),
+    }
+}
+
+/// Writes the partition table of `disk` and makes the kernel read it again.
+///
+/// On failure, the function prints an error and exits. `bin` is the name of the command.
+fn write_disk(bin: &str, disk: &mut Disk) {
+    let path = disk.get_path().to_path_buf();
+    match disk.write() {
+        Ok(_) => println!("The partition table has been altered."),
+        Err(e) => error(
+            bin,
+            format_args!("cannot write to disk `{}`: {e}", path.display()),
+        ),
+    }
+    match utils::disk::read_partitions(&path) {
+        Ok(_) => println!("Syncing disks."),
+        Err(e) => error(
+            bin,
+            format_args!("cannot read partition table from `{}`: {e}", path.display()),
+        ),
+    }
+}
+
+/// Opens the disk at `path`. On failure, the function prints an error and exits.
+///
+/// `bin` is the name of the command.
+fn open_disk(bin: &str, path: &Path) -> Disk {
+    match Disk::read(path.to_path_buf()) {
+        Ok(Some(disk)) => {
+            if disk.partition_table.from_backup {
+                eprintln!(
+                    "{bin}: warning: {}: the primary GPT table is corrupt, the backup table is used",
+                    path.display()
+                );
+            }
+            disk
+        }
+        Ok(None) => error(
+            bin,
+            format_args!("cannot open {}: Invalid argument", path.display()),
+        ),
+        Err(e) => error(bin, format_args!("cannot open {}: {e}", path.display())),
+    }
+}
+
+/// Parses the partition number `n` of `disk`, starting at `1`. On failure, the function prints an
+/// error and exits.
+fn parse_partition_number(disk: &Disk, n: &Path) -> usize {
+    n.to_str()
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| disk.partition_table.get(*n).is_some())
+        .unwrap_or_else(|| {
+            error(
+                "sfdisk",
+                format_args!("invalid partition number `{}`", n.display()),
+            )
+        })
+}
+
+/// Runs the sfdisk operation `op` on a single partition.
+///
+/// `args` are the arguments of the operation, starting with the path to the disk.
+fn run_operation(op: Operation, args: &Args) {
+    let [disk_path, op_args @ ..] = args.disks.as_slice() else {
+        print_usage();
+        exit(1);
+    };
+    let mut disk = open_disk("sfdisk", disk_path);
+    if let Operation::Delete = op {
+        let mut numbers: Vec<_> = op_args
+            .iter()
+            .map(|n| parse_partition_number(&disk, n))
+            .collect();
+        if numbers.is_empty() {
+            numbers = disk.partition_table.iter().map(|(n, _)| n).collect();
+        }
+        for n in numbers {
+            disk.partition_table.remove(n);
+        }
+    } else {
+        let (n, value) = match op_args {
+            [n] => (n, None),
+            [n, value] => (n, Some(value)),
+            _ => {
+                print_usage();
+                exit(1);
+            }
+        };
+        let n = parse_partition_number(&disk, n);
+        let table_type = disk.partition_table.table_type;
+        let gpt_only = matches!(op, Operation::Uuid | Operation::Label | Operation::Attrs);
+        if gpt_only && table_type != PartitionTableType::Gpt {
+            error(
+                "sfdisk",
+                format_args!("operation not supported by {table_type} tables"),
+            );
+        }
+        let Some(p) = disk.partition_table.get_mut(n) else {
+            unreachable!();
+        };
+        let Some(value) = value else {
+            match op {
+                Operation::Type => println!("{}", p.part_type),
+                Operation::Uuid => {
+                    println!("{}", p.uuid.map(|u| u.to_string()).unwrap_or_default())
+                }
+                Operation::Label => println!("{}", p.name.as_deref().unwrap_or_default()),
+                Operation::Attrs => println!("{}", p.attrs),
+                Operation::Delete => unreachable!(),
+            }
+            return;
+        };
+        let value = value
+            .to_str()
+            .unwrap_or_else(|| error("sfdisk", "invalid value"));
+        let invalid = format!("invalid value `{value}`");
+        match op {
+            Operation::Type => {
+                p.part_type = table_type
+                    .parse_partition_type(value)
+                    .unwrap_or_else(|| error("sfdisk", &invalid));
+            }
+            Operation::Uuid => {
+                p.uuid = Some(Uuid::from_str(value).unwrap_or_else(|_| error("sfdisk", &invalid)))
+            }
+            Operation::Label => p.name = Some(value.to_owned()).filter(|name| !name.is_empty()),
+            Operation::Attrs => {
+                p.attrs =
+                    PartitionAttrs::from_str(value).unwrap_or_else(|_| error("sfdisk", &invalid))
+            }
+            Operation::Delete => unreachable!(),
+        }
+    }
+    if args.no_act {
+        println!("The partition table is unchanged (--no-act).");
+        return;
+    }
+    write_disk("sfdisk", &mut disk);
+}
+
+/// Runs `sfdisk` on the disk at `disk_path`, reading the script from the standard input.
+fn run_script(args: &Args, disk_path: &Path) {
+    let mut disk = open_disk("sfdisk", disk_path);
+    if args.dump {
+        print!("{}", disk.partition_table.serialize(disk_path));
+        return;
+    }
+    if args.json {
+        let json = disk.partition_table.to_json(
+            disk_path,
+            disk.sectors_count(),
+            disk.sector_size() as u64,
+        );
+        println!("{json}");
+        return;
+    }
+    let mut script = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut script) {
+        error("sfdisk", format_args!("cannot read script: {e}"));
+    }
+    let sector_size = disk.sector_size() as u64;
+    let table = PartitionTable::from_script(&script, disk.partition_table.table_type, sector_size)
+        .unwrap_or_else(|(line, msg)| {
+            error("sfdisk", format_args!("invalid script: line {line}: {msg}"))
+        });
+    if args.append {
+        if table.table_type != disk.partition_table.table_type {
+            error(
+                "sfdisk",
+                format_args!(
+                    "cannot append to a {} table with a {} script",
+                    disk.partition_table.table_type, table.table_type
+                ),
+            );
+        }
+        for (_, part) in table.iter() {
+            disk.partition_table.add(part.clone());
+        }
+    } else {
+        disk.partition_table = table;
+    }
+    disk.partition_table
+        .fill_defaults(disk.sectors_count(), sector_size)
+        .unwrap_or_else(|e| error("sfdisk", format_args!("invalid script: {e}")));
+    let problems = disk
+        .partition_table
+        .verify(disk.sectors_count(), sector_size);
+    if !problems.is_empty() {
+        for problem in problems {
+            eprintln!("sfdisk: {problem}");
+        }
+        error("sfdisk", "the script does not fit the disk");
+    }
+    println!("New situation:");
+    println!("{disk}");
+    if args.no_act {
+        println!("The partition table is unchanged (--no-act).");
+        return;
+    }
+    write_disk("sfdisk", &mut disk);
+}
+
+/// Prints the changes made to the partition table of `disk`, compared to `original`.
+///
+/// If no change has been made, the function returns `false`.
+fn print_changes(disk: &Disk, original: &PartitionTable) -> bool {
+    let sector_size = disk.sector_size() as u64;
+    let changes = original.diff(&disk.partition_table);
+    for change in &changes {
+        match change {
+            Change::TableType { old, new } => {
+                println!("Disklabel type changed from {old} to {new}.");
+            }
+            Change::TableId { new, .. } => match new {
+                Some(id) => println!("Disk identifier changed to {id}."),
+                None => println!("Disk identifier removed."),
+            },
+            Change::TableLayout { old, new } => {
+                let layout = [
+                    ("First usable sector", old.first_lba, new.first_lba),
+                    ("Last usable sector", old.last_lba, new.last_lba),
+                    (
+                        "Partition table length",
+                        old.table_length.map(|n| n as u64),
+                        new.table_length.map(|n| n as u64),
+                    ),
+                ];
+                for (name, old, new) in layout {
+                    match new {
+                        _ if old == new => {}
+                        Some(val) => println!("{name} changed to {val}."),
+                        None => println!("{name} reset to the default."),
+                    }
+                }
+            }
+            Change::Added { n, part } => println!(
+                "Partition {n}: added, sectors {}-{}, {}, type '{}'.",
+                part.start,
+                (part.start + part.size).saturating_sub(1),
+                ByteSize(part.size * sector_size),
+                type_name(&part.part_type)
+            ),
+            Change::Removed { n, part } => println!(
+                "Partition {n}: removed, sectors {}-{}, {}.",
+                part.start,
+                (part.start + part.size).saturating_sub(1),
+                ByteSize(part.size * sector_size)
+            ),
+            Change::Resized { n, old, new } => println!(
+                "Partition {n}: resized from {} to {} sectors ({} to {}).",
+                old.size,
+                new.size,
+                ByteSize(old.size * sector_size),
+                ByteSize(new.size * sector_size)
+            ),
+            Change::Retyped { n, old, new } => println!(
+                "Partition {n}: type changed from '{}' to '{}'.",
+                type_name(old),
+                type_name(new)
+            ),
+            Change::Modified { n, old, new } => {
+                if old.bootable != new.bootable {
+                    let state = if new.bootable { "enabled" } else { "disabled" };
+                    println!("Partition {n}: bootable flag {state}.");
+                }
+                if old.uuid != new.uuid {
+                    println!("Partition {n}: UUID changed.");
+                }
+                if old.name != new.name {
+                    match &new.name {
+                        Some(name) => println!("Partition {n}: name changed to '{name}'."),
+                        None => println!("Partition {n}: name removed."),
+                    }
+                }
+                if old.attrs != new.attrs && new.attrs.is_empty() {
+                    println!("Partition {n}: attributes cleared.");
+                } else if old.attrs != new.attrs {
+                    println!("Partition {n}: attributes changed to '{}'.", new.attrs);
+                }
+            }
+        }
+    }
+    !changes.is_empty()
+}
+
+/// Handles the command `cmd` on `disk`.
 ///
-/// If modifications have been made, the function returns `true`.
-fn handle_cmd(cmd: &str, disk_path: &Path, disk: &mut Disk) {
+/// Arguments:
+/// - `original` is the partition table as read from the disk.
+/// - `history` is the list of previous versions of the partition table, to undo changes.
+fn handle_cmd(
+    cmd: &str,
+    disk_path: &Path,
+    disk: &mut Disk,
+    original: &PartitionTable,
+    history: &mut Vec<PartitionTable>,
+) {
+    let before = disk.partition_table.clone();
     match cmd {
         "a" => {
-            // TODO check whether partition table type supports boot flag. If not, error
-            // TODO select partition and toggle boot flag
+            if disk.partition_table.table_type != PartitionTableType::Mbr {
+                eprintln!("The bootable flag is only supported on DOS disklabels.");
+            } else if let Some(n) = select_partition(disk)
+                && let Some(p) = disk.partition_table.get_mut(n)
+            {
+                p.bootable = !p.bootable;
+                let state = if p.bootable { "enabled" } else { "disabled" };
+                println!("The bootable flag on partition {n} is {state} now.");
+            }
         }
 
         "d" => {
-            // TODO:
-            // - If only one partition is present, use it and print `Selected partition 1`
-            // - Else, prompt `Partition number (1,2,... default <>): `
-            // - Delete partition and print `Partition <> has been deleted.`
+            if let Some(n) = select_partition(disk) {
+                disk.partition_table.remove(n);
+                println!("Partition {n} has been deleted.");
+            }
         }
 
-        "F" => todo!(), // TODO
+        "F" => print_free_space(disk),
 
         "l" => disk.partition_table.table_type.print_partition_types(),
 
         "n" => {
-            let _new_partition = disk.partition_table.table_type.prompt_new_partition();
-            // TODO insert new partition to disk
+            let sector_size = disk.sector_size() as u64;
+            let new_partition = disk
+                .partition_table
+                .prompt_new_partition(disk.sectors_count(), sector_size);
+            match new_partition {
+                Ok(p) => {
+                    let part_type = type_name(&p.part_type);
+                    let size = ByteSize(p.size * sector_size);
+                    let n = disk.partition_table.add(p);
+                    println!(
+                        "Created a new partition {n} of type '{part_type}' and of size {size}."
+                    );
+                }
+                Err(e) => eprintln!("cannot create partition: {e}"),
+            }
         }
 
         "p" => println!("{disk}\n"),
 
         "t" => {
-            // TODO:
-            // - If only one partition is present, use it and print `Selected partition 1`
-            // - Else, prompt `Partition number (1,2,... default <>): `
-            // - Prompt `Partition type (type L to list all): `
-            //   - On L: partition_table_type.print_partition_types()
-            // - Change type and print `Changed type of partition `<old>` to `<new>``
+            if let Some(n) = select_partition(disk) {
+                let table_type = &disk.partition_table.table_type;
+                let part_type = loop {
+                    let Some(input) = prompt("Partition type (type L to list all): ", false) else {
+                        break None;
+                    };
+                    match input.trim() {
+                        "L" => table_type.print_partition_types(),
+                        s => match table_type.parse_partition_type(s) {
+                            Some(t) => break Some(t),
+                            None => eprintln!("Invalid partition type `{s}`."),
+                        },
+                    }
+                };
+                if let Some(part_type) = part_type
+                    && let Some(p) = disk.partition_table.get_mut(n)
+                {
+                    let old = std::mem::replace(&mut p.part_type, part_type);
+                    println!(
+                        "Changed type of partition '{}' to '{}'.",
+                        type_name(&old),
+                        type_name(&p.part_type)
+                    );
+                }
+            }
         }
 
-        "v" => todo!(), // TODO
+        "v" => {
+            let sector_size = disk.sector_size() as u64;
+            let problems = disk
+                .partition_table
+                .verify(disk.sectors_count(), sector_size);
+            for problem in &problems {
+                println!("{problem}");
+            }
+            if problems.is_empty() {
+                println!("No errors detected.");
+            }
+            let free: u64 = disk
+                .partition_table
+                .free_ranges(disk.sectors_count(), sector_size)
+                .iter()
+                .map(|(start, end)| end - start + 1)
+                .sum();
+            println!("Remaining {free} unallocated {sector_size}-byte sectors.");
+        }
 
-        "i" => todo!(), // TODO
+        "i" => {
+            if let Some(n) = select_partition(disk) {
+                print_partition_info(disk, n);
+            }
+        }
 
         "m" => print_cmd_help(),
 
+        "u" => {
+            match history.pop() {
+                Some(table) => {
+                    disk.partition_table = table;
+                    println!("The last change has been undone.");
+                }
+                None => println!("Nothing to undo."),
+            }
+            println!();
+            return;
+        }
+
         "I" => {
-            if let Some(script_path) = prompt("Enter script file name: ", false) {
+            if let Some(script_path) = prompt_path("Enter script file name: ") {
                 let script_path = PathBuf::from(script_path);
 
                 match import_script(disk, &script_path) {
@@ -193,7 +768,7 @@ fn handle_cmd(cmd: &str, disk_path: &Path, disk: &mut Disk) {
         }
 
         "O" => {
-            if let Some(script_path) = prompt("Enter script file name: ", false) {
+            if let Some(script_path) = prompt_path("Enter script file name: ") {
                 let script_path = PathBuf::from(script_path);
 
                 match export_script(disk, &script_path) {
@@ -204,62 +779,59 @@ fn handle_cmd(cmd: &str, disk_path: &Path, disk: &mut Disk) {
         }
 
         "w" => {
-            // TODO ask only if modifications have been made
-            let prompt_str = format!("Write changes to `{}`? (y/n) ", disk_path.display());
-            let confirm = prompt(&prompt_str, false)
-                .map(|s| s == "y")
-                .unwrap_or(false);
-            if !confirm {
+            let problems = disk
+                .partition_table
+                .verify(disk.sectors_count(), disk.sector_size() as u64);
+            if !problems.is_empty() {
+                for problem in &problems {
+                    eprintln!("{problem}");
+                }
+                eprintln!("The partition table is invalid and cannot be written.");
+                println!();
                 return;
             }
-
-            match disk.write() {
-                Ok(_) => println!("The partition table has been altered."),
-                Err(e) => {
-                    eprintln!("cannot write to disk `{}`: {e}", disk_path.display());
-                    exit(1);
-                }
+            if !print_changes(disk, original) {
+                println!("The partition table has not been altered.");
+                exit(0);
             }
+            let confirm = confirm(
+                format_args!("Write changes to `{}`?", disk_path.display()),
+                false,
+            );
+            if !confirm.unwrap_or(false) {
+                return;
+            }
+            write_disk("fdisk", disk);
+            exit(0);
+        }
 
-            match utils::disk::read_partitions(disk.get_path()) {
-                Ok(_) => println!("Syncing disks."),
-                Err(e) => {
-                    eprintln!(
-                        "cannot read partition table from `{}`: {e}",
-                        disk_path.display()
-                    );
-                    exit(1);
+        "q" => {
+            if disk.partition_table != *original {
+                println!("There are unsaved changes:");
+                print_changes(disk, original);
+                if !confirm("Quit without saving?", false).unwrap_or(false) {
+                    println!();
+                    return;
                 }
             }
-
             exit(0);
         }
 
-        "q" => exit(0),
-
-        "g" => {
-            // TODO:
-            // - Remove all partitions
-            // - If the partition table type is not the same:
-            //   - Change partition table type
-            //   - Print `Created a new GPT disklabel (GUID: <>)\n`
-        }
+        "g" => create_label(disk, PartitionTableType::Gpt),
 
-        "o" => {
-            // TODO:
-            // - Remove all partitions
-            // - If the partition table type is not the same:
-            //   - Change partition table type
-            //   - Print `Created a new DOS disklabel (identifier: <>)\n`
-        }
+        "o" => create_label(disk, PartitionTableType::Mbr),
 
         _ => eprintln!("{cmd}: unknown command"),
     }
+    if disk.partition_table != before {
+        history.push(before);
+    }
 
     println!();
 }
 
 pub fn main(script: bool, args: ArgsOs) {
+    let bin = if !script { "fdisk" } else { "sfdisk" };
     let args = parse_args(args);
     if args.help {
         print_help(script);
@@ -271,37 +843,37 @@ pub fn main(script: bool, args: ArgsOs) {
         } else {
             match Disk::list() {
                 Ok(disks) => disks.into_iter(),
-                Err(e) => error("fdisk", format_args!("cannot list disks: {e}")),
+                Err(e) => error(bin, format_args!("cannot list disks: {e}")),
             }
         };
         for path in iter {
-            match Disk::read(path.clone()) {
-                Ok(Some(disk)) => print!("{disk}"),
-                Ok(None) => {
-                    error(
-                        "fdisk",
-                        format_args!("cannot open {}: Invalid argument", path.display()),
-                    );
-                }
-                Err(e) => error("fdisk", format_args!("cannot open {}: {e}", path.display())),
-            }
+            print!("{}", open_disk(bin, &path));
         }
         return;
     }
+    if let (true, Some(op)) = (script, args.operation) {
+        run_operation(op, &args);
+        return;
+    }
     let Some(disk_path) = args.disks.first() else {
         print_usage();
         exit(1);
     };
     if !script {
-        let mut disk = Disk::read(disk_path.clone())
-            .unwrap() // TODO handle error
-            .unwrap(); // TODO handle error
-        while let Some(cmd) = prompt("Command (m for help): ", false) {
-            handle_cmd(&cmd, disk_path, &mut disk);
+        let mut disk = open_disk(bin, disk_path);
+        if disk.partition_table.from_backup
+            && confirm("Restore the primary GPT table from the backup?", true).unwrap_or(false)
+        {
+            disk.partition_table.from_backup = false;
+            write_disk(bin, &mut disk);
+        }
+        let original = disk.partition_table.clone();
+        let mut history = vec![];
+        let mut editor = Editor::new();
+        while let Some(cmd) = editor.read_line("Command (m for help): ") {
+            handle_cmd(&cmd, disk_path, &mut disk, &original, &mut history);
         }
     } else {
-        // TODO Read and parse script
-        // TODO Write partition table accordingly
-        todo!();
+        run_script(&args, disk_path);
     }
 }
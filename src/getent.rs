@@ -0,0 +1,128 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `getent` prints entries from the account databases.
+//!
+//! Entries can be selected by name, or by ID for databases that have one. Without a key, all the
+//! entries of the database are printed.
+
+use std::env::ArgsOs;
+use std::fmt;
+use std::process::exit;
+use utils::error;
+use utils::nss::{Database, NSSWITCH_PATH, Nss};
+
+/// Exit code when a key cannot be found in the database.
+const NOT_FOUND_EXIT: i32 = 2;
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" getent <database> [key...]");
+    println!();
+    println!("Prints entries from the given database.");
+    println!();
+    println!("Supported databases: passwd, group, shadow");
+}
+
+/// Prints the entries of a database.
+///
+/// Arguments:
+/// - `entries` is the list of entries in the database.
+/// - `keys` is the list of keys to look up. If empty, all entries are printed.
+/// - `matches` tells whether an entry matches a key.
+///
+/// The function returns `false` if at least one key could not be found.
+fn print_entries<E, F>(entries: &[E], keys: &[String], matches: F) -> bool
+where
+    E: fmt::Display,
+    F: Fn(&E, &str) -> bool,
+{
+    if keys.is_empty() {
+        for e in entries {
+            print!("{e}");
+        }
+        return true;
+    }
+    let mut found = true;
+    for key in keys {
+        match entries.iter().find(|e| matches(e, key)) {
+            Some(e) => print!("{e}"),
+            None => found = false,
+        }
+    }
+    found
+}
+
+pub fn main(args: ArgsOs) {
+    let mut args = args.map(|arg| {
+        arg.into_string().unwrap_or_else(|arg| {
+            error(
+                "getent",
+                format_args!("invalid argument `{}`", arg.display()),
+            );
+        })
+    });
+    let Some(database) = args.next() else {
+        print_usage();
+        exit(1);
+    };
+    let keys: Vec<_> = args.collect();
+    let db = match database.as_str() {
+        "passwd" => Database::Passwd,
+        "group" => Database::Group,
+        "shadow" => Database::Shadow,
+        "-h" | "--help" => {
+            print_usage();
+            exit(0);
+        }
+        _ => error("getent", format_args!("unknown database: {database}")),
+    };
+    let nss = Nss::load().unwrap_or_else(|e| {
+        error("getent", format_args!("cannot read {NSSWITCH_PATH}: {e}"));
+    });
+    let read_error = |e| -> ! {
+        error("getent", format_args!("cannot read {database}: {e}"));
+    };
+    // Matches either the name or the numeric ID
+    let id_matches = |id: u32, key: &str| key.parse() == Ok(id);
+    let found = match db {
+        Database::Passwd => {
+            let passwd = nss.passwd().unwrap_or_else(|e| read_error(e));
+            let entries: Vec<_> = passwd.iter().collect();
+            print_entries(&entries, &keys, |u, key| {
+                u.login_name == key || id_matches(u.uid, key)
+            })
+        }
+        Database::Group => {
+            let group = nss.group().unwrap_or_else(|e| read_error(e));
+            let entries: Vec<_> = group.iter().collect();
+            print_entries(&entries, &keys, |g, key| {
+                g.group_name == key || id_matches(g.gid, key)
+            })
+        }
+        Database::Shadow => {
+            let shadow = nss.shadow().unwrap_or_else(|e| read_error(e));
+            let entries: Vec<_> = shadow.iter().collect();
+            print_entries(&entries, &keys, |s, key| s.login_name == key)
+        }
+    };
+    if !found {
+        exit(NOT_FOUND_EXIT);
+    }
+}
@@ -0,0 +1,323 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `gpasswd` administers the groups' password, members and administrators, stored in the group
+//! and gshadow files.
+//!
+//! Root can perform any operation. Administrators of a group can change its password and
+//! members.
+
+use std::env::ArgsOs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::exit;
+use std::{fs, io};
+use utils::error;
+use utils::nss::Nss;
+use utils::prompt::prompt;
+use utils::pwquality::Policy;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, get_uid, hash_password, list_add, list_remove,
+};
+
+/// An operation to perform on a group.
+enum Action {
+    /// Set the group's password.
+    SetPassword,
+    /// Remove the group's password.
+    RemovePassword,
+    /// Restrict access to the group to its members.
+    Restrict,
+    /// Add a member to the group.
+    Add(String),
+    /// Remove a member from the group.
+    Delete(String),
+    /// Set the lists of administrators and members of the group.
+    Set {
+        /// The new comma-separated list of administrators.
+        administrators: Option<String>,
+        /// The new comma-separated list of members.
+        members: Option<String>,
+    },
+}
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" gpasswd [option] group");
+    println!();
+    println!("Administers a group.");
+    println!();
+    println!("Options:");
+    println!(" -a, --add <user>\t\tadd the user to the group");
+    println!(" -d, --delete <user>\t\tremove the user from the group");
+    println!(" -r, --remove-password\t\tremove the group's password");
+    println!(" -R, --restrict\t\t\trestrict access to the group to its members");
+    println!(" -A, --administrators <users>\tset the list of administrators");
+    println!(" -M, --members <users>\t\tset the list of members");
+}
+
+/// Returns the value following the option `opt`, exiting if missing.
+fn next_value(args: &mut impl Iterator<Item = String>, opt: &str) -> String {
+    args.next().unwrap_or_else(|| {
+        error("gpasswd", format_args!("option `{opt}` requires a value"));
+    })
+}
+
+/// Parses the given CLI arguments `args` and returns the action along with the group's name.
+fn parse_args(args: ArgsOs) -> (Action, String) {
+    let mut args = args.map(|arg| {
+        arg.into_string().unwrap_or_else(|arg| {
+            error(
+                "gpasswd",
+                format_args!("invalid argument `{}`", arg.display()),
+            )
+        })
+    });
+    let mut action = None;
+    let mut administrators = None;
+    let mut members = None;
+    let mut group = None;
+    while let Some(arg) = args.next() {
+        let new_action = match arg.as_str() {
+            "-h" | "--help" => {
+                print_usage();
+                exit(0);
+            }
+            "-a" | "--add" => Action::Add(next_value(&mut args, &arg)),
+            "-d" | "--delete" => Action::Delete(next_value(&mut args, &arg)),
+            "-r" | "--remove-password" => Action::RemovePassword,
+            "-R" | "--restrict" => Action::Restrict,
+            "-A" | "--administrators" => {
+                administrators = Some(next_value(&mut args, &arg));
+                continue;
+            }
+            "-M" | "--members" => {
+                members = Some(next_value(&mut args, &arg));
+                continue;
+            }
+            a if a.starts_with('-') => error("gpasswd", format_args!("invalid option `{a}`")),
+            _ => {
+                if group.is_some() {
+                    print_usage();
+                    exit(1);
+                }
+                group = Some(arg);
+                continue;
+            }
+        };
+        if action.is_some() {
+            error("gpasswd", "only one operation can be performed at once");
+        }
+        action = Some(new_action);
+    }
+    let Some(group) = group else {
+        print_usage();
+        exit(1);
+    };
+    let set = administrators.is_some() || members.is_some();
+    let action = match (action, set) {
+        (Some(_), true) => error("gpasswd", "only one operation can be performed at once"),
+        (Some(action), false) => action,
+        (None, true) => Action::Set {
+            administrators,
+            members,
+        },
+        (None, false) => Action::SetPassword,
+    };
+    (action, group)
+}
+
+/// Reads the file at the given path, returning an empty string if it does not exist.
+fn read_optional(path: &str) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        res => res,
+    }
+}
+
+/// Prompts for the new password of the group `group` and returns it hashed.
+fn prompt_password(group: &str) -> String {
+    println!("Changing the password for group {group}");
+    let pass = prompt("New Password: ", true).unwrap_or_else(|| exit(1));
+    let policy = Policy::load().unwrap_or_else(|e| {
+        error("gpasswd", format_args!("cannot read password policy: {e}"));
+    });
+    if let Err(e) = policy.check(&pass, None, group, "") {
+        if policy.is_enforced() {
+            error("gpasswd", e);
+        }
+        eprintln!("gpasswd: warning: {e}");
+    }
+    let confirm = prompt("Re-enter new password: ", true).unwrap_or_else(|| exit(1));
+    if pass != confirm {
+        error("gpasswd", "passwords do not match");
+    }
+    hash_password(&pass).unwrap_or_else(|e| {
+        error("gpasswd", format_args!("cannot hash password: {e}"));
+    })
+}
+
+pub fn main(args: ArgsOs) {
+    let (action, group_name) = parse_args(args);
+    let uid = get_uid();
+    // Check users exist
+    let passwd = Nss::load()
+        .and_then(|nss| nss.passwd())
+        .unwrap_or_else(|e| error("gpasswd", format_args!("cannot read passwd database: {e}")));
+    let check_user = |name: &str| {
+        if passwd.by_name(name).is_none() {
+            error("gpasswd", format_args!("user `{name}` does not exist"));
+        }
+    };
+    match &action {
+        Action::Add(user) | Action::Delete(user) => check_user(user),
+        Action::Set {
+            administrators,
+            members,
+        } => {
+            if uid != 0 {
+                error("gpasswd", "permission denied");
+            }
+            administrators
+                .iter()
+                .chain(members)
+                .flat_map(|list| list.split(','))
+                .filter(|name| !name.is_empty())
+                .for_each(check_user);
+        }
+        _ => {}
+    }
+    let login_name = match passwd.by_uid(uid) {
+        Some(user) => user.login_name,
+        None if uid == 0 => "",
+        None => error("gpasswd", "permission denied"),
+    };
+    // Check permissions before prompting. The check is done again once the files are locked
+    if uid != 0 {
+        let gshadow_buff = read_optional(GSHADOW_PATH).unwrap_or_else(|e| {
+            error("gpasswd", format_args!("cannot read gshadow file: {e}"));
+        });
+        let admin = GShadow::deserialize(&gshadow_buff)
+            .filter_map(Result::ok)
+            .find(|g| g.group_name == group_name)
+            .is_some_and(|g| g.is_admin(login_name));
+        if !admin {
+            error("gpasswd", "permission denied");
+        }
+    }
+    let password = matches!(action, Action::SetPassword).then(|| prompt_password(&group_name));
+    // Read groups
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error("gpasswd", format_args!("cannot lock group file: {e}"));
+    });
+    let groups_buff = fs::read_to_string(GROUP_PATH).unwrap_or_else(|e| {
+        error("gpasswd", format_args!("cannot read group file: {e}"));
+    });
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("gpasswd", format_args!("{GROUP_PATH}: {e}")));
+    let gshadow_buff = read_optional(GSHADOW_PATH).unwrap_or_else(|e| {
+        error("gpasswd", format_args!("cannot read gshadow file: {e}"));
+    });
+    let mut gshadows = GShadow::deserialize(&gshadow_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("gpasswd", format_args!("{GSHADOW_PATH}: {e}")));
+    let Some(group) = groups.iter_mut().find(|g| g.group_name == group_name) else {
+        error(
+            "gpasswd",
+            format_args!("group `{group_name}` does not exist"),
+        );
+    };
+    // Create the gshadow entry if missing
+    let gshadow_index = match gshadows.iter().position(|g| g.group_name == group_name) {
+        Some(i) => i,
+        None => {
+            let password = match group.password {
+                "x" => "!",
+                p => p,
+            };
+            gshadows.push(GShadow {
+                group_name: group.group_name,
+                password,
+                administrators: "",
+                members: group.users_list,
+            });
+            gshadows.len() - 1
+        }
+    };
+    let gshadow = &mut gshadows[gshadow_index];
+    // Check permissions
+    if uid != 0 && !gshadow.is_admin(login_name) {
+        error("gpasswd", "permission denied");
+    }
+    // Apply modifications
+    let users_list;
+    let members;
+    match &action {
+        Action::SetPassword => {
+            gshadow.password = password.as_deref().unwrap();
+            group.password = "x";
+        }
+        Action::RemovePassword => {
+            gshadow.password = "";
+            group.password = "x";
+        }
+        Action::Restrict => {
+            gshadow.password = "!";
+            group.password = "x";
+        }
+        Action::Add(user) => {
+            println!("Adding user {user} to group {group_name}");
+            users_list = list_add(group.users_list, user);
+            members = list_add(gshadow.members, user);
+            group.users_list = &users_list;
+            gshadow.members = &members;
+        }
+        Action::Delete(user) => {
+            if !group.is_member(user) && !gshadow.is_member(user) {
+                error(
+                    "gpasswd",
+                    format_args!("user `{user}` is not a member of `{group_name}`"),
+                );
+            }
+            println!("Removing user {user} from group {group_name}");
+            users_list = list_remove(group.users_list, user);
+            members = list_remove(gshadow.members, user);
+            group.users_list = &users_list;
+            gshadow.members = &members;
+        }
+        Action::Set {
+            administrators,
+            members,
+        } => {
+            if let Some(administrators) = administrators {
+                gshadow.administrators = administrators;
+            }
+            if let Some(members) = members {
+                group.users_list = members;
+                gshadow.members = members;
+            }
+        }
+    }
+    // Write
+    lock.write(Path::new(GSHADOW_PATH), gshadows)
+        .unwrap_or_else(|e| error("gpasswd", format_args!("cannot write gshadow file: {e}")));
+    lock.write(Path::new(GROUP_PATH), groups)
+        .unwrap_or_else(|e| error("gpasswd", format_args!("cannot write group file: {e}")));
+}
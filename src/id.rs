@@ -0,0 +1,389 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `id` prints the user and group IDs of a user, `groups` prints the groups a user is a member
+//! of, and `whoami` prints the name of the effective user.
+//!
+//! Without a user argument, the identity of the current process is used.
+
+use std::env::ArgsOs;
+use std::process::exit;
+use utils::error;
+use utils::nss::{Groups, NSSWITCH_PATH, Nss, Passwd};
+use utils::user::{Group, User, get_egid, get_euid, get_gid, get_groups, get_uid};
+
+/// The identity of a user or process.
+struct Identity {
+    /// Real user ID.
+    uid: u32,
+    /// Real group ID.
+    gid: u32,
+    /// Effective user ID.
+    euid: u32,
+    /// Effective group ID.
+    egid: u32,
+    /// The list of groups, starting with the primary group.
+    groups: Vec<u32>,
+}
+
+impl Identity {
+    /// Returns the identity of the current process.
+    fn current(bin: &str) -> Self {
+        let egid = get_egid();
+        let groups = get_groups().unwrap_or_else(|e| {
+            error(bin, format_args!("cannot get groups: {e}"));
+        });
+        Self {
+            uid: get_uid(),
+            gid: get_gid(),
+            euid: get_euid(),
+            egid,
+            groups: dedup_groups(egid, groups),
+        }
+    }
+
+    /// Returns the identity of the given user, according to the group file.
+    fn of_user(user: &User, groups: &[Group]) -> Self {
+        let member_of = groups
+            .iter()
+            .filter(|g| g.is_member(user.login_name))
+            .map(|g| g.gid);
+        Self {
+            uid: user.uid,
+            gid: user.gid,
+            euid: user.uid,
+            egid: user.gid,
+            groups: dedup_groups(user.gid, member_of),
+        }
+    }
+}
+
+/// Returns the list of groups `groups` with `primary` at the beginning and without duplicates.
+fn dedup_groups<I: IntoIterator<Item = u32>>(primary: u32, groups: I) -> Vec<u32> {
+    let mut res = vec![primary];
+    for gid in groups {
+        if !res.contains(&gid) {
+            res.push(gid);
+        }
+    }
+    res
+}
+
+/// The content of the account files required to resolve names.
+struct Database<'s> {
+    users: Vec<User<'s>>,
+    groups: Vec<Group<'s>>,
+}
+
+impl<'s> Database<'s> {
+    /// Collects the entries of the given databases.
+    fn new(passwd: &'s Passwd, group: &'s Groups) -> Self {
+        Self {
+            users: passwd.iter().collect(),
+            groups: group.iter().collect(),
+        }
+    }
+
+    /// Returns the user with the given name or UID.
+    fn find_user(&self, key: &str) -> Option<&User<'s>> {
+        self.users.iter().find(|u| u.login_name == key).or_else(|| {
+            let uid: u32 = key.parse().ok()?;
+            self.users.iter().find(|u| u.uid == uid)
+        })
+    }
+
+    /// Returns the name of the user with the given UID.
+    fn user_name(&self, uid: u32) -> Option<&'s str> {
+        self.users
+            .iter()
+            .find(|u| u.uid == uid)
+            .map(|u| u.login_name)
+    }
+
+    /// Returns the name of the group with the given GID.
+    fn group_name(&self, gid: u32) -> Option<&'s str> {
+        self.groups
+            .iter()
+            .find(|g| g.gid == gid)
+            .map(|g| g.group_name)
+    }
+}
+
+/// Reads the passwd and group databases.
+fn read_databases(bin: &str) -> (Passwd, Groups) {
+    let nss = Nss::load().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read {NSSWITCH_PATH}: {e}"));
+    });
+    let passwd = nss.passwd().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read passwd database: {e}"));
+    });
+    let group = nss.group().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read group database: {e}"));
+    });
+    (passwd, group)
+}
+
+/// Formats an ID followed by its name in parentheses, if any.
+fn format_id(id: u32, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{id}({name})"),
+        None => id.to_string(),
+    }
+}
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    println!("Usage:");
+    match bin {
+        "id" => {
+            println!(" id [-u|-g|-G] [-n] [-r] [user]");
+            println!();
+            println!("Prints user and group IDs.");
+            println!();
+            println!("Options:");
+            println!(" -u, --user\tprint only the user ID");
+            println!(" -g, --group\tprint only the group ID");
+            println!(" -G, --groups\tprint all group IDs");
+            println!(" -n, --name\tprint names instead of IDs");
+            println!(" -r, --real\tprint the real ID instead of the effective ID");
+        }
+        "groups" => {
+            println!(" groups [user...]");
+            println!();
+            println!("Prints the groups a user is a member of.");
+        }
+        _ => {
+            println!(" whoami");
+            println!();
+            println!("Prints the name of the effective user.");
+        }
+    }
+}
+
+/// What the `id` command prints.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum IdMode {
+    /// All information.
+    All,
+    /// The user ID only.
+    User,
+    /// The group ID only.
+    Group,
+    /// All group IDs.
+    Groups,
+}
+
+/// Runs the `id` command.
+fn id(args: ArgsOs) {
+    let mut mode = IdMode::All;
+    let mut name = false;
+    let mut real = false;
+    let mut user = None;
+    // Split grouped short options (such as `-un`) into separate options
+    let args = args.flat_map(|arg| {
+        let arg = arg.into_string().unwrap_or_else(|arg| {
+            error("id", format_args!("invalid argument `{}`", arg.display()));
+        });
+        match arg.strip_prefix('-') {
+            Some(flags) if flags.len() > 1 && !flags.starts_with('-') => {
+                flags.chars().map(|c| format!("-{c}")).collect()
+            }
+            _ => vec![arg],
+        }
+    });
+    for arg in args {
+        let new_mode = match arg.as_str() {
+            "-u" | "--user" => IdMode::User,
+            "-g" | "--group" => IdMode::Group,
+            "-G" | "--groups" => IdMode::Groups,
+            "-n" | "--name" => {
+                name = true;
+                continue;
+            }
+            "-r" | "--real" => {
+                real = true;
+                continue;
+            }
+            "-h" | "--help" => {
+                print_usage("id");
+                exit(0);
+            }
+            _ if !arg.starts_with('-') && user.is_none() => {
+                user = Some(arg);
+                continue;
+            }
+            _ => {
+                print_usage("id");
+                exit(1);
+            }
+        };
+        if mode != IdMode::All && mode != new_mode {
+            error("id", "cannot print more than one choice");
+        }
+        mode = new_mode;
+    }
+    if mode == IdMode::All && (name || real) {
+        error(
+            "id",
+            "cannot print only names or real IDs in default format",
+        );
+    }
+    let (passwd, group) = read_databases("id");
+    let db = Database::new(&passwd, &group);
+    let ident = match &user {
+        Some(key) => {
+            let Some(user) = db.find_user(key) else {
+                error("id", format_args!("{key}: no such user"));
+            };
+            Identity::of_user(user, &db.groups)
+        }
+        None => Identity::current("id"),
+    };
+    let (uid, gid) = if real {
+        (ident.uid, ident.gid)
+    } else {
+        (ident.euid, ident.egid)
+    };
+    // Prints an ID, or its name if requested. Returns `false` if the name cannot be found
+    let print_one = |id: u32, resolved: Option<&str>| match (name, resolved) {
+        (true, Some(n)) => {
+            print!("{n}");
+            true
+        }
+        (true, None) => {
+            print!("{id}");
+            false
+        }
+        (false, _) => {
+            print!("{id}");
+            true
+        }
+    };
+    let ok = match mode {
+        IdMode::User => print_one(uid, db.user_name(uid)),
+        IdMode::Group => print_one(gid, db.group_name(gid)),
+        IdMode::Groups => {
+            let mut ok = true;
+            for (i, gid) in ident.groups.iter().enumerate() {
+                if i > 0 {
+                    print!(" ");
+                }
+                ok &= print_one(*gid, db.group_name(*gid));
+            }
+            ok
+        }
+        IdMode::All => {
+            print!(
+                "uid={} gid={}",
+                format_id(ident.uid, db.user_name(ident.uid)),
+                format_id(ident.gid, db.group_name(ident.gid))
+            );
+            if ident.euid != ident.uid {
+                print!(" euid={}", format_id(ident.euid, db.user_name(ident.euid)));
+            }
+            if ident.egid != ident.gid {
+                print!(" egid={}", format_id(ident.egid, db.group_name(ident.egid)));
+            }
+            let groups: Vec<_> = ident
+                .groups
+                .iter()
+                .map(|gid| format_id(*gid, db.group_name(*gid)))
+                .collect();
+            print!(" groups={}", groups.join(","));
+            true
+        }
+    };
+    println!();
+    if !ok {
+        exit(1);
+    }
+}
+
+/// Runs the `groups` command.
+fn groups(args: ArgsOs) {
+    let users: Vec<_> = args
+        .map(|arg| {
+            arg.into_string().unwrap_or_else(|arg| {
+                error(
+                    "groups",
+                    format_args!("invalid argument `{}`", arg.display()),
+                );
+            })
+        })
+        .collect();
+    if users.iter().any(|u| u == "-h" || u == "--help") {
+        print_usage("groups");
+        exit(0);
+    }
+    let (passwd, group) = read_databases("groups");
+    let db = Database::new(&passwd, &group);
+    let names = |ident: &Identity| {
+        ident
+            .groups
+            .iter()
+            .map(|gid| match db.group_name(*gid) {
+                Some(name) => name.to_owned(),
+                None => gid.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if users.is_empty() {
+        println!("{}", names(&Identity::current("groups")));
+        return;
+    }
+    let mut failed = false;
+    for key in &users {
+        match db.find_user(key) {
+            Some(user) => println!("{key} : {}", names(&Identity::of_user(user, &db.groups))),
+            None => {
+                eprintln!("groups: {key}: no such user");
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
+
+/// Runs the `whoami` command.
+fn whoami(args: ArgsOs) {
+    if let Some(arg) = args.into_iter().next() {
+        print_usage("whoami");
+        exit(if arg == "-h" || arg == "--help" { 0 } else { 1 });
+    }
+    let (passwd, _) = read_databases("whoami");
+    let euid = get_euid();
+    let name = passwd.by_uid(euid).map(|u| u.login_name);
+    let Some(name) = name else {
+        error(
+            "whoami",
+            format_args!("cannot find name for user ID {euid}"),
+        );
+    };
+    println!("{name}");
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    match bin {
+        "id" => id(args),
+        "groups" => groups(args),
+        _ => whoami(args),
+    }
+}
@@ -22,17 +22,43 @@ use std::convert::Infallible;
 use std::env::ArgsOs;
 use std::ffi::{CString, OsString};
 use std::fmt::Formatter;
+use std::io::ErrorKind;
 use std::os::unix::ffi::OsStrExt;
 use std::process::exit;
 use std::ptr::null;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{env, fmt, fs, io, iter};
-use utils::prompt::prompt;
+use utils::auth::{Account, AccountStatus, Backend, NameService};
+use utils::prompt::{prompt, prompt_timeout};
+use utils::pwquality::Policy;
 use utils::user;
-use utils::user::{PASSWD_PATH, SHADOW_PATH, Shadow, User};
 use utils::util;
 use utils::util::get_hostname;
 
+/// The path to the login configuration file.
+const LOGIN_DEFS_PATH: &str = "/etc/login.defs";
+/// The default time allowed to log in, in seconds.
+const DEFAULT_LOGIN_TIMEOUT: u64 = 60;
+
+/// Returns the time allowed to log in, from `LOGIN_TIMEOUT` in [`LOGIN_DEFS_PATH`].
+///
+/// If the returned value is `None`, there is no limit.
+fn login_timeout() -> Option<Duration> {
+    let secs = fs::read_to_string(LOGIN_DEFS_PATH)
+        .ok()
+        .and_then(|buf| {
+            buf.lines().find_map(|line| {
+                let mut words = line.split_whitespace();
+                if words.next()? != "LOGIN_TIMEOUT" {
+                    return None;
+                }
+                words.next()?.parse().ok()
+            })
+        })
+        .unwrap_or(DEFAULT_LOGIN_TIMEOUT);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
 /// Builds an environment variable in the form: name=value
 fn build_env_var(name: &str, value: impl IntoIterator<Item = u8>) -> CString {
     let data: Vec<u8> = name
@@ -51,21 +77,21 @@ fn build_env_var(name: &str, value: impl IntoIterator<Item = u8>) -> CString {
 /// Arguments:
 /// - `logname` is the name of the user used to log in.
 /// - `user` is the user to switch to.
-fn switch_user(logname: &str, user: User) -> io::Result<Infallible> {
-    let User {
+fn switch_user(logname: &str, account: Account) -> io::Result<Infallible> {
+    let Account {
         login_name,
         uid,
         gid,
         home,
         interpreter,
         ..
-    } = user;
+    } = account;
     // Prepare environment
     let term = env::var_os("TERM").unwrap_or_else(|| {
         // TODO fetch from the terminal
         "linux".into()
     });
-    let shell = match interpreter {
+    let shell = match interpreter.as_str() {
         "" => "/bin/sh",
         i => i,
     };
@@ -117,49 +143,93 @@ impl fmt::Display for LoginPrompt {
     }
 }
 
+/// Prompts a new password for `account`, whose password `old` has expired.
+///
+/// On failure, the function exits.
+fn change_expired_password(backend: &dyn Backend, account: &Account, old: &str) {
+    println!("You are required to change your password immediately");
+    let policy = Policy::load().unwrap_or_else(|e| {
+        eprintln!("login: cannot read password policy: {e}");
+        exit(1);
+    });
+    let pass = prompt("New password: ", true).unwrap_or_else(|| exit(1));
+    // `login` runs as root, so enforcement depends on the user logging in
+    let enforced = policy.enforce_for_root || account.uid != 0;
+    if let Err(e) = policy.check(&pass, Some(old), &account.login_name, &account.comment) {
+        if enforced {
+            eprintln!("login: {e}");
+            exit(1);
+        }
+        eprintln!("login: warning: {e}");
+    }
+    let confirm = prompt("Re-enter new password: ", true).unwrap_or_else(|| exit(1));
+    if pass != confirm {
+        eprintln!("login: passwords do not match");
+        exit(1);
+    }
+    backend
+        .change_password(&account.login_name, &pass)
+        .unwrap_or_else(|e| {
+            eprintln!("login: cannot change password: {e}");
+            exit(1);
+        });
+}
+
 pub fn main(_args: ArgsOs) {
+    let backend = NameService::new();
     let login_prompt = LoginPrompt(get_hostname());
+    let timeout = login_timeout();
     loop {
         println!();
-        // Prompt for login and password
-        let login = prompt(&login_prompt, false).unwrap_or_else(|| exit(1));
-        let pass = prompt("Password: ", true).unwrap_or_else(|| exit(1));
-        // Check
-        util::exec_wait(Duration::from_millis(1000), || {
-            // Get user from prompted login
-            let users_buff = fs::read_to_string(PASSWD_PATH).unwrap_or_else(|e| {
-                eprintln!("login: cannot read passwd file: {e}");
-                exit(1);
-            });
-            let user_entry = User::deserialize(&users_buff)
-                .filter_map(Result::ok)
-                .find(|e| e.login_name == login);
-            let Some(user_entry) = user_entry else {
-                return;
-            };
-            // Check password against user entry
-            let correct = match user_entry.check_password(&pass) {
-                Some(c) => c,
-                // The passwd file does not have the password. Fallback onto the shadow file
-                None => {
-                    let shadow_buff = fs::read_to_string(SHADOW_PATH).unwrap_or_else(|e| {
-                        eprintln!("login: cannot read passwd file: {e}");
-                        exit(1);
-                    });
-                    Shadow::deserialize(&shadow_buff)
-                        .filter_map(Result::ok)
-                        .any(|e| e.login_name == login && e.check_password(&pass))
+        // Prompt for login and password, within the allowed time
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let read = |prompt: &dyn fmt::Display, hidden| {
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            match prompt_timeout(prompt, hidden, remaining) {
+                Ok(Some(input)) => input,
+                Ok(None) => exit(1),
+                Err(e) if e.kind() == ErrorKind::TimedOut => {
+                    let secs = timeout.unwrap_or_default().as_secs();
+                    eprintln!("Login timed out after {secs} seconds.");
+                    exit(1);
+                }
+                Err(e) => {
+                    eprintln!("login: cannot read input: {e}");
+                    exit(1);
                 }
-            };
-            if !correct {
-                return;
             }
-            // Correct, setup session
-            switch_user(&login, user_entry).unwrap_or_else(|e| {
-                eprintln!("login: cannot initialize session: {e}");
-                exit(1);
-            });
+        };
+        let login = read(&login_prompt, false);
+        let pass = read(&"Password: ", true);
+        // Check
+        let account = util::exec_wait(Duration::from_millis(1000), || {
+            let account = backend.lookup(&login)?;
+            let correct = backend.authenticate(&login, &pass)?;
+            io::Result::Ok(account.filter(|_| correct))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("login: cannot read account files: {e}");
+            exit(1);
+        });
+        let Some(account) = account else {
+            eprintln!("Login incorrect");
+            continue;
+        };
+        let status = backend.account_ok(&login).unwrap_or_else(|e| {
+            eprintln!("login: cannot read account files: {e}");
+            exit(1);
         });
-        eprintln!("Login incorrect");
+        match status {
+            AccountStatus::Valid => {}
+            AccountStatus::PasswordExpired => change_expired_password(&backend, &account, &pass),
+            AccountStatus::Expired | AccountStatus::Inactive => {
+                eprintln!("Your account has expired; please contact your system administrator");
+                exit(1);
+            }
+        }
+        // Correct, setup session
+        let Err(e) = switch_user(&login, account);
+        eprintln!("login: cannot initialize session: {e}");
+        exit(1);
     }
 }
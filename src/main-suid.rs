@@ -18,7 +18,11 @@
 
 //! Main of all commands that **require** the SUID flag.
 
+mod chsh;
+mod gpasswd;
 mod login;
+mod newgrp;
+mod passwd;
 mod su;
 
 use utils::{args, error};
@@ -26,7 +30,11 @@ use utils::{args, error};
 fn main() {
     let (bin, args) = args();
     match bin.as_str() {
+        bin @ ("chsh" | "chfn") => chsh::main(bin, args),
+        "gpasswd" => gpasswd::main(args),
         "login" => login::main(args),
+        bin @ ("newgrp" | "sg") => newgrp::main(bin, args),
+        "passwd" => passwd::main(args),
         "su" => su::main(args),
         _ => error("mutils", "invalid binary name"),
     }
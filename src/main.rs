@@ -18,17 +18,27 @@
 
 //! Main of all commands that **do not require** the SUID flag.
 
+mod chpasswd;
 mod dmesg;
 mod fdisk;
+mod getent;
+mod id;
 mod insmod;
 mod lsmod;
 mod mkfs;
 mod mount;
+mod newusers;
 mod nologin;
 mod powerctl;
 mod ps;
+mod pwck;
+mod pwconv;
 mod rmmod;
+mod sysusers;
 mod umount;
+mod useradd;
+mod usermod;
+mod vipw;
 
 use utils::{args, error};
 
@@ -53,7 +63,17 @@ fn main() {
             powerctl::main(bin, args)
         }
         "ps" => ps::main(),
-        bin @ ("useradd" | "usermod" | "userdel" | "groupadd" | "groupmod" | "groupdel") => todo!(),
+        bin @ ("pwck" | "grpck") => pwck::main(bin, args),
+        bin @ ("vipw" | "vigr") => vipw::main(bin, args),
+        "chpasswd" => chpasswd::main(args),
+        "newusers" => newusers::main(args),
+        bin @ ("id" | "groups" | "whoami") => id::main(bin, args),
+        "getent" => getent::main(args),
+        bin @ ("pwconv" | "pwunconv" | "grpconv" | "grpunconv") => pwconv::main(bin, args),
+        "sysusers" => sysusers::main(args),
+        "useradd" => useradd::main(args),
+        "usermod" => usermod::main(args),
+        "userdel" | "groupadd" | "groupmod" | "groupdel" => todo!(),
         _ => error("mutils", "invalid binary name"),
     }
 }
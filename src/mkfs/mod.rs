@@ -28,7 +28,7 @@ use std::io;
 use std::path::PathBuf;
 use std::process::exit;
 use utils::error;
-use utils::prompt::prompt;
+use utils::prompt::confirm;
 
 /// Structure storing command line arguments.
 #[derive(Default)]
@@ -104,9 +104,7 @@ pub fn main(fs_name: &str, args: ArgsOs) {
         );
         // TODO print details on fs (use factory)
 
-        let confirm = prompt("Proceed anyway? (y/N) ", false)
-            .map(|s| s.to_lowercase() == "y")
-            .unwrap_or(false);
+        let confirm = confirm("Proceed anyway?", false).unwrap_or(false);
         if !confirm {
             eprintln!("Abort.");
             exit(1);
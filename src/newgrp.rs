@@ -0,0 +1,183 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `newgrp` runs a new shell with a different group ID, and `sg` runs a command with a different
+//! group ID.
+//!
+//! Users that are not members of the group have to enter the group's password.
+
+use std::env::ArgsOs;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, exit};
+use std::time::Duration;
+use std::{env, fs};
+use utils::nss::{NSSWITCH_PATH, Nss};
+use utils::prompt::prompt;
+use utils::user::{GSHADOW_PATH, GShadow, Group, User, get_uid};
+use utils::{error, user, util};
+
+/// The command's arguments.
+#[derive(Default)]
+struct Args {
+    /// If true, the environment is reinitialized as if the user logged in.
+    login: bool,
+    /// The group to switch to. If `None`, the user's primary group is used.
+    group: Option<String>,
+    /// The command to execute (`sg`). If empty, the user's shell is executed.
+    command: Vec<OsString>,
+}
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    println!("Usage:");
+    match bin {
+        "newgrp" => println!(" newgrp [-] [group]"),
+        _ => println!(" sg [-] group [-c] command"),
+    }
+}
+
+/// Parses the given CLI arguments `args` and returns their representation in the [`Args`]
+/// structure.
+fn parse_args(bin: &str, args: ArgsOs) -> Args {
+    let mut res = Args::default();
+    let mut args = args.peekable();
+    if args.next_if(|a| a == "-" || a == "-l").is_some() {
+        res.login = true;
+    }
+    res.group = args.next().map(|group| {
+        group.into_string().unwrap_or_else(|group| {
+            error(bin, format_args!("invalid group `{}`", group.display()));
+        })
+    });
+    if bin == "sg" {
+        args.next_if(|a| a == "-c");
+        res.command = args.collect();
+        if res.group.is_none() || res.command.is_empty() {
+            print_usage(bin);
+            exit(1);
+        }
+    } else if args.next().is_some() {
+        print_usage(bin);
+        exit(1);
+    }
+    res
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    let args = parse_args(bin, args);
+    let uid = get_uid();
+    // Get the current user
+    let nss = Nss::load().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read {NSSWITCH_PATH}: {e}"));
+    });
+    let passwd = nss.passwd().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read passwd database: {e}"));
+    });
+    let Some(user) = passwd.by_uid(uid) else {
+        error(bin, format_args!("cannot find user with UID {uid}"));
+    };
+    // Get the group
+    let gid = match &args.group {
+        Some(group_name) => {
+            let groups = nss.group().unwrap_or_else(|e| {
+                error(bin, format_args!("cannot read group database: {e}"));
+            });
+            let group = groups
+                .iter()
+                .find(|g| g.group_name == group_name || g.gid.to_string() == *group_name);
+            let Some(group) = group else {
+                error(bin, format_args!("group `{group_name}` does not exist"));
+            };
+            let gshadow_buff = match fs::read_to_string(GSHADOW_PATH) {
+                Ok(buff) => buff,
+                Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+                Err(e) => error(bin, format_args!("cannot read gshadow file: {e}")),
+            };
+            let gshadow = GShadow::deserialize(&gshadow_buff)
+                .filter_map(Result::ok)
+                .find(|g| g.group_name == group.group_name);
+            check_access(bin, &user, &group, gshadow.as_ref());
+            group.gid
+        }
+        None => user.gid,
+    };
+    // Switch group, dropping privileges
+    user::set(uid, gid).unwrap_or_else(|e| {
+        error(bin, format_args!("cannot change group: {e}"));
+    });
+    let shell = match user.interpreter {
+        "" => "/bin/sh",
+        i => i,
+    };
+    let mut cmd = Command::new(shell);
+    if args.command.is_empty() {
+        if args.login {
+            // Make the shell a login shell
+            let name = Path::new(shell)
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            cmd.arg0(format!("-{name}"));
+        }
+    } else {
+        cmd.arg("-c")
+            .arg(args.command.join(OsString::from(" ").as_os_str()));
+    }
+    if args.login {
+        env::set_current_dir(user.home).unwrap_or_else(|e| {
+            error(
+                bin,
+                format_args!("cannot change directory to {}: {e}", user.home.display()),
+            );
+        });
+    }
+    let e = cmd.exec();
+    error(bin, format_args!("cannot execute {shell}: {e}"));
+}
+
+/// Checks whether the user `user` can switch to the group `group`, prompting for the group's
+/// password if necessary.
+///
+/// If access is denied, the function exits.
+fn check_access(bin: &str, user: &User, group: &Group, gshadow: Option<&GShadow>) {
+    let member = user.uid == 0
+        || user.gid == group.gid
+        || group.is_member(user.login_name)
+        || gshadow.is_some_and(|g| g.is_member(user.login_name));
+    if member {
+        return;
+    }
+    let password = match gshadow {
+        Some(gshadow) => gshadow.password,
+        None => group.password,
+    };
+    // Groups without a usable password are restricted to their members
+    if password.is_empty() || password == "x" || password.starts_with(['!', '*']) {
+        error(bin, "permission denied");
+    }
+    let pass = prompt("Password: ", true).unwrap_or_else(|| exit(1));
+    let correct = util::exec_wait(Duration::from_millis(1000), || {
+        user::check_password(password, &pass)
+    });
+    if !correct {
+        error(bin, "invalid password");
+    }
+}
@@ -0,0 +1,299 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `newusers` creates users in batch, reading passwd-format lines from the standard input.
+//!
+//! For each line, the user is created along with its group and home directory if they do not
+//! exist. The UID and GID fields may be left empty to allocate them automatically, and the GID
+//! field may also be the name of a group. Passwords are given in clear text.
+//!
+//! If any line is invalid, no change is made.
+
+use std::env::ArgsOs;
+use std::fs::Permissions;
+use std::io::{ErrorKind, Read};
+use std::os::unix;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::exit;
+use std::{fs, io};
+use utils::error;
+use utils::pwquality::Policy;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, ID_RANGE, Lock, PASSWD_PATH, SHADOW_PATH, Shadow,
+    User, allocate_id, current_day, hash_password, is_valid_name,
+};
+
+/// A user to be created, as read from the input.
+struct NewUser<'s> {
+    /// The line number in the input.
+    line: usize,
+    /// The user's login name.
+    name: &'s str,
+    /// The hashed password.
+    hash: String,
+    /// The UID field, which may be empty.
+    uid: &'s str,
+    /// The GID field, which may be empty, a GID, or the name of a group.
+    gid: &'s str,
+    /// User comment.
+    comment: &'s str,
+    /// User's home path.
+    home: &'s str,
+    /// User's command interpreter.
+    interpreter: &'s str,
+}
+
+/// Parses the input line `line` at line number `i`, checking the password against `policy`.
+fn parse_line<'s>(policy: &Policy, i: usize, line: &'s str) -> Result<NewUser<'s>, String> {
+    let fields: Vec<_> = line.split(':').collect();
+    let [name, pass, uid, gid, comment, home, interpreter] = fields[..] else {
+        return Err("invalid number of fields".to_owned());
+    };
+    if name.is_empty() {
+        return Err("missing login name".to_owned());
+    }
+    if !is_valid_name(name) {
+        return Err(format!("invalid user name `{name}`"));
+    }
+    // A GID field that is not a number names a group, which may have to be created
+    if !gid.is_empty() && gid.parse::<u32>().is_err() && !is_valid_name(gid) {
+        return Err(format!("invalid group name `{gid}`"));
+    }
+    if let Err(e) = policy.check(pass, None, name, comment) {
+        if policy.is_enforced() {
+            return Err(e.to_string());
+        }
+        eprintln!("newusers: line {i}: warning: {e}");
+    }
+    let hash = hash_password(pass).map_err(|e| format!("cannot hash password: {e}"))?;
+    Ok(NewUser {
+        line: i,
+        name,
+        hash,
+        uid,
+        gid,
+        comment,
+        home,
+        interpreter,
+    })
+}
+
+/// Reads the file at `path`, returning `None` if it does not exist.
+fn read_optional(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Some(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => error("newusers", format_args!("cannot read {path}: {e}")),
+    }
+}
+
+/// Creates the home directory at `home` for the user with the given UID and GID, if it does not
+/// exist.
+fn create_home(home: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    if home.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(home)?;
+    unix::fs::chown(home, Some(uid), Some(gid))?;
+    fs::set_permissions(home, Permissions::from_mode(0o700))
+}
+
+pub fn main(args: ArgsOs) {
+    if let Some(arg) = args.into_iter().next() {
+        if arg != "-h" && arg != "--help" {
+            error(
+                "newusers",
+                format_args!("invalid argument `{}`", arg.display()),
+            );
+        }
+        println!("Usage:");
+        println!(" newusers");
+        println!();
+        println!("Creates users from passwd-format lines read on the standard input.");
+        exit(0);
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap_or_else(|e| {
+        error("newusers", format_args!("cannot read input: {e}"));
+    });
+    let policy = Policy::load().unwrap_or_else(|e| {
+        error("newusers", format_args!("cannot read password policy: {e}"));
+    });
+    // Parse input and hash passwords before locking, since hashing is slow
+    let mut failed = false;
+    let new_users: Vec<_> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .filter_map(|(i, line)| {
+            parse_line(&policy, i + 1, line)
+                .inspect_err(|e| {
+                    eprintln!("newusers: line {}: {e}", i + 1);
+                    failed = true;
+                })
+                .ok()
+        })
+        .collect();
+    if failed {
+        error("newusers", "errors detected, changes ignored");
+    }
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error("newusers", format_args!("cannot lock account files: {e}"));
+    });
+    let users_buff = read_optional(PASSWD_PATH).unwrap_or_default();
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("newusers", format_args!("{PASSWD_PATH}: {e}")));
+    let shadow_buff = read_optional(SHADOW_PATH);
+    let mut shadows = shadow_buff.as_deref().map(|buff| {
+        Shadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("newusers", format_args!("{SHADOW_PATH}: {e}")))
+    });
+    let groups_buff = read_optional(GROUP_PATH).unwrap_or_default();
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("newusers", format_args!("{GROUP_PATH}: {e}")));
+    let gshadow_buff = read_optional(GSHADOW_PATH);
+    let mut gshadows = gshadow_buff.as_deref().map(|buff| {
+        GShadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("newusers", format_args!("{GSHADOW_PATH}: {e}")))
+    });
+    let today = current_day();
+    let mut homes = vec![];
+    for new in &new_users {
+        let line = new.line;
+        if users.iter().any(|u| u.login_name == new.name) {
+            eprintln!("newusers: line {line}: user '{}' already exists", new.name);
+            failed = true;
+            continue;
+        }
+        // Get UID
+        let uid = if new.uid.is_empty() {
+            allocate_id(ID_RANGE, |id| users.iter().any(|u| u.uid == id))
+        } else {
+            new.uid
+                .parse()
+                .ok()
+                .filter(|id| !users.iter().any(|u| u.uid == *id))
+        };
+        let Some(uid) = uid else {
+            eprintln!("newusers: line {line}: invalid or unavailable UID");
+            failed = true;
+            continue;
+        };
+        // Get GID, creating the group if necessary
+        let gid_num = new.gid.parse::<u32>().ok();
+        let group_name = match (new.gid, gid_num) {
+            ("", _) | (_, Some(_)) => new.name,
+            (name, None) => name,
+        };
+        let existing = groups.iter().find(|g| match gid_num {
+            Some(gid) => g.gid == gid,
+            None => g.group_name == group_name,
+        });
+        let gid = match existing {
+            Some(g) => g.gid,
+            None => {
+                if groups.iter().any(|g| g.group_name == group_name) {
+                    eprintln!("newusers: line {line}: group '{group_name}' already exists");
+                    failed = true;
+                    continue;
+                }
+                // Prefer using the same ID as the user
+                let gid = gid_num.or_else(|| {
+                    let used = |id| groups.iter().any(|g| g.gid == id);
+                    allocate_id(uid..=uid, used).or_else(|| allocate_id(ID_RANGE, used))
+                });
+                let Some(gid) = gid else {
+                    eprintln!("newusers: line {line}: no GID available");
+                    failed = true;
+                    continue;
+                };
+                groups.push(Group {
+                    group_name,
+                    password: "x",
+                    gid,
+                    users_list: "",
+                });
+                if let Some(gshadows) = &mut gshadows {
+                    gshadows.push(GShadow {
+                        group_name,
+                        password: "!",
+                        administrators: "",
+                        members: "",
+                    });
+                }
+                gid
+            }
+        };
+        users.push(User {
+            login_name: new.name,
+            password: if shadows.is_some() { "x" } else { &new.hash },
+            uid,
+            gid,
+            comment: new.comment,
+            home: Path::new(new.home),
+            interpreter: new.interpreter,
+        });
+        if let Some(shadows) = &mut shadows {
+            shadows.push(Shadow {
+                login_name: new.name,
+                password: &new.hash,
+                last_change: Some(today),
+                minimum_age: None,
+                maximum_age: None,
+                warning_period: None,
+                inactivity_period: None,
+                account_expiration: None,
+                reserved: "",
+            });
+        }
+        if !new.home.is_empty() {
+            homes.push((Path::new(new.home), uid, gid));
+        }
+    }
+    if failed {
+        error("newusers", "errors detected, changes ignored");
+    }
+    // Write files
+    if let Some(gshadows) = gshadows {
+        lock.write(Path::new(GSHADOW_PATH), gshadows)
+            .unwrap_or_else(|e| error("newusers", format_args!("cannot write gshadow file: {e}")));
+    }
+    lock.write(Path::new(GROUP_PATH), groups)
+        .unwrap_or_else(|e| error("newusers", format_args!("cannot write group file: {e}")));
+    if let Some(shadows) = shadows {
+        lock.write(Path::new(SHADOW_PATH), shadows)
+            .unwrap_or_else(|e| error("newusers", format_args!("cannot write shadow file: {e}")));
+    }
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error("newusers", format_args!("cannot write passwd file: {e}")));
+    // Create home directories
+    for (home, uid, gid) in homes {
+        if let Err(e) = create_home(home, uid, gid) {
+            eprintln!("newusers: cannot create home {}: {e}", home.display());
+            failed = true;
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `passwd` changes the password of a user.
+//!
+//! Unless run by root, the command only allows a user to change their own password, after
+//! authenticating with the current one. The new password is checked against the password
+//! quality policy.
+
+use std::env::ArgsOs;
+use std::process::exit;
+use std::time::Duration;
+use utils::auth::{Backend, NameService};
+use utils::nss::Nss;
+use utils::prompt::prompt;
+use utils::pwquality::Policy;
+use utils::user::get_uid;
+use utils::{error, util};
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" passwd [user]");
+    println!();
+    println!("Changes the password of a user.");
+}
+
+pub fn main(args: ArgsOs) {
+    let args: Vec<_> = args.collect();
+    let name = match args.as_slice() {
+        [] => None,
+        [arg] if arg == "-h" || arg == "--help" => {
+            print_usage();
+            exit(0);
+        }
+        [arg] => Some(arg.to_str().unwrap_or_else(|| {
+            error("passwd", format_args!("invalid user `{}`", arg.display()));
+        })),
+        _ => {
+            print_usage();
+            exit(1);
+        }
+    };
+    let uid = get_uid();
+    let backend = NameService::new();
+    let policy = Policy::load().unwrap_or_else(|e| {
+        error("passwd", format_args!("cannot read password policy: {e}"));
+    });
+    // Get the user
+    let passwd = Nss::load()
+        .and_then(|nss| nss.passwd())
+        .unwrap_or_else(|e| error("passwd", format_args!("cannot read passwd database: {e}")));
+    let user = match name {
+        Some(name) => passwd.by_name(name),
+        None => passwd.by_uid(uid),
+    };
+    let Some(user) = user else {
+        match name {
+            Some(name) => error("passwd", format_args!("user `{name}` does not exist")),
+            None => error("passwd", format_args!("cannot find user with UID {uid}")),
+        }
+    };
+    println!("Changing password for {}", user.login_name);
+    // Check permissions
+    let old = if uid != 0 {
+        if user.uid != uid {
+            error("passwd", "permission denied");
+        }
+        let pass = prompt("Current password: ", true).unwrap_or_else(|| exit(1));
+        let correct = util::exec_wait(Duration::from_millis(1000), || {
+            backend.authenticate(user.login_name, &pass)
+        })
+        .unwrap_or_else(|e| error("passwd", format_args!("cannot read account files: {e}")));
+        if !correct {
+            error("passwd", "Authentication failure");
+        }
+        Some(pass)
+    } else {
+        None
+    };
+    // Get the new password
+    let pass = prompt("New password: ", true).unwrap_or_else(|| exit(1));
+    if let Err(e) = policy.check(&pass, old.as_deref(), user.login_name, user.comment) {
+        if policy.is_enforced() {
+            error("passwd", e);
+        }
+        eprintln!("passwd: warning: {e}");
+    }
+    let confirm = prompt("Re-enter new password: ", true).unwrap_or_else(|| exit(1));
+    if pass != confirm {
+        error("passwd", "passwords do not match");
+    }
+    backend
+        .change_password(user.login_name, &pass)
+        .unwrap_or_else(|e| error("passwd", format_args!("cannot change password: {e}")));
+    println!("Password changed");
+}
@@ -26,8 +26,9 @@ use format::parse_display_format;
 use process::Process;
 use process::ProcessIterator;
 use std::process::exit;
-use std::{env, fs, io};
-use utils::user::{Group, PASSWD_PATH, User, get_egid, get_euid};
+use std::{env, io};
+use utils::nss::Nss;
+use utils::user::{get_egid, get_euid};
 
 // TODO Implement every arguments
 // TODO Implement environment variables
@@ -103,14 +104,11 @@ fn error(msg: &str) -> ! {
 /// Parses arguments and returns the selectors list and format.
 fn parse_args() -> io::Result<(Vec<Selector>, DisplayFormat)> {
     // Read users and groups lists
-    let users_buff = fs::read_to_string(PASSWD_PATH)?;
-    let users: Vec<_> = User::deserialize(&users_buff)
-        .filter_map(Result::ok)
-        .collect();
-    let groups_buff = fs::read_to_string(PASSWD_PATH)?;
-    let groups: Vec<_> = Group::deserialize(&groups_buff)
-        .filter_map(Result::ok)
-        .collect();
+    let nss = Nss::load()?;
+    let passwd = nss.passwd()?;
+    let users: Vec<_> = passwd.iter().collect();
+    let group = nss.group()?;
+    let groups: Vec<_> = group.iter().collect();
     // Results
     let mut selectors = Vec::new();
     let mut format: Option<DisplayFormat> = None;
@@ -0,0 +1,494 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `pwck` checks the consistency of the passwd and shadow files, and `grpck` checks the
+//! consistency of the group and gshadow files.
+//!
+//! Each problem is reported. Unless run in read-only mode, the commands ask whether fixable
+//! problems should be fixed.
+
+use std::collections::{HashMap, HashSet};
+use std::env::ArgsOs;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use utils::error;
+use utils::prompt::confirm;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, PASSWD_PATH, SHADOW_PATH, Shadow, User,
+    current_day, read_shells,
+};
+
+/// The command's arguments.
+struct Args {
+    /// If true, the files are not modified.
+    read_only: bool,
+    /// The path to the passwd or group file.
+    path: PathBuf,
+    /// The path to the shadow or gshadow file.
+    shadow_path: PathBuf,
+    /// The path to the file entries are checked against: the group file for primary groups, or
+    /// the passwd file for group members.
+    other_path: PathBuf,
+}
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    let files = match bin {
+        "pwck" => "[passwd [shadow [group]]]",
+        _ => "[group [gshadow [passwd]]]",
+    };
+    println!("Usage:");
+    println!(" {bin} [-r] {files}");
+    println!();
+    println!("Checks the consistency of account files.");
+    println!();
+    println!("Options:");
+    println!(" -r, --read-only\tonly report problems, without fixing them");
+}
+
+/// Parses the given CLI arguments `args` and returns their representation in the [`Args`]
+/// structure.
+fn parse_args(bin: &str, args: ArgsOs) -> Args {
+    let mut read_only = false;
+    let mut paths = vec![];
+    for arg in args {
+        match arg.to_str() {
+            Some("-h" | "--help") => {
+                print_usage(bin);
+                exit(0);
+            }
+            Some("-r" | "--read-only") => read_only = true,
+            Some(a) if a.starts_with('-') => error(bin, format_args!("invalid option `{a}`")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.len() > 3 {
+        print_usage(bin);
+        exit(1);
+    }
+    let mut paths = paths.into_iter();
+    let (default_path, default_shadow_path, default_other_path) = match bin {
+        "pwck" => (PASSWD_PATH, SHADOW_PATH, GROUP_PATH),
+        _ => (GROUP_PATH, GSHADOW_PATH, PASSWD_PATH),
+    };
+    let path = paths.next();
+    // By default, the other file is next to a custom file
+    let default_other_path = match &path {
+        Some(path) => {
+            let name = Path::new(default_other_path)
+                .file_name()
+                .unwrap_or_default();
+            path.with_file_name(name)
+        }
+        None => default_other_path.into(),
+    };
+    Args {
+        read_only,
+        path: path.unwrap_or_else(|| default_path.into()),
+        shadow_path: paths.next().unwrap_or_else(|| default_shadow_path.into()),
+        other_path: paths.next().unwrap_or(default_other_path),
+    }
+}
+
+/// An account file, loaded as a list of lines.
+struct AccountFile {
+    /// The path to the file.
+    path: PathBuf,
+    /// The lines of the file.
+    lines: Vec<String>,
+    /// Tells whether the file has been modified.
+    modified: bool,
+}
+
+impl AccountFile {
+    /// Reads the file at `path`.
+    ///
+    /// If `optional` is true and the file does not exist, the function returns `None`.
+    fn read(bin: &str, path: &Path, optional: bool) -> Option<Self> {
+        let buff = match fs::read_to_string(path) {
+            Ok(buff) => buff,
+            Err(e) if optional && e.kind() == ErrorKind::NotFound => return None,
+            Err(e) => error(bin, format_args!("cannot read {}: {e}", path.display())),
+        };
+        Some(Self {
+            path: path.to_owned(),
+            lines: buff.lines().map(str::to_owned).collect(),
+            modified: false,
+        })
+    }
+
+    /// Writes the file back if it has been modified.
+    fn write(&self, bin: &str, lock: &Lock) {
+        if !self.modified {
+            return;
+        }
+        let content = self.lines.iter().map(|l| format!("{l}\n"));
+        lock.write(&self.path, content).unwrap_or_else(|e| {
+            error(
+                bin,
+                format_args!("cannot write {}: {e}", self.path.display()),
+            );
+        });
+    }
+}
+
+/// Counts problems and asks whether to fix them.
+struct Checker {
+    /// If true, problems are never fixed.
+    read_only: bool,
+    /// The number of problems found.
+    problems: usize,
+}
+
+impl Checker {
+    /// Reports the problem described by `msg`, which cannot be fixed automatically.
+    fn report<M: fmt::Display>(&mut self, msg: M) {
+        println!("{msg}");
+        self.problems += 1;
+    }
+
+    /// Reports the problem described by `msg`, then asks whether the fix described by `fix`
+    /// shall be applied.
+    ///
+    /// The function returns `true` if the fix shall be applied.
+    fn fix<M: fmt::Display, F: fmt::Display>(&mut self, msg: M, fix: F) -> bool {
+        self.report(msg);
+        if self.read_only {
+            return false;
+        }
+        confirm(format_args!("{fix}?"), false).unwrap_or(false)
+    }
+}
+
+/// Checks for malformed lines and duplicate names in `file`.
+///
+/// Arguments:
+/// - `desc` is the description of the file used in messages.
+/// - `name` parses a line and returns the name of the entry. If the line is invalid, it returns
+///   `None`.
+fn check_lines<F: Fn(&str) -> Option<String>>(
+    checker: &mut Checker,
+    file: &mut AccountFile,
+    desc: &str,
+    name: F,
+) {
+    let mut names = HashSet::new();
+    let mut modified = false;
+    file.lines.retain(|line| {
+        let remove = match name(line) {
+            None => checker.fix(format_args!("invalid {desc} entry '{line}'"), "delete line"),
+            Some(name) => {
+                !names.insert(name)
+                    && checker.fix(
+                        format_args!("duplicate {desc} entry '{line}'"),
+                        "delete line",
+                    )
+            }
+        };
+        modified |= remove;
+        !remove
+    });
+    file.modified |= modified;
+}
+
+/// Tells whether `shell` is a valid login shell.
+fn is_valid_shell(shells: &[String], shell: &str) -> bool {
+    shell.is_empty()
+        || shells.iter().any(|s| s == shell)
+        || fs::metadata(shell).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Checks the passwd and shadow files.
+fn check_passwd(bin: &str, checker: &mut Checker, args: &Args, lock: Option<&Lock>) {
+    let mut passwd = AccountFile::read(bin, &args.path, false).unwrap();
+    let mut shadow = AccountFile::read(bin, &args.shadow_path, true);
+    check_lines(checker, &mut passwd, "password file", |l| {
+        Some(User::deserialize(l).next()?.ok()?.login_name.to_owned())
+    });
+    let users: Vec<_> = passwd
+        .lines
+        .iter()
+        .filter_map(|l| User::deserialize(l).next()?.ok())
+        .collect();
+    if let Some(shadow) = &mut shadow {
+        check_lines(checker, shadow, "shadow file", |l| {
+            Some(Shadow::deserialize(l).next()?.ok()?.login_name.to_owned())
+        });
+        // Shadow entries without passwd entries
+        let mut modified = false;
+        shadow.lines.retain(|line| {
+            let Some(ent) = Shadow::deserialize(line).next().and_then(Result::ok) else {
+                return true;
+            };
+            if users.iter().any(|u| u.login_name == ent.login_name) {
+                return true;
+            }
+            let remove = checker.fix(
+                format_args!("no matching password file entry for '{}'", ent.login_name),
+                "delete line",
+            );
+            modified |= remove;
+            !remove
+        });
+        shadow.modified |= modified;
+        // Passwd entries without shadow entries
+        let names: HashSet<_> = shadow
+            .lines
+            .iter()
+            .filter_map(|l| Shadow::deserialize(l).next()?.ok())
+            .map(|s| s.login_name.to_owned())
+            .collect();
+        for user in &users {
+            if user.password != "x" || names.contains(user.login_name) {
+                continue;
+            }
+            let fix = checker.fix(
+                format_args!("no matching shadow file entry for '{}'", user.login_name),
+                "add entry",
+            );
+            if fix {
+                let ent = Shadow {
+                    login_name: user.login_name,
+                    password: "!",
+                    last_change: Some(current_day()),
+                    minimum_age: None,
+                    maximum_age: None,
+                    warning_period: None,
+                    inactivity_period: None,
+                    account_expiration: None,
+                    reserved: "",
+                };
+                shadow.lines.push(ent.to_string().trim_end().to_owned());
+                shadow.modified = true;
+            }
+        }
+    }
+    // Checks on the content of entries
+    let groups_buff = fs::read_to_string(&args.other_path).unwrap_or_default();
+    let gids: HashSet<_> = Group::deserialize(&groups_buff)
+        .filter_map(Result::ok)
+        .map(|g| g.gid)
+        .collect();
+    let shells = read_shells().unwrap_or_default();
+    let mut uids = HashMap::new();
+    for user in &users {
+        let name = user.login_name;
+        if let Some(other) = uids.insert(user.uid, name) {
+            checker.report(format_args!(
+                "users '{other}' and '{name}' have the same UID {}",
+                user.uid
+            ));
+        }
+        if !gids.contains(&user.gid) {
+            checker.report(format_args!("user '{name}': no group {}", user.gid));
+        }
+        if !user.home.is_dir() {
+            checker.report(format_args!(
+                "user '{name}': directory '{}' does not exist",
+                user.home.display()
+            ));
+        }
+        if !is_valid_shell(&shells, user.interpreter) {
+            checker.report(format_args!(
+                "user '{name}': program '{}' is not a valid shell",
+                user.interpreter
+            ));
+        }
+    }
+    if let Some(lock) = lock {
+        passwd.write(bin, lock);
+        if let Some(shadow) = &shadow {
+            shadow.write(bin, lock);
+        }
+    }
+}
+
+/// Returns the comma-separated list `list` without the names that are not in `users`.
+///
+/// `desc` describes the list in messages.
+fn check_members(
+    checker: &mut Checker,
+    users: &HashSet<String>,
+    group: &str,
+    desc: &str,
+    list: &str,
+) -> String {
+    list.split(',')
+        .filter(|name| {
+            if name.is_empty() || users.contains(*name) {
+                return true;
+            }
+            let remove = checker.fix(
+                format_args!("group '{group}': no user '{name}'"),
+                format_args!("delete {desc} '{name}'"),
+            );
+            !remove
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Checks the group and gshadow files.
+fn check_group(bin: &str, checker: &mut Checker, args: &Args, lock: Option<&Lock>) {
+    let mut group = AccountFile::read(bin, &args.path, false).unwrap();
+    let mut gshadow = AccountFile::read(bin, &args.shadow_path, true);
+    let users_buff = fs::read_to_string(&args.other_path).unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read passwd file: {e}"));
+    });
+    let users: HashSet<_> = User::deserialize(&users_buff)
+        .filter_map(Result::ok)
+        .map(|u| u.login_name.to_owned())
+        .collect();
+    check_lines(checker, &mut group, "group file", |l| {
+        Some(Group::deserialize(l).next()?.ok()?.group_name.to_owned())
+    });
+    // Members
+    for line in &mut group.lines {
+        let Some(ent) = Group::deserialize(line).next().and_then(Result::ok) else {
+            continue;
+        };
+        let users_list = check_members(checker, &users, ent.group_name, "member", ent.users_list);
+        if users_list != ent.users_list {
+            let ent = Group {
+                users_list: &users_list,
+                ..ent
+            };
+            *line = ent.to_string().trim_end().to_owned();
+            group.modified = true;
+        }
+    }
+    let groups: Vec<_> = group
+        .lines
+        .iter()
+        .filter_map(|l| Group::deserialize(l).next()?.ok())
+        .collect();
+    let mut gids = HashMap::new();
+    for ent in &groups {
+        if let Some(other) = gids.insert(ent.gid, ent.group_name) {
+            checker.report(format_args!(
+                "groups '{other}' and '{}' have the same GID {}",
+                ent.group_name, ent.gid
+            ));
+        }
+    }
+    if let Some(gshadow) = &mut gshadow {
+        check_lines(checker, gshadow, "gshadow file", |l| {
+            Some(GShadow::deserialize(l).next()?.ok()?.group_name.to_owned())
+        });
+        // Gshadow entries without group entries
+        let mut modified = false;
+        gshadow.lines.retain(|line| {
+            let Some(ent) = GShadow::deserialize(line).next().and_then(Result::ok) else {
+                return true;
+            };
+            if groups.iter().any(|g| g.group_name == ent.group_name) {
+                return true;
+            }
+            let remove = checker.fix(
+                format_args!("no matching group file entry for '{}'", ent.group_name),
+                "delete line",
+            );
+            modified |= remove;
+            !remove
+        });
+        gshadow.modified |= modified;
+        // Administrators and members
+        for line in &mut gshadow.lines {
+            let Some(ent) = GShadow::deserialize(line).next().and_then(Result::ok) else {
+                continue;
+            };
+            let administrators = check_members(
+                checker,
+                &users,
+                ent.group_name,
+                "administrator",
+                ent.administrators,
+            );
+            let members = check_members(checker, &users, ent.group_name, "member", ent.members);
+            if administrators != ent.administrators || members != ent.members {
+                let ent = GShadow {
+                    administrators: &administrators,
+                    members: &members,
+                    ..ent
+                };
+                *line = ent.to_string().trim_end().to_owned();
+                gshadow.modified = true;
+            }
+        }
+        // Group entries without gshadow entries
+        let names: HashSet<_> = gshadow
+            .lines
+            .iter()
+            .filter_map(|l| GShadow::deserialize(l).next()?.ok())
+            .map(|g| g.group_name.to_owned())
+            .collect();
+        for ent in &groups {
+            if names.contains(ent.group_name) {
+                continue;
+            }
+            let fix = checker.fix(
+                format_args!("no matching gshadow file entry for '{}'", ent.group_name),
+                "add entry",
+            );
+            if fix {
+                let password = match ent.password {
+                    "x" => "!",
+                    p => p,
+                };
+                let ent = GShadow {
+                    group_name: ent.group_name,
+                    password,
+                    administrators: "",
+                    members: ent.users_list,
+                };
+                gshadow.lines.push(ent.to_string().trim_end().to_owned());
+                gshadow.modified = true;
+            }
+        }
+    }
+    if let Some(lock) = lock {
+        group.write(bin, lock);
+        if let Some(gshadow) = &gshadow {
+            gshadow.write(bin, lock);
+        }
+    }
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    let args = parse_args(bin, args);
+    let lock = (!args.read_only).then(|| {
+        Lock::acquire().unwrap_or_else(|e| {
+            error(bin, format_args!("cannot lock account files: {e}"));
+        })
+    });
+    let mut checker = Checker {
+        read_only: args.read_only,
+        problems: 0,
+    };
+    match bin {
+        "pwck" => check_passwd(bin, &mut checker, &args, lock.as_ref()),
+        _ => check_group(bin, &mut checker, &args, lock.as_ref()),
+    }
+    if checker.problems > 0 {
+        exit(2);
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `pwconv` and `grpconv` move password hashes from respectively the passwd and group files to
+//! their shadow counterparts. `pwunconv` and `grpunconv` do the reverse, removing the shadow
+//! files.
+//!
+//! All conversions are idempotent: running them on already converted files changes nothing.
+
+use std::env::ArgsOs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::exit;
+use std::{fs, io};
+use utils::error;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, PASSWD_PATH, SHADOW_PATH, Shadow, User,
+    current_day,
+};
+
+/// The password field value telling the password is in the shadow file.
+const SHADOWED: &str = "x";
+/// The password of created shadow entries for which no password is known.
+const LOCKED: &str = "!";
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    let desc = match bin {
+        "pwconv" => "Moves password hashes from the passwd file to the shadow file.",
+        "pwunconv" => "Moves password hashes from the shadow file back to the passwd file.",
+        "grpconv" => "Moves password hashes from the group file to the gshadow file.",
+        _ => "Moves password hashes from the gshadow file back to the group file.",
+    };
+    println!("Usage:");
+    println!(" {bin}");
+    println!();
+    println!("{desc}");
+}
+
+/// Reads the file at `path`, returning `None` if it does not exist.
+fn read_optional(bin: &str, path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Some(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => error(bin, format_args!("cannot read {path}: {e}")),
+    }
+}
+
+/// Removes the file at `path`, ignoring it if it does not exist.
+fn remove_optional(path: &str) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Moves password hashes from the passwd file to the shadow file.
+fn pwconv(lock: &Lock) {
+    let users_buff = read_optional("pwconv", PASSWD_PATH).unwrap_or_default();
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("pwconv", format_args!("{PASSWD_PATH}: {e}")));
+    let shadow_buff = read_optional("pwconv", SHADOW_PATH);
+    let mut shadows = Shadow::deserialize(shadow_buff.as_deref().unwrap_or_default())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("pwconv", format_args!("{SHADOW_PATH}: {e}")));
+    let today = current_day();
+    // Remove shadow entries of users that do not exist anymore
+    let len = shadows.len();
+    shadows.retain(|s| users.iter().any(|u| u.login_name == s.login_name));
+    let mut changed = shadow_buff.is_none() || shadows.len() != len;
+    for user in &mut users {
+        let shadow = shadows.iter_mut().find(|s| s.login_name == user.login_name);
+        match shadow {
+            Some(shadow) if user.password != SHADOWED => {
+                shadow.password = user.password;
+                shadow.last_change = Some(today);
+            }
+            Some(_) => continue,
+            None => shadows.push(Shadow {
+                login_name: user.login_name,
+                password: match user.password {
+                    SHADOWED => LOCKED,
+                    pass => pass,
+                },
+                last_change: Some(today),
+                minimum_age: None,
+                maximum_age: None,
+                warning_period: None,
+                inactivity_period: None,
+                account_expiration: None,
+                reserved: "",
+            }),
+        }
+        user.password = SHADOWED;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+    lock.write(Path::new(SHADOW_PATH), shadows)
+        .unwrap_or_else(|e| error("pwconv", format_args!("cannot write shadow file: {e}")));
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error("pwconv", format_args!("cannot write passwd file: {e}")));
+}
+
+/// Moves password hashes from the shadow file back to the passwd file.
+fn pwunconv(lock: &Lock) {
+    let Some(shadow_buff) = read_optional("pwunconv", SHADOW_PATH) else {
+        return;
+    };
+    let shadows = Shadow::deserialize(&shadow_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("pwunconv", format_args!("{SHADOW_PATH}: {e}")));
+    let users_buff = read_optional("pwunconv", PASSWD_PATH).unwrap_or_default();
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("pwunconv", format_args!("{PASSWD_PATH}: {e}")));
+    for user in users.iter_mut().filter(|u| u.password == SHADOWED) {
+        if let Some(shadow) = shadows.iter().find(|s| s.login_name == user.login_name) {
+            user.password = shadow.password;
+        }
+    }
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error("pwunconv", format_args!("cannot write passwd file: {e}")));
+    remove_optional(SHADOW_PATH).unwrap_or_else(|e| {
+        error("pwunconv", format_args!("cannot remove shadow file: {e}"));
+    });
+}
+
+/// Moves password hashes from the group file to the gshadow file.
+fn grpconv(lock: &Lock) {
+    let groups_buff = read_optional("grpconv", GROUP_PATH).unwrap_or_default();
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("grpconv", format_args!("{GROUP_PATH}: {e}")));
+    let gshadow_buff = read_optional("grpconv", GSHADOW_PATH);
+    let mut gshadows = GShadow::deserialize(gshadow_buff.as_deref().unwrap_or_default())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("grpconv", format_args!("{GSHADOW_PATH}: {e}")));
+    // Remove gshadow entries of groups that do not exist anymore
+    let len = gshadows.len();
+    gshadows.retain(|s| groups.iter().any(|g| g.group_name == s.group_name));
+    let mut changed = gshadow_buff.is_none() || gshadows.len() != len;
+    for group in &mut groups {
+        let gshadow = gshadows
+            .iter_mut()
+            .find(|s| s.group_name == group.group_name);
+        match gshadow {
+            Some(gshadow) if group.password != SHADOWED => gshadow.password = group.password,
+            Some(_) => continue,
+            None => gshadows.push(GShadow {
+                group_name: group.group_name,
+                password: match group.password {
+                    SHADOWED => LOCKED,
+                    pass => pass,
+                },
+                administrators: "",
+                members: group.users_list,
+            }),
+        }
+        group.password = SHADOWED;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+    lock.write(Path::new(GSHADOW_PATH), gshadows)
+        .unwrap_or_else(|e| error("grpconv", format_args!("cannot write gshadow file: {e}")));
+    lock.write(Path::new(GROUP_PATH), groups)
+        .unwrap_or_else(|e| error("grpconv", format_args!("cannot write group file: {e}")));
+}
+
+/// Moves password hashes from the gshadow file back to the group file.
+fn grpunconv(lock: &Lock) {
+    let Some(gshadow_buff) = read_optional("grpunconv", GSHADOW_PATH) else {
+        return;
+    };
+    let gshadows = GShadow::deserialize(&gshadow_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("grpunconv", format_args!("{GSHADOW_PATH}: {e}")));
+    let groups_buff = read_optional("grpunconv", GROUP_PATH).unwrap_or_default();
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("grpunconv", format_args!("{GROUP_PATH}: {e}")));
+    for group in groups.iter_mut().filter(|g| g.password == SHADOWED) {
+        if let Some(gshadow) = gshadows.iter().find(|s| s.group_name == group.group_name) {
+            group.password = gshadow.password;
+        }
+    }
+    lock.write(Path::new(GROUP_PATH), groups)
+        .unwrap_or_else(|e| error("grpunconv", format_args!("cannot write group file: {e}")));
+    remove_optional(GSHADOW_PATH).unwrap_or_else(|e| {
+        error("grpunconv", format_args!("cannot remove gshadow file: {e}"));
+    });
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    if let Some(arg) = args.into_iter().next() {
+        print_usage(bin);
+        exit(if arg == "-h" || arg == "--help" { 0 } else { 1 });
+    }
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot lock account files: {e}"));
+    });
+    match bin {
+        "pwconv" => pwconv(&lock),
+        "pwunconv" => pwunconv(&lock),
+        "grpconv" => grpconv(&lock),
+        _ => grpunconv(&lock),
+    }
+}
@@ -23,9 +23,12 @@ use std::ffi::{OsStr, OsString};
 use std::os::unix::ffi::OsStrExt;
 use std::process::Command;
 use std::process::exit;
-use utils::error;
-
+use std::time::Duration;
+use utils::auth::{AccountStatus, Backend, NameService};
+use utils::nss::Nss;
 use utils::prompt::prompt;
+use utils::user::{get_uid, read_shells};
+use utils::{error, user, util};
 
 /// The command's arguments.
 #[derive(Default)]
@@ -66,32 +69,82 @@ fn parse_args(args: ArgsOs) -> Args {
 pub fn main(args: ArgsOs) {
     let args = parse_args(args);
 
-    let _user = args.user.as_deref().unwrap_or(OsStr::new("root"));
-    // TODO Read user's entry
-    let shell = args.shell.as_deref().unwrap_or(OsStr::new("TODO"));
-
-    let _pass = prompt("Password: ", true);
-    let correct = false; // TODO Check password against user's
+    let name = args.user.as_deref().unwrap_or(OsStr::new("root"));
+    let name = name.to_str().unwrap_or_else(|| {
+        error("su", format_args!("invalid user `{}`", name.display()));
+    });
+    let backend = NameService::new();
+    let account = backend
+        .lookup(name)
+        .unwrap_or_else(|e| error("su", format_args!("cannot read account files: {e}")));
+    let Some(account) = account else {
+        error("su", format_args!("user `{name}` does not exist"));
+    };
 
-    if correct {
-        // TODO Change user
-        // TODO use `execve` instead
-        // Run the shell
-        let status = Command::new(shell)
-            .args(args.args)
-            // TODO Set env
-            .status()
-            .unwrap_or_else(|e| {
-                error(
-                    "su",
-                    format_args!("Failed to run shell `{}`: {e}", shell.display()),
-                );
-            });
+    // Root does not need to authenticate
+    let uid = get_uid();
+    if uid != 0 {
+        let pass = prompt("Password: ", true).unwrap_or_else(|| exit(1));
+        let correct = util::exec_wait(Duration::from_millis(1000), || {
+            backend.authenticate(name, &pass)
+        })
+        .unwrap_or_else(|e| error("su", format_args!("cannot read account files: {e}")));
+        if !correct {
+            eprintln!("su: Authentication failure");
+            exit(1);
+        }
+    }
+    let status = backend
+        .account_ok(name)
+        .unwrap_or_else(|e| error("su", format_args!("cannot read account files: {e}")));
+    match status {
+        AccountStatus::Valid => {}
+        AccountStatus::PasswordExpired => error("su", "password expired, change it with `passwd`"),
+        AccountStatus::Expired | AccountStatus::Inactive => error("su", "account expired"),
+    }
 
-        // Exit with the shell's status
-        exit(status.code().unwrap());
-    } else {
-        eprintln!("su: Authentication failure");
-        exit(1);
+    // Other users can only use a shell listed in the shells file
+    if uid != 0
+        && let Some(shell) = &args.shell
+    {
+        let shells = read_shells().unwrap_or_default();
+        if !shells.iter().any(|s| OsStr::new(s) == shell) {
+            error(
+                "su",
+                format_args!("shell `{}` is not allowed", shell.display()),
+            );
+        }
     }
+    let shell = match (&args.shell, account.interpreter.as_str()) {
+        (Some(shell), _) => shell.as_os_str(),
+        (None, "") => OsStr::new("/bin/sh"),
+        (None, interpreter) => OsStr::new(interpreter),
+    };
+    // Replace the caller's supplementary groups with the target's
+    let groups: Vec<_> = Nss::load()
+        .and_then(|nss| nss.group())
+        .unwrap_or_else(|e| error("su", format_args!("cannot read group database: {e}")))
+        .iter()
+        .filter(|g| g.is_member(&account.login_name))
+        .map(|g| g.gid)
+        .collect();
+    user::set_groups(&groups)
+        .unwrap_or_else(|e| error("su", format_args!("cannot change groups: {e}")));
+    user::set(account.uid, account.gid)
+        .unwrap_or_else(|e| error("su", format_args!("cannot change user: {e}")));
+    // TODO use `execve` instead
+    // Run the shell
+    let status = Command::new(shell)
+        .args(args.args)
+        // TODO Set env
+        .status()
+        .unwrap_or_else(|e| {
+            error(
+                "su",
+                format_args!("Failed to run shell `{}`: {e}", shell.display()),
+            );
+        });
+
+    // Exit with the shell's status
+    exit(status.code().unwrap_or(1));
 }
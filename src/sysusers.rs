@@ -0,0 +1,651 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `sysusers` creates system users and groups declared in `sysusers.d` configuration files.
+//!
+//! Each non-empty line of a configuration file declares an entry:
+//! - `u name id "gecos" home shell`: a user. `id` may be `uid:gid`, where `gid` is a GID or a
+//!   group name. If no group is specified, a group with the same name as the user is created
+//! - `g name gid`: a group
+//! - `m user group`: makes a user a member of a group
+//! - `r - min-max`: a range in which IDs are allocated
+//!
+//! A field set to `-` takes its default value. Entries that already exist are left untouched,
+//! so that the command can be run several times.
+
+use std::collections::BTreeMap;
+use std::env::ArgsOs;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::{fs, io};
+use utils::error;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, PASSWD_PATH, SHADOW_PATH, SYSTEM_ID_RANGE,
+    Shadow, User, allocate_id, current_day, is_valid_field, is_valid_name, root_path,
+};
+
+/// Directories containing configuration files, by decreasing priority.
+const CONFIG_DIRS: &[&str] = &["/etc/sysusers.d", "/run/sysusers.d", "/usr/lib/sysusers.d"];
+/// The default home directory of created users.
+const DEFAULT_HOME: &str = "/";
+/// The default shell of created users.
+const DEFAULT_SHELL: &str = "/sbin/nologin";
+/// The password of created accounts, which cannot be used to log in.
+const LOCKED: &str = "!*";
+
+/// A declaration in a configuration file.
+enum Entry<'s> {
+    /// A user.
+    User {
+        /// The user's name.
+        name: &'s str,
+        /// The requested UID.
+        uid: Option<u32>,
+        /// The name or GID of the user's primary group.
+        group: Option<&'s str>,
+        /// User comment.
+        gecos: &'s str,
+        /// User's home directory.
+        home: &'s str,
+        /// User's shell.
+        shell: &'s str,
+    },
+    /// A group.
+    Group {
+        /// The group's name.
+        name: &'s str,
+        /// The requested GID.
+        gid: Option<u32>,
+    },
+    /// A membership.
+    Member {
+        /// The user's name.
+        user: &'s str,
+        /// The group's name.
+        group: &'s str,
+    },
+    /// An ID allocation range.
+    Range(RangeInclusive<u32>),
+}
+
+/// Splits `line` into whitespace-separated fields. Fields may be surrounded by double quotes to
+/// contain whitespace.
+fn split_fields(line: &str) -> Result<Vec<&str>, String> {
+    let mut fields = vec![];
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (field, remain) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').ok_or("unterminated quote")?;
+                (&quoted[..end], &quoted[(end + 1)..])
+            }
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+        fields.push(field);
+        rest = remain.trim_start();
+    }
+    Ok(fields)
+}
+
+/// Parses an ID field. `-` means no ID is requested.
+fn parse_id(field: &str) -> Result<Option<u32>, String> {
+    match field {
+        "-" => Ok(None),
+        _ => field
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid ID `{field}`")),
+    }
+}
+
+/// Returns `field`, or `default` if the field is `-`.
+fn or_default<'s>(field: &'s str, default: &'s str) -> &'s str {
+    match field {
+        "-" => default,
+        f => f,
+    }
+}
+
+/// Parses a configuration line. If the line is empty or a comment, the function returns `None`.
+fn parse_line<'s>(line: &'s str) -> Result<Option<Entry<'s>>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let fields = split_fields(line)?;
+    // Missing fields take their default value
+    let field = |i: usize| -> &'s str { fields.get(i).copied().unwrap_or("-") };
+    let name = field(1);
+    let check_name = |name: &str| {
+        if is_valid_name(name) {
+            Ok(())
+        } else {
+            Err(format!("invalid name `{name}`"))
+        }
+    };
+    let entry = match field(0) {
+        "u" => {
+            check_name(name)?;
+            let (uid, group) = match field(2).split_once(':') {
+                Some((uid, group)) => (parse_id(uid)?, Some(group)),
+                None => (parse_id(field(2))?, None),
+            };
+            let gecos = or_default(field(3), "");
+            let home = or_default(field(4), DEFAULT_HOME);
+            let shell = or_default(field(5), DEFAULT_SHELL);
+            if let Some(val) = [gecos, home, shell]
+                .into_iter()
+                .find(|v| !is_valid_field(v))
+            {
+                return Err(format!("`{val}` contains invalid characters"));
+            }
+            Entry::User {
+                name,
+                uid,
+                group,
+                gecos,
+                home,
+                shell,
+            }
+        }
+        "g" => {
+            check_name(name)?;
+            Entry::Group {
+                name,
+                gid: parse_id(field(2))?,
+            }
+        }
+        "m" => {
+            check_name(name)?;
+            check_name(field(2))?;
+            Entry::Member {
+                user: name,
+                group: field(2),
+            }
+        }
+        "r" => {
+            let range = field(2);
+            let (min, max) = range.split_once('-').unwrap_or((range, range));
+            let (Some(min), Some(max)) = (parse_id(min)?, parse_id(max)?) else {
+                return Err(format!("invalid range `{range}`"));
+            };
+            if min > max {
+                return Err(format!("invalid range `{range}`"));
+            }
+            Entry::Range(min..=max)
+        }
+        t => return Err(format!("invalid entry type `{t}`")),
+    };
+    Ok(Some(entry))
+}
+
+/// The account databases being modified.
+struct Accounts<'s> {
+    users: Vec<User<'s>>,
+    shadows: Option<Vec<Shadow<'s>>>,
+    groups: Vec<Group<'s>>,
+    gshadows: Option<Vec<GShadow<'s>>>,
+    /// Memberships to add, as `(group, user)` pairs.
+    members: Vec<(&'s str, &'s str)>,
+    /// The ranges in which IDs are allocated.
+    ranges: Vec<RangeInclusive<u32>>,
+    /// Tells whether the databases have been modified.
+    changed: bool,
+}
+
+impl<'s> Accounts<'s> {
+    /// Allocates an ID in the configured ranges, for which `used` returns `false`.
+    fn allocate<F: Fn(u32) -> bool>(&self, used: F) -> Option<u32> {
+        if self.ranges.is_empty() {
+            return allocate_id(SYSTEM_ID_RANGE, used);
+        }
+        self.ranges
+            .iter()
+            .find_map(|range| allocate_id(range.clone(), &used))
+    }
+
+    /// Tells whether the GID `gid` is used.
+    fn gid_used(&self, gid: u32) -> bool {
+        self.groups.iter().any(|g| g.gid == gid)
+    }
+
+    /// Tells whether the UID `uid` is used.
+    fn uid_used(&self, uid: u32) -> bool {
+        self.users.iter().any(|u| u.uid == uid)
+    }
+
+    /// Creates the group `name` if it does not exist, preferably with the GID `gid`, and returns
+    /// its GID.
+    fn add_group(&mut self, name: &'s str, gid: Option<u32>) -> Result<u32, String> {
+        if let Some(group) = self.groups.iter().find(|g| g.group_name == name) {
+            return Ok(group.gid);
+        }
+        let gid = gid
+            .filter(|gid| !self.gid_used(*gid))
+            .or_else(|| self.allocate(|id| self.gid_used(id)))
+            .ok_or_else(|| format!("no GID available for group `{name}`"))?;
+        println!("Creating group '{name}' with GID {gid}");
+        self.groups.push(Group {
+            group_name: name,
+            password: if self.gshadows.is_some() { "x" } else { LOCKED },
+            gid,
+            users_list: "",
+        });
+        if let Some(gshadows) = &mut self.gshadows {
+            gshadows.push(GShadow {
+                group_name: name,
+                password: LOCKED,
+                administrators: "",
+                members: "",
+            });
+        }
+        self.changed = true;
+        Ok(gid)
+    }
+
+    /// Creates the user `name` if it does not exist.
+    ///
+    /// Arguments:
+    /// - `uid` is the preferred UID.
+    /// - `group` is the name or GID of the user's primary group. If `None`, a group with the same
+    ///   name as the user is used, and created if necessary.
+    fn add_user(
+        &mut self,
+        name: &'s str,
+        uid: Option<u32>,
+        group: Option<&'s str>,
+        gecos: &'s str,
+        home: &'s str,
+        shell: &'s str,
+    ) -> Result<(), String> {
+        if self.users.iter().any(|u| u.login_name == name) {
+            return Ok(());
+        }
+        let gid = match group {
+            Some(group) => {
+                let gid = self
+                    .groups
+                    .iter()
+                    .find(|g| g.group_name == group || g.gid.to_string() == group)
+                    .map(|g| g.gid);
+                Some(gid.ok_or_else(|| format!("group `{group}` does not exist"))?)
+            }
+            None => self
+                .groups
+                .iter()
+                .find(|g| g.group_name == name)
+                .map(|g| g.gid),
+        };
+        let uid = match uid.filter(|uid| !self.uid_used(*uid)) {
+            Some(uid) => uid,
+            None => {
+                // Prefer using the same ID as the user's own group
+                let same_id = gid
+                    .filter(|_| group.is_none())
+                    .filter(|gid| !self.uid_used(*gid));
+                same_id
+                    .or_else(|| {
+                        self.allocate(|id| {
+                            self.uid_used(id) || (gid.is_none() && self.gid_used(id))
+                        })
+                    })
+                    .ok_or_else(|| format!("no UID available for user `{name}`"))?
+            }
+        };
+        let gid = match gid {
+            Some(gid) => gid,
+            None => self.add_group(name, Some(uid))?,
+        };
+        println!("Creating user '{name}' with UID {uid} and GID {gid}");
+        self.users.push(User {
+            login_name: name,
+            password: if self.shadows.is_some() { "x" } else { LOCKED },
+            uid,
+            gid,
+            comment: gecos,
+            home: Path::new(home),
+            interpreter: shell,
+        });
+        if let Some(shadows) = &mut self.shadows {
+            shadows.push(Shadow {
+                login_name: name,
+                password: LOCKED,
+                last_change: Some(current_day()),
+                minimum_age: None,
+                maximum_age: None,
+                warning_period: None,
+                inactivity_period: None,
+                account_expiration: None,
+                reserved: "",
+            });
+        }
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Makes the user `user` a member of the group `group`, creating both if necessary.
+    fn add_member(&mut self, user: &'s str, group: &'s str) -> Result<(), String> {
+        self.add_group(group, None)?;
+        self.add_user(user, None, None, "", DEFAULT_HOME, DEFAULT_SHELL)?;
+        let member = self
+            .groups
+            .iter()
+            .any(|g| g.group_name == group && g.is_member(user));
+        if !member && !self.members.contains(&(group, user)) {
+            println!("Adding user '{user}' to group '{group}'");
+            self.members.push((group, user));
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Returns the member lists of the groups, with the added memberships, in the same order as
+    /// the groups.
+    fn member_lists<F: Fn(usize) -> &'s str>(&self, current: F) -> Vec<String> {
+        self.groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let added = self
+                    .members
+                    .iter()
+                    .filter(|(g, _)| *g == group.group_name)
+                    .map(|(_, user)| *user);
+                let current = current(i);
+                let list = current.split(',').filter(|s| !s.is_empty());
+                list.chain(added).collect::<Vec<_>>().join(",")
+            })
+            .collect()
+    }
+}
+
+/// Reads the file at `path`, returning `None` if it does not exist.
+fn read_optional(path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Some(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => error(
+            "sysusers",
+            format_args!("cannot read {}: {e}", path.display()),
+        ),
+    }
+}
+
+/// Returns the paths of the configuration files in the system at `root`, sorted by name.
+///
+/// If several files have the same name, only the one in the directory with the highest priority
+/// is used.
+fn config_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = BTreeMap::new();
+    for dir in CONFIG_DIRS {
+        let entries = match fs::read_dir(root_path(root, dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "conf")
+                && let Some(name) = path.file_name()
+            {
+                files.entry(name.to_owned()).or_insert(path);
+            }
+        }
+    }
+    Ok(files.into_values().collect())
+}
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" sysusers [--root <path>] [config...]");
+    println!();
+    println!("Creates the system users and groups declared in configuration files.");
+    println!();
+    println!("Options:");
+    println!(" --root <path>\toperate on the system whose root directory is at the given path");
+}
+
+pub fn main(args: ArgsOs) {
+    let mut root = PathBuf::from("/");
+    let mut files = vec![];
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--root") => {
+                root = args.next().map(PathBuf::from).unwrap_or_else(|| {
+                    print_usage();
+                    exit(1);
+                });
+            }
+            Some("-h" | "--help") => {
+                print_usage();
+                exit(0);
+            }
+            Some(a) if a.starts_with('-') => {
+                print_usage();
+                exit(1);
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        files = config_files(&root).unwrap_or_else(|e| {
+            error(
+                "sysusers",
+                format_args!("cannot list configuration files: {e}"),
+            );
+        });
+    }
+    // Parse configuration
+    let buffs: Vec<_> = files
+        .iter()
+        .map(|path| {
+            let buff = fs::read_to_string(path).unwrap_or_else(|e| {
+                error(
+                    "sysusers",
+                    format_args!("cannot read {}: {e}", path.display()),
+                );
+            });
+            (path, buff)
+        })
+        .collect();
+    let mut failed = false;
+    let mut entries = vec![];
+    for (path, buff) in &buffs {
+        for (i, line) in buff.lines().enumerate() {
+            match parse_line(line) {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("sysusers: {}:{}: {e}", path.display(), i + 1);
+                    failed = true;
+                }
+            }
+        }
+    }
+    if failed {
+        error("sysusers", "errors detected, changes ignored");
+    }
+    // Read account databases
+    let lock = Lock::acquire_in(&root).unwrap_or_else(|e| {
+        error("sysusers", format_args!("cannot lock account files: {e}"));
+    });
+    let passwd_path = root_path(&root, PASSWD_PATH);
+    let shadow_path = root_path(&root, SHADOW_PATH);
+    let group_path = root_path(&root, GROUP_PATH);
+    let gshadow_path = root_path(&root, GSHADOW_PATH);
+    let parse_error = |path: &Path, e| -> ! {
+        error("sysusers", format_args!("{}: {e}", path.display()));
+    };
+    let users_buff = read_optional(&passwd_path).unwrap_or_default();
+    let shadow_buff = read_optional(&shadow_path);
+    let groups_buff = read_optional(&group_path).unwrap_or_default();
+    let gshadow_buff = read_optional(&gshadow_path);
+    let mut accounts = Accounts {
+        users: User::deserialize(&users_buff)
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| parse_error(&passwd_path, e)),
+        shadows: shadow_buff.as_deref().map(|buff| {
+            Shadow::deserialize(buff)
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|e| parse_error(&shadow_path, e))
+        }),
+        groups: Group::deserialize(&groups_buff)
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| parse_error(&group_path, e)),
+        gshadows: gshadow_buff.as_deref().map(|buff| {
+            GShadow::deserialize(buff)
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|e| parse_error(&gshadow_path, e))
+        }),
+        members: vec![],
+        ranges: vec![],
+        changed: false,
+    };
+    // Apply entries: ranges first, then groups, users and memberships
+    for entry in &entries {
+        if let Entry::Range(range) = entry {
+            accounts.ranges.push(range.clone());
+        }
+    }
+    let mut apply = |res: Result<_, String>| {
+        if let Err(e) = res {
+            eprintln!("sysusers: {e}");
+            failed = true;
+        }
+    };
+    for entry in &entries {
+        if let Entry::Group { name, gid } = entry {
+            apply(accounts.add_group(name, *gid).map(|_| ()));
+        }
+    }
+    for entry in &entries {
+        if let Entry::User {
+            name,
+            uid,
+            group,
+            gecos,
+            home,
+            shell,
+        } = entry
+        {
+            apply(accounts.add_user(name, *uid, *group, gecos, home, shell));
+        }
+    }
+    for entry in &entries {
+        if let Entry::Member { user, group } = entry {
+            apply(accounts.add_member(user, group));
+        }
+    }
+    if failed {
+        error("sysusers", "errors detected, changes ignored");
+    }
+    if !accounts.changed {
+        return;
+    }
+    // Write files
+    let users_lists = accounts.member_lists(|i| accounts.groups[i].users_list);
+    for (group, list) in accounts.groups.iter_mut().zip(&users_lists) {
+        group.users_list = list;
+    }
+    let gshadow_lists = accounts.gshadows.as_ref().map(|gshadows| {
+        accounts.member_lists(|i| {
+            let name = accounts.groups[i].group_name;
+            gshadows
+                .iter()
+                .find(|g| g.group_name == name)
+                .map(|g| g.members)
+                .unwrap_or_default()
+        })
+    });
+    let write_error = |path: &Path, e| -> ! {
+        error(
+            "sysusers",
+            format_args!("cannot write {}: {e}", path.display()),
+        );
+    };
+    if let (Some(mut gshadows), Some(lists)) = (accounts.gshadows, &gshadow_lists) {
+        for (group, list) in accounts.groups.iter().zip(lists) {
+            if let Some(gshadow) = gshadows
+                .iter_mut()
+                .find(|g| g.group_name == group.group_name)
+            {
+                gshadow.members = list;
+            }
+        }
+        lock.write(&gshadow_path, gshadows)
+            .unwrap_or_else(|e| write_error(&gshadow_path, e));
+    }
+    lock.write(&group_path, accounts.groups)
+        .unwrap_or_else(|e| write_error(&group_path, e));
+    if let Some(shadows) = accounts.shadows {
+        lock.write(&shadow_path, shadows)
+            .unwrap_or_else(|e| write_error(&shadow_path, e));
+    }
+    lock.write(&passwd_path, accounts.users)
+        .unwrap_or_else(|e| write_error(&passwd_path, e));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        assert_eq!(split_fields("").unwrap(), Vec::<&str>::new());
+        assert_eq!(
+            split_fields("u  httpd 80 \"Web server\" /srv").unwrap(),
+            ["u", "httpd", "80", "Web server", "/srv"]
+        );
+        assert!(split_fields("u httpd - \"Web server").is_err());
+    }
+
+    #[test]
+    fn entries() {
+        assert!(parse_line("# comment").unwrap().is_none());
+        let Some(Entry::User {
+            name,
+            uid,
+            group,
+            home,
+            shell,
+            ..
+        }) = parse_line("u httpd 80:www").unwrap()
+        else {
+            panic!();
+        };
+        assert_eq!(
+            (name, uid, group, home, shell),
+            ("httpd", Some(80), Some("www"), DEFAULT_HOME, DEFAULT_SHELL)
+        );
+        let Some(Entry::Range(range)) = parse_line("r - 100-200").unwrap() else {
+            panic!();
+        };
+        assert_eq!(range, 100..=200);
+        assert!(parse_line("r - 200-100").is_err());
+        assert!(parse_line("u bad:name").is_err());
+        assert!(parse_line("u foo - \"a:b\"").is_err());
+        assert!(parse_line("z foo").is_err());
+    }
+}
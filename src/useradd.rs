@@ -0,0 +1,366 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `useradd` creates a user.
+//!
+//! Unless a primary group is specified, a group with the same name as the user is created.
+//! Regular users are also allocated ranges of subordinate UIDs and GIDs.
+
+use std::env::ArgsOs;
+use std::ffi::OsString;
+use std::fs::Permissions;
+use std::io::ErrorKind;
+use std::os::unix;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::exit;
+use std::{fs, io};
+use utils::error;
+use utils::subid;
+use utils::subid::{SUBGID_PATH, SUBID_COUNT, SUBUID_PATH, SubId};
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, ID_RANGE, Lock, PASSWD_PATH, SHADOW_PATH,
+    SYSTEM_ID_RANGE, Shadow, User, allocate_id, current_day, is_valid_field, is_valid_name,
+    list_add,
+};
+
+/// The default shell of created users.
+const DEFAULT_SHELL: &str = "/bin/sh";
+/// The password of created users, which cannot be used until a password is set.
+const LOCKED: &str = "!";
+
+/// The command's arguments.
+#[derive(Default)]
+struct Args {
+    /// The user's name.
+    name: String,
+    /// The requested UID.
+    uid: Option<u32>,
+    /// The name or GID of the user's primary group.
+    group: Option<String>,
+    /// Comma-separated list of supplementary groups.
+    groups: Option<String>,
+    /// User comment.
+    comment: String,
+    /// User's home directory.
+    home: Option<String>,
+    /// If true, the home directory is created.
+    create_home: bool,
+    /// User's shell.
+    shell: Option<String>,
+    /// If true, a system user is created.
+    system: bool,
+}
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" useradd [options] <login>");
+    println!();
+    println!("Creates a user.");
+    println!();
+    println!("Options:");
+    println!(" -u, --uid <uid>\t\tthe user ID");
+    println!(" -g, --gid <group>\t\tthe name or ID of the primary group");
+    println!(" -G, --groups <groups>\t\tcomma-separated list of supplementary groups");
+    println!(" -c, --comment <comment>\tthe comment field");
+    println!(" -d, --home-dir <dir>\t\tthe home directory");
+    println!(" -m, --create-home\t\tcreate the home directory");
+    println!(" -s, --shell <shell>\t\tthe login shell");
+    println!(" -r, --system\t\t\tcreate a system user");
+}
+
+/// Returns the next argument as the value of the option `opt`.
+fn next_value<I: Iterator<Item = OsString>>(args: &mut I, opt: &str) -> String {
+    let Some(val) = args.next() else {
+        error("useradd", format_args!("option `{opt}` requires a value"));
+    };
+    val.into_string().unwrap_or_else(|val| {
+        error(
+            "useradd",
+            format_args!("invalid value `{}` for `{opt}`", val.display()),
+        );
+    })
+}
+
+/// Parses the given CLI arguments `args` and returns their representation in the [`Args`]
+/// structure.
+fn parse_args(mut args: ArgsOs) -> Args {
+    let mut res = Args::default();
+    let mut name = None;
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            print_usage();
+            exit(1);
+        };
+        match arg {
+            "-u" | "--uid" => {
+                let uid = next_value(&mut args, arg);
+                res.uid = Some(uid.parse().unwrap_or_else(|_| {
+                    error("useradd", format_args!("invalid UID `{uid}`"));
+                }));
+            }
+            "-g" | "--gid" => res.group = Some(next_value(&mut args, arg)),
+            "-G" | "--groups" => res.groups = Some(next_value(&mut args, arg)),
+            "-c" | "--comment" => res.comment = next_value(&mut args, arg),
+            "-d" | "--home-dir" => res.home = Some(next_value(&mut args, arg)),
+            "-m" | "--create-home" => res.create_home = true,
+            "-s" | "--shell" => res.shell = Some(next_value(&mut args, arg)),
+            "-r" | "--system" => res.system = true,
+            "-h" | "--help" => {
+                print_usage();
+                exit(0);
+            }
+            _ if !arg.starts_with('-') && name.is_none() => name = Some(arg.to_owned()),
+            _ => {
+                print_usage();
+                exit(1);
+            }
+        }
+    }
+    let Some(name) = name else {
+        print_usage();
+        exit(1);
+    };
+    let fields = [Some(&res.comment), res.home.as_ref(), res.shell.as_ref()];
+    if let Some(val) = fields
+        .into_iter()
+        .flatten()
+        .find(|val| !is_valid_field(val))
+    {
+        error(
+            "useradd",
+            format_args!("`{val}` contains invalid characters"),
+        );
+    }
+    res.name = name;
+    res
+}
+
+/// Reads the file at `path`, returning `None` if it does not exist.
+fn read_optional(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Some(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => error("useradd", format_args!("cannot read {path}: {e}")),
+    }
+}
+
+/// Creates the home directory at `home` for the user with the given UID and GID.
+fn create_home(home: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    fs::create_dir_all(home)?;
+    unix::fs::chown(home, Some(uid), Some(gid))?;
+    fs::set_permissions(home, Permissions::from_mode(0o700))
+}
+
+/// Allocates a range of subordinate IDs for the user `name` in the file at `path`, if the user
+/// does not already have one.
+fn allocate_subids(lock: &Lock, path: &str, name: &str) {
+    let buff = read_optional(path).unwrap_or_default();
+    let mut entries = SubId::deserialize(&buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("useradd", format_args!("{path}: {e}")));
+    if entries.iter().any(|e| e.owner == name) {
+        return;
+    }
+    let Some(start) = subid::allocate(&entries, SUBID_COUNT) else {
+        error(
+            "useradd",
+            format_args!("no subordinate IDs available in {path}"),
+        );
+    };
+    entries.push(SubId {
+        owner: name,
+        start,
+        count: SUBID_COUNT,
+    });
+    lock.write(Path::new(path), entries)
+        .unwrap_or_else(|e| error("useradd", format_args!("cannot write {path}: {e}")));
+}
+
+pub fn main(args: ArgsOs) {
+    let args = parse_args(args);
+    let name = args.name.as_str();
+    if !is_valid_name(name) {
+        error("useradd", format_args!("invalid user name `{name}`"));
+    }
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error("useradd", format_args!("cannot lock account files: {e}"));
+    });
+    let users_buff = read_optional(PASSWD_PATH).unwrap_or_default();
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("useradd", format_args!("{PASSWD_PATH}: {e}")));
+    let shadow_buff = read_optional(SHADOW_PATH);
+    let mut shadows = shadow_buff.as_deref().map(|buff| {
+        Shadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("useradd", format_args!("{SHADOW_PATH}: {e}")))
+    });
+    let groups_buff = read_optional(GROUP_PATH).unwrap_or_default();
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("useradd", format_args!("{GROUP_PATH}: {e}")));
+    let gshadow_buff = read_optional(GSHADOW_PATH);
+    let mut gshadows = gshadow_buff.as_deref().map(|buff| {
+        GShadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("useradd", format_args!("{GSHADOW_PATH}: {e}")))
+    });
+    if users.iter().any(|u| u.login_name == name) {
+        error("useradd", format_args!("user `{name}` already exists"));
+    }
+    let find_group = |groups: &[Group], group: &str| {
+        groups
+            .iter()
+            .position(|g| g.group_name == group || g.gid.to_string() == group)
+            .unwrap_or_else(|| error("useradd", format_args!("group `{group}` does not exist")))
+    };
+    // Check supplementary groups before modifying anything
+    let supplementary: Vec<_> = args
+        .groups
+        .iter()
+        .flat_map(|groups| groups.split(','))
+        .filter(|g| !g.is_empty())
+        .map(|g| find_group(&groups, g))
+        .collect();
+    let gid = args
+        .group
+        .as_ref()
+        .map(|g| groups[find_group(&groups, g)].gid);
+    if gid.is_none() && groups.iter().any(|g| g.group_name == name) {
+        error(
+            "useradd",
+            format_args!("group `{name}` exists, use `-g` to add the user to it"),
+        );
+    }
+    // Allocate IDs
+    let range = if args.system {
+        SYSTEM_ID_RANGE
+    } else {
+        ID_RANGE
+    };
+    let uid_used = |id| users.iter().any(|u| u.uid == id);
+    let gid_used = |id| groups.iter().any(|g| g.gid == id);
+    let uid = match args.uid {
+        Some(uid) if uid_used(uid) => error("useradd", format_args!("UID {uid} is not unique")),
+        Some(uid) => uid,
+        // If a group has to be created, prefer using the same ID for both
+        None => allocate_id(range.clone(), |id| {
+            uid_used(id) || (gid.is_none() && gid_used(id))
+        })
+        .or_else(|| allocate_id(range.clone(), uid_used))
+        .unwrap_or_else(|| error("useradd", "no UID available")),
+    };
+    let gid = match gid {
+        Some(gid) => gid,
+        None => {
+            let gid = Some(uid)
+                .filter(|id| !gid_used(*id))
+                .or_else(|| allocate_id(range, gid_used))
+                .unwrap_or_else(|| error("useradd", "no GID available"));
+            groups.push(Group {
+                group_name: name,
+                password: "x",
+                gid,
+                users_list: "",
+            });
+            if let Some(gshadows) = &mut gshadows {
+                gshadows.push(GShadow {
+                    group_name: name,
+                    password: LOCKED,
+                    administrators: "",
+                    members: "",
+                });
+            }
+            gid
+        }
+    };
+    // Add to supplementary groups
+    let lists: Vec<_> = supplementary
+        .iter()
+        .map(|i| list_add(groups[*i].users_list, name))
+        .collect();
+    let members: Vec<_> = supplementary
+        .iter()
+        .map(|i| {
+            let group_name = groups[*i].group_name;
+            gshadows
+                .iter()
+                .flatten()
+                .find(|g| g.group_name == group_name)
+                .map(|g| list_add(g.members, name))
+        })
+        .collect();
+    for ((i, list), members) in supplementary.iter().zip(&lists).zip(&members) {
+        let group = &mut groups[*i];
+        group.users_list = list;
+        if let (Some(gshadows), Some(members)) = (&mut gshadows, members)
+            && let Some(gshadow) = gshadows
+                .iter_mut()
+                .find(|g| g.group_name == group.group_name)
+        {
+            gshadow.members = members;
+        }
+    }
+    let home = args.home.unwrap_or_else(|| format!("/home/{name}"));
+    users.push(User {
+        login_name: name,
+        password: if shadows.is_some() { "x" } else { LOCKED },
+        uid,
+        gid,
+        comment: &args.comment,
+        home: Path::new(&home),
+        interpreter: args.shell.as_deref().unwrap_or(DEFAULT_SHELL),
+    });
+    if let Some(shadows) = &mut shadows {
+        shadows.push(Shadow {
+            login_name: name,
+            password: LOCKED,
+            last_change: Some(current_day()),
+            minimum_age: None,
+            maximum_age: None,
+            warning_period: None,
+            inactivity_period: None,
+            account_expiration: None,
+            reserved: "",
+        });
+    }
+    // Write files
+    if !args.system {
+        allocate_subids(&lock, SUBUID_PATH, name);
+        allocate_subids(&lock, SUBGID_PATH, name);
+    }
+    if let Some(gshadows) = gshadows {
+        lock.write(Path::new(GSHADOW_PATH), gshadows)
+            .unwrap_or_else(|e| error("useradd", format_args!("cannot write gshadow file: {e}")));
+    }
+    lock.write(Path::new(GROUP_PATH), groups)
+        .unwrap_or_else(|e| error("useradd", format_args!("cannot write group file: {e}")));
+    if let Some(shadows) = shadows {
+        lock.write(Path::new(SHADOW_PATH), shadows)
+            .unwrap_or_else(|e| error("useradd", format_args!("cannot write shadow file: {e}")));
+    }
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error("useradd", format_args!("cannot write passwd file: {e}")));
+    if args.create_home {
+        create_home(Path::new(&home), uid, gid).unwrap_or_else(|e| {
+            error("useradd", format_args!("cannot create home {home}: {e}"));
+        });
+    }
+}
@@ -0,0 +1,352 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `usermod` modifies a user.
+
+use std::env::ArgsOs;
+use std::ffi::OsString;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::Path;
+use std::process::exit;
+use utils::error;
+use utils::subid;
+use utils::subid::{SUBGID_PATH, SUBUID_PATH, SubId};
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, PASSWD_PATH, SHADOW_PATH, Shadow, User,
+    is_valid_field, list_add, list_remove,
+};
+
+/// The command's arguments.
+#[derive(Default)]
+struct Args {
+    /// The user's name.
+    name: String,
+    /// The new comment.
+    comment: Option<String>,
+    /// The new home directory.
+    home: Option<String>,
+    /// The name or GID of the new primary group.
+    group: Option<String>,
+    /// Comma-separated list of supplementary groups.
+    groups: Option<String>,
+    /// If true, the user is added to the supplementary groups instead of replacing them.
+    append: bool,
+    /// The new shell.
+    shell: Option<String>,
+    /// If `Some`, tells whether the password must be locked or unlocked.
+    lock: Option<bool>,
+    /// Subordinate UID ranges to add.
+    add_subuids: Vec<Range<u64>>,
+    /// Subordinate UID ranges to remove.
+    del_subuids: Vec<Range<u64>>,
+    /// Subordinate GID ranges to add.
+    add_subgids: Vec<Range<u64>>,
+    /// Subordinate GID ranges to remove.
+    del_subgids: Vec<Range<u64>>,
+}
+
+/// Prints command usage.
+fn print_usage() {
+    println!("Usage:");
+    println!(" usermod [options] <login>");
+    println!();
+    println!("Modifies a user.");
+    println!();
+    println!("Options:");
+    println!(" -c, --comment <comment>\t\tthe new comment field");
+    println!(" -d, --home <dir>\t\t\tthe new home directory");
+    println!(" -g, --gid <group>\t\t\tthe name or ID of the new primary group");
+    println!(" -G, --groups <groups>\t\t\tcomma-separated list of supplementary groups");
+    println!(" -a, --append\t\t\t\tadd to the supplementary groups instead of replacing them");
+    println!(" -s, --shell <shell>\t\t\tthe new login shell");
+    println!(" -L, --lock\t\t\t\tlock the user's password");
+    println!(" -U, --unlock\t\t\t\tunlock the user's password");
+    println!(" -v, --add-subuids <first>-<last>\tadd a range of subordinate UIDs");
+    println!(" -V, --del-subuids <first>-<last>\tremove a range of subordinate UIDs");
+    println!(" -w, --add-subgids <first>-<last>\tadd a range of subordinate GIDs");
+    println!(" -W, --del-subgids <first>-<last>\tremove a range of subordinate GIDs");
+}
+
+/// Returns the next argument as the value of the option `opt`.
+fn next_value<I: Iterator<Item = OsString>>(args: &mut I, opt: &str) -> String {
+    let Some(val) = args.next() else {
+        error("usermod", format_args!("option `{opt}` requires a value"));
+    };
+    val.into_string().unwrap_or_else(|val| {
+        error(
+            "usermod",
+            format_args!("invalid value `{}` for `{opt}`", val.display()),
+        );
+    })
+}
+
+/// Parses a range of IDs in the format `first-last`.
+fn parse_range(s: &str) -> Option<Range<u64>> {
+    let (first, last) = s.split_once('-')?;
+    let first: u32 = first.parse().ok()?;
+    let last: u32 = last.parse().ok()?;
+    (first <= last).then_some(first as u64..(last as u64 + 1))
+}
+
+/// Parses the given CLI arguments `args` and returns their representation in the [`Args`]
+/// structure.
+fn parse_args(mut args: ArgsOs) -> Args {
+    let mut res = Args::default();
+    let mut name = None;
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            print_usage();
+            exit(1);
+        };
+        let range = |args: &mut ArgsOs| {
+            let val = next_value(args, arg);
+            parse_range(&val)
+                .unwrap_or_else(|| error("usermod", format_args!("invalid range `{val}`")))
+        };
+        match arg {
+            "-c" | "--comment" => res.comment = Some(next_value(&mut args, arg)),
+            "-d" | "--home" => res.home = Some(next_value(&mut args, arg)),
+            "-g" | "--gid" => res.group = Some(next_value(&mut args, arg)),
+            "-G" | "--groups" => res.groups = Some(next_value(&mut args, arg)),
+            "-a" | "--append" => res.append = true,
+            "-s" | "--shell" => res.shell = Some(next_value(&mut args, arg)),
+            "-L" | "--lock" => res.lock = Some(true),
+            "-U" | "--unlock" => res.lock = Some(false),
+            "-v" | "--add-subuids" => res.add_subuids.push(range(&mut args)),
+            "-V" | "--del-subuids" => res.del_subuids.push(range(&mut args)),
+            "-w" | "--add-subgids" => res.add_subgids.push(range(&mut args)),
+            "-W" | "--del-subgids" => res.del_subgids.push(range(&mut args)),
+            "-h" | "--help" => {
+                print_usage();
+                exit(0);
+            }
+            _ if !arg.starts_with('-') && name.is_none() => name = Some(arg.to_owned()),
+            _ => {
+                print_usage();
+                exit(1);
+            }
+        }
+    }
+    let Some(name) = name else {
+        print_usage();
+        exit(1);
+    };
+    if res.append && res.groups.is_none() {
+        error("usermod", "`-a` requires `-G`");
+    }
+    let fields = [&res.comment, &res.home, &res.shell];
+    if let Some(val) = fields
+        .into_iter()
+        .flatten()
+        .find(|val| !is_valid_field(val))
+    {
+        error(
+            "usermod",
+            format_args!("`{val}` contains invalid characters"),
+        );
+    }
+    res.name = name;
+    res
+}
+
+/// Reads the file at `path`, returning `None` if it does not exist.
+fn read_optional(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Some(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => error("usermod", format_args!("cannot read {path}: {e}")),
+    }
+}
+
+/// Locks or unlocks the password `password`, returning the new value.
+///
+/// Unlocking an account that has no password is refused, since it would allow logging in without
+/// a password.
+fn lock_password(password: &str, lock: bool) -> String {
+    match (lock, password.strip_prefix('!')) {
+        (true, Some(_)) | (false, None) => password.to_owned(),
+        (true, None) => format!("!{password}"),
+        (false, Some("")) => error(
+            "usermod",
+            "unlocking the password would result in a passwordless account",
+        ),
+        (false, Some(unlocked)) => unlocked.to_owned(),
+    }
+}
+
+/// Adds and removes the given subordinate ID ranges of the user `name`, in the file at `path`.
+fn update_subids(lock: &Lock, path: &str, name: &str, add: &[Range<u64>], del: &[Range<u64>]) {
+    if add.is_empty() && del.is_empty() {
+        return;
+    }
+    let buff = read_optional(path).unwrap_or_default();
+    let mut entries = SubId::deserialize(&buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("usermod", format_args!("{path}: {e}")));
+    for range in del {
+        subid::remove(&mut entries, name, range.clone());
+    }
+    for range in add {
+        // Avoid duplicates by removing the range first
+        subid::remove(&mut entries, name, range.clone());
+        entries.push(SubId {
+            owner: name,
+            start: range.start as _,
+            count: (range.end - range.start).try_into().unwrap_or_else(|_| {
+                error("usermod", "subordinate ID range is too large");
+            }),
+        });
+    }
+    lock.write(Path::new(path), entries)
+        .unwrap_or_else(|e| error("usermod", format_args!("cannot write {path}: {e}")));
+}
+
+pub fn main(args: ArgsOs) {
+    let args = parse_args(args);
+    let name = args.name.as_str();
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error("usermod", format_args!("cannot lock account files: {e}"));
+    });
+    let users_buff = read_optional(PASSWD_PATH).unwrap_or_default();
+    let mut users = User::deserialize(&users_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("usermod", format_args!("{PASSWD_PATH}: {e}")));
+    let shadow_buff = read_optional(SHADOW_PATH);
+    let mut shadows = shadow_buff.as_deref().map(|buff| {
+        Shadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("usermod", format_args!("{SHADOW_PATH}: {e}")))
+    });
+    let groups_buff = read_optional(GROUP_PATH).unwrap_or_default();
+    let mut groups = Group::deserialize(&groups_buff)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| error("usermod", format_args!("{GROUP_PATH}: {e}")));
+    let gshadow_buff = read_optional(GSHADOW_PATH);
+    let mut gshadows = gshadow_buff.as_deref().map(|buff| {
+        GShadow::deserialize(buff)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| error("usermod", format_args!("{GSHADOW_PATH}: {e}")))
+    });
+    let Some(user) = users.iter_mut().find(|u| u.login_name == name) else {
+        error("usermod", format_args!("user `{name}` does not exist"));
+    };
+    let find_group = |group: &str| {
+        groups
+            .iter()
+            .find(|g| g.group_name == group || g.gid.to_string() == group)
+            .unwrap_or_else(|| error("usermod", format_args!("group `{group}` does not exist")))
+    };
+    // Modify the passwd entry
+    if let Some(comment) = &args.comment {
+        user.comment = comment;
+    }
+    if let Some(home) = &args.home {
+        user.home = Path::new(home);
+    }
+    if let Some(group) = &args.group {
+        user.gid = find_group(group).gid;
+    }
+    if let Some(shell) = &args.shell {
+        user.interpreter = shell;
+    }
+    let password;
+    if let Some(lock) = args.lock {
+        let shadow = shadows.iter_mut().flatten().find(|s| s.login_name == name);
+        match shadow {
+            Some(shadow) => {
+                password = lock_password(shadow.password, lock);
+                shadow.password = &password;
+            }
+            None => {
+                password = lock_password(user.password, lock);
+                user.password = &password;
+            }
+        }
+    }
+    // Update supplementary groups
+    let memberships = args.groups.as_ref().map(|list| {
+        let new: Vec<_> = list
+            .split(',')
+            .filter(|g| !g.is_empty())
+            .map(|g| find_group(g).group_name)
+            .collect();
+        let update = |group_name: &str, current: &str| {
+            if new.contains(&group_name) {
+                list_add(current, name)
+            } else if args.append {
+                current.to_owned()
+            } else {
+                list_remove(current, name)
+            }
+        };
+        let users_lists: Vec<_> = groups
+            .iter()
+            .map(|g| update(g.group_name, g.users_list))
+            .collect();
+        let members: Option<Vec<_>> = gshadows.as_ref().map(|gshadows| {
+            gshadows
+                .iter()
+                .map(|g| update(g.group_name, g.members))
+                .collect()
+        });
+        (users_lists, members)
+    });
+    if let Some((users_lists, members)) = &memberships {
+        for (group, list) in groups.iter_mut().zip(users_lists) {
+            group.users_list = list;
+        }
+        if let (Some(gshadows), Some(members)) = (&mut gshadows, members) {
+            for (gshadow, list) in gshadows.iter_mut().zip(members) {
+                gshadow.members = list;
+            }
+        }
+    }
+    // Write files
+    update_subids(
+        &lock,
+        SUBUID_PATH,
+        name,
+        &args.add_subuids,
+        &args.del_subuids,
+    );
+    update_subids(
+        &lock,
+        SUBGID_PATH,
+        name,
+        &args.add_subgids,
+        &args.del_subgids,
+    );
+    if memberships.is_some() {
+        if let Some(gshadows) = gshadows {
+            lock.write(Path::new(GSHADOW_PATH), gshadows)
+                .unwrap_or_else(|e| {
+                    error("usermod", format_args!("cannot write gshadow file: {e}"));
+                });
+        }
+        lock.write(Path::new(GROUP_PATH), groups)
+            .unwrap_or_else(|e| error("usermod", format_args!("cannot write group file: {e}")));
+    }
+    if let Some(shadows) = shadows {
+        lock.write(Path::new(SHADOW_PATH), shadows)
+            .unwrap_or_else(|e| error("usermod", format_args!("cannot write shadow file: {e}")));
+    }
+    lock.write(Path::new(PASSWD_PATH), users)
+        .unwrap_or_else(|e| error("usermod", format_args!("cannot write passwd file: {e}")));
+}
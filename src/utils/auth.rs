@@ -0,0 +1,425 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Authentication backends.
+//!
+//! Commands authenticating users go through the [`Backend`] trait, so that the source of
+//! accounts can be changed without modifying them.
+
+use crate::nss::{Nss, Source};
+use crate::user::{
+    Lock, PASSWD_PATH, SHADOW_PATH, Shadow, User, current_day, hash_password, is_valid_field,
+    root_path,
+};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::{fs, io};
+
+/// A user account, as returned by a [`Backend`].
+pub struct Account {
+    /// The user's login name.
+    pub login_name: String,
+    /// The user ID.
+    pub uid: u32,
+    /// The user's group ID.
+    pub gid: u32,
+    /// User comment.
+    pub comment: String,
+    /// User's home path.
+    pub home: PathBuf,
+    /// User's command interpreter.
+    pub interpreter: String,
+}
+
+impl From<&User<'_>> for Account {
+    fn from(user: &User<'_>) -> Self {
+        Self {
+            login_name: user.login_name.to_owned(),
+            uid: user.uid,
+            gid: user.gid,
+            comment: user.comment.to_owned(),
+            home: user.home.to_owned(),
+            interpreter: user.interpreter.to_owned(),
+        }
+    }
+}
+
+/// The status of an account.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AccountStatus {
+    /// The account can be used.
+    Valid,
+    /// The account has expired and cannot be used anymore.
+    Expired,
+    /// The password has expired and has to be changed before using the account.
+    PasswordExpired,
+    /// The password has expired for too long and the account cannot be used anymore.
+    Inactive,
+}
+
+/// A source of user accounts.
+pub trait Backend {
+    /// Returns the account of the user with the login name `name`, if it exists.
+    fn lookup(&self, name: &str) -> io::Result<Option<Account>>;
+
+    /// Checks the given (not hashed) password `secret` for the user `name`.
+    ///
+    /// If the user does not exist, the function returns `false`.
+    fn authenticate(&self, name: &str, secret: &str) -> io::Result<bool>;
+
+    /// Returns the status of the account of the user `name`.
+    fn account_ok(&self, name: &str) -> io::Result<AccountStatus>;
+
+    /// Sets the (not hashed) password `secret` for the user `name`.
+    fn change_password(&self, name: &str, secret: &str) -> io::Result<()>;
+}
+
+/// Backend using the passwd and shadow files in `/etc`.
+///
+/// Everything is relative to a root directory, which allows using the accounts of another system,
+/// or fixture files.
+pub struct Files {
+    /// The root directory.
+    root: PathBuf,
+}
+
+impl Default for Files {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Files {
+    /// Returns the backend for the current system.
+    pub fn new() -> Self {
+        Self::with_root("/")
+    }
+
+    /// Returns the backend for the system whose root directory is at `root`.
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Reads the file at `path`, relative to the root directory.
+    ///
+    /// If the file does not exist, the function returns `None`.
+    fn read(&self, path: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(root_path(&self.root, path)) {
+            Ok(buff) => Ok(Some(buff)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets the password hashes of several users at once, given as `(name, hash)` pairs.
+    ///
+    /// If a user does not exist or a hash cannot be stored in the files, no password is changed.
+    pub fn set_hashes(&self, hashes: &[(&str, &str)]) -> io::Result<()> {
+        if let Some((name, _)) = hashes.iter().find(|(_, hash)| !is_valid_field(hash)) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid password hash for `{name}`"),
+            ));
+        }
+        let lock = Lock::acquire_in(&self.root)?;
+        let users_buff = self.read(PASSWD_PATH)?.unwrap_or_default();
+        let mut users = User::deserialize(&users_buff)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_data(PASSWD_PATH))?;
+        let shadow_buff = self.read(SHADOW_PATH)?;
+        let mut shadows = shadow_buff
+            .as_deref()
+            .map(|buff| {
+                Shadow::deserialize(buff)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid_data(SHADOW_PATH))
+            })
+            .transpose()?;
+        let today = current_day();
+        for &(name, hash) in hashes {
+            let Some(user) = users.iter_mut().find(|u| u.login_name == name) else {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("user `{name}` does not exist"),
+                ));
+            };
+            // If the system does not use a shadow file, store the password in the passwd file
+            let Some(shadows) = &mut shadows else {
+                user.password = hash;
+                continue;
+            };
+            match shadows.iter_mut().find(|s| s.login_name == name) {
+                Some(shadow) => {
+                    shadow.password = hash;
+                    shadow.last_change = Some(today);
+                }
+                None => shadows.push(Shadow {
+                    login_name: name,
+                    password: hash,
+                    last_change: Some(today),
+                    minimum_age: None,
+                    maximum_age: None,
+                    warning_period: None,
+                    inactivity_period: None,
+                    account_expiration: None,
+                    reserved: "",
+                }),
+            }
+            user.password = "x";
+        }
+        if let Some(shadows) = shadows {
+            lock.write(&root_path(&self.root, SHADOW_PATH), shadows)?;
+        }
+        lock.write(&root_path(&self.root, PASSWD_PATH), users)
+    }
+}
+
+/// Returns the error for the invalid file at `path`.
+fn invalid_data(path: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("{path}: invalid entry"))
+}
+
+/// Returns the account of the user `name` in the databases of `nss`.
+fn lookup(nss: &Nss, name: &str) -> io::Result<Option<Account>> {
+    let passwd = nss.passwd()?;
+    Ok(passwd.by_name(name).map(|u| Account::from(&u)))
+}
+
+/// Checks the password `secret` of the user `name` in the databases of `nss`.
+fn authenticate(nss: &Nss, name: &str, secret: &str) -> io::Result<bool> {
+    let passwd = nss.passwd()?;
+    let Some(user) = passwd.by_name(name) else {
+        return Ok(false);
+    };
+    if let Some(correct) = user.check_password(secret) {
+        return Ok(correct);
+    }
+    // Fall back onto the shadow database
+    Ok(nss
+        .shadow()?
+        .by_name(name)
+        .is_some_and(|s| s.check_password(secret)))
+}
+
+/// Returns the status of the account of the user `name` in the databases of `nss`.
+fn account_ok(nss: &Nss, name: &str) -> io::Result<AccountStatus> {
+    let shadows = nss.shadow()?;
+    let Some(shadow) = shadows.by_name(name) else {
+        return Ok(AccountStatus::Valid);
+    };
+    let today = current_day();
+    if shadow.account_expiration.is_some_and(|day| today >= day) {
+        return Ok(AccountStatus::Expired);
+    }
+    // A last change at zero means the password must be changed, and an empty one that aging is
+    // disabled
+    let last_change = match shadow.last_change {
+        Some(0) => return Ok(AccountStatus::PasswordExpired),
+        Some(day) => day,
+        None => return Ok(AccountStatus::Valid),
+    };
+    let Some(max) = shadow.maximum_age else {
+        return Ok(AccountStatus::Valid);
+    };
+    let expiration = last_change.saturating_add(max);
+    if today <= expiration {
+        return Ok(AccountStatus::Valid);
+    }
+    let inactive = shadow
+        .inactivity_period
+        .is_some_and(|period| today > expiration.saturating_add(period));
+    if inactive {
+        Ok(AccountStatus::Inactive)
+    } else {
+        Ok(AccountStatus::PasswordExpired)
+    }
+}
+
+impl Backend for Files {
+    fn lookup(&self, name: &str) -> io::Result<Option<Account>> {
+        lookup(&Nss::files_in(&self.root), name)
+    }
+
+    fn authenticate(&self, name: &str, secret: &str) -> io::Result<bool> {
+        authenticate(&Nss::files_in(&self.root), name, secret)
+    }
+
+    fn account_ok(&self, name: &str) -> io::Result<AccountStatus> {
+        account_ok(&Nss::files_in(&self.root), name)
+    }
+
+    fn change_password(&self, name: &str, secret: &str) -> io::Result<()> {
+        // Hash before locking, since hashing is slow
+        let hash = hash_password(secret).map_err(|e| io::Error::other(e.to_string()))?;
+        self.set_hashes(&[(name, &hash)])
+    }
+}
+
+/// Backend looking accounts up through the name service switch (see [`crate::nss`]).
+///
+/// Passwords can only be changed for accounts stored in the files in `/etc`, through [`Files`].
+pub struct NameService {
+    /// The root directory.
+    root: PathBuf,
+}
+
+impl Default for NameService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NameService {
+    /// Returns the backend for the current system.
+    pub fn new() -> Self {
+        Self::with_root("/")
+    }
+
+    /// Returns the backend for the system whose root directory is at `root`.
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Backend for NameService {
+    fn lookup(&self, name: &str) -> io::Result<Option<Account>> {
+        lookup(&Nss::load_in(&self.root)?, name)
+    }
+
+    fn authenticate(&self, name: &str, secret: &str) -> io::Result<bool> {
+        authenticate(&Nss::load_in(&self.root)?, name, secret)
+    }
+
+    fn account_ok(&self, name: &str) -> io::Result<AccountStatus> {
+        account_ok(&Nss::load_in(&self.root)?, name)
+    }
+
+    fn change_password(&self, name: &str, secret: &str) -> io::Result<()> {
+        let passwd = Nss::load_in(&self.root)?.passwd()?;
+        match passwd.source_of(name) {
+            Some(Source::Files) => Files::with_root(&self.root).change_password(name, secret),
+            Some(_) => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("the account of `{name}` is not stored in {PASSWD_PATH}"),
+            )),
+            None => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("user `{name}` does not exist"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+    use std::process;
+
+    /// Creates a root directory with fixture account files, and returns its path.
+    fn fixture(name: &str, passwd: &str, shadow: Option<&str>) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("mutils-auth-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("etc")).unwrap();
+        fs::write(root.join("etc/passwd"), passwd).unwrap();
+        if let Some(shadow) = shadow {
+            fs::write(root.join("etc/shadow"), shadow).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn auth_files() {
+        let hash = hash_password("secret").unwrap();
+        let root = fixture(
+            "files",
+            &format!("alice:x:1000:1000:Alice:/home/alice:/bin/sh\nbob:{hash}:1001:1001::/:\n"),
+            Some(&format!("alice:{hash}:1::::::\n")),
+        );
+        let backend = Files::with_root(&root);
+        let alice = backend.lookup("alice").unwrap().unwrap();
+        assert_eq!(alice.uid, 1000);
+        assert_eq!(alice.home, Path::new("/home/alice"));
+        assert!(backend.lookup("carol").unwrap().is_none());
+        // Shadowed and non-shadowed passwords
+        assert!(backend.authenticate("alice", "secret").unwrap());
+        assert!(!backend.authenticate("alice", "wrong").unwrap());
+        assert!(backend.authenticate("bob", "secret").unwrap());
+        assert!(!backend.authenticate("carol", "secret").unwrap());
+        // Change password
+        backend.change_password("alice", "other").unwrap();
+        assert!(!backend.authenticate("alice", "secret").unwrap());
+        assert!(backend.authenticate("alice", "other").unwrap());
+        assert!(backend.change_password("carol", "other").is_err());
+        let err = backend.set_hashes(&[("alice", "$6$x:0:0")]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(backend.authenticate("alice", "other").unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn auth_name_service() {
+        let hash = hash_password("secret").unwrap();
+        let root = fixture(
+            "nss",
+            "alice:x:1000:1000::/:\n",
+            Some(&format!("alice:{hash}:1::::::\n")),
+        );
+        fs::write(root.join("etc/nsswitch.conf"), "passwd: files extrausers\n").unwrap();
+        fs::create_dir_all(root.join("var/lib/extrausers")).unwrap();
+        fs::write(
+            root.join("var/lib/extrausers/passwd"),
+            format!("bob:{hash}:1001:1001::/:\n"),
+        )
+        .unwrap();
+        // The files backend does not see other sources
+        assert!(Files::with_root(&root).lookup("bob").unwrap().is_none());
+        let backend = NameService::with_root(&root);
+        assert_eq!(backend.lookup("bob").unwrap().unwrap().uid, 1001);
+        assert!(backend.authenticate("bob", "secret").unwrap());
+        // Passwords are changed in the files only
+        let err = backend.change_password("bob", "other").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        backend.change_password("alice", "other").unwrap();
+        assert!(backend.authenticate("alice", "other").unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn auth_account_status() {
+        let today = current_day();
+        let root = fixture(
+            "status",
+            "",
+            Some(&format!(
+                "valid:!:{today}::::::\nexpired:!:{today}:::::1:\nchange:!:0::::::\n\
+                 old:!:1::10::::\ninactive:!:1::10::5::\nnoaging:!:::::::\n"
+            )),
+        );
+        let backend = Files::with_root(&root);
+        let status = |name| backend.account_ok(name).unwrap();
+        assert_eq!(status("valid"), AccountStatus::Valid);
+        assert_eq!(status("missing"), AccountStatus::Valid);
+        assert_eq!(status("expired"), AccountStatus::Expired);
+        assert_eq!(status("change"), AccountStatus::PasswordExpired);
+        assert_eq!(status("noaging"), AccountStatus::Valid);
+        assert_eq!(status("old"), AccountStatus::PasswordExpired);
+        assert_eq!(status("inactive"), AccountStatus::Inactive);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
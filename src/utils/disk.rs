@@ -18,7 +18,7 @@
 
 //! Implements disk-related utility functions.
 
-use super::partition::PartitionTable;
+use super::partition::{PartitionTable, partition_path};
 use super::util::ByteSize;
 use libc::ioctl;
 use std::ffi::c_long;
@@ -142,7 +142,7 @@ impl Disk {
             return Ok(None);
         };
         let sectors_count = size / sector_size as u64;
-        let partition_table = PartitionTable::read(&mut dev, sectors_count)?;
+        let partition_table = PartitionTable::read(&mut dev, sectors_count, sector_size as u64)?;
         Ok(Some(Self {
             dev_path,
             dev,
@@ -156,7 +156,9 @@ impl Disk {
 
     /// Writes the partition table to the disk.
     pub fn write(&mut self) -> io::Result<()> {
-        self.partition_table.write(&mut self.dev, self.size)
+        let sectors_count = self.sectors_count();
+        self.partition_table
+            .write(&mut self.dev, sectors_count, self.sector_size as u64)
     }
 
     /// Lists disks present on the system.
@@ -189,6 +191,19 @@ impl Disk {
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// Returns the number of sectors on the disk
+    #[inline]
+    pub fn sectors_count(&self) -> u64 {
+        self.size / self.sector_size as u64
+    }
+
+    /// Returns the path to the device file of the partition with the given number, starting at
+    /// `1`.
+    #[inline]
+    pub fn partition_path(&self, n: usize) -> PathBuf {
+        partition_path(&self.dev_path, n)
+    }
 }
 
 impl fmt::Display for Disk {
@@ -199,7 +214,7 @@ impl fmt::Display for Disk {
             self.dev_path.display(),
             ByteSize(self.size),
             self.size,
-            self.size / self.sector_size as u64
+            self.sectors_count()
         )?;
         writeln!(fmt, "Disk model: TODO")?; // TODO
         writeln!(
@@ -218,18 +233,22 @@ impl fmt::Display for Disk {
             sector_size = self.sector_size,
         )?;
         writeln!(fmt, "Disklabel type: {}", self.partition_table.table_type)?;
-        writeln!(fmt, "Disk identifier: TODO")?; // TODO
+        if let Some(id) = self.partition_table.id {
+            writeln!(fmt, "Disk identifier: {id}")?;
+        }
         if !self.partition_table.partitions.is_empty() {
             writeln!(fmt, "\nDevice\tStart\tEnd\tSectors\tSize\tType")?;
         }
-        for p in &self.partition_table.partitions {
+        for (n, p) in self.partition_table.iter() {
             writeln!(
                 fmt,
-                "/dev/TODO\t{}\t{}\t{}\t{}\tTODO", // TODO
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                self.partition_path(n).display(),
                 p.start,
-                p.start + p.size,
+                (p.start + p.size).saturating_sub(1),
                 p.size,
-                ByteSize(p.size)
+                ByteSize(p.size * self.sector_size as u64),
+                p.part_type.name().unwrap_or("unknown")
             )?;
         }
         Ok(())
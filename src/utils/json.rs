@@ -0,0 +1,372 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Minimal JSON support, for machine-readable input and output of commands.
+//!
+//! Only non-negative integers are supported as numbers.
+
+use std::fmt;
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// The indentation of pretty-printed values.
+const INDENT: usize = 3;
+/// The maximum nesting depth of arrays and objects when parsing, to avoid overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// `null`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A non-negative integer.
+    Number(u64),
+    /// A string.
+    String(String),
+    /// An array.
+    Array(Vec<Value>),
+    /// An object, whose entries are kept in order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Parses a value.
+    ///
+    /// On error, the function returns the line number and a description.
+    pub fn parse(s: &str) -> Result<Self, (usize, String)> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+            line: 1,
+        };
+        let val = parser.value(0).map_err(|msg| (parser.line, msg))?;
+        parser.skip_whitespaces();
+        if parser.chars.peek().is_some() {
+            return Err((parser.line, "trailing characters".to_owned()));
+        }
+        Ok(val)
+    }
+
+    /// If the value is an object, returns the value associated with `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// If the value is a boolean, returns it.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// If the value is a number, returns it.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// If the value is a string, returns it.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If the value is an array, returns its elements.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Writes the value, indented by `level` levels.
+    fn write(&self, fmt: &mut fmt::Formatter<'_>, level: usize) -> fmt::Result {
+        let indent = (level + 1) * INDENT;
+        match self {
+            Self::Null => write!(fmt, "null"),
+            Self::Bool(b) => write!(fmt, "{b}"),
+            Self::Number(n) => write!(fmt, "{n}"),
+            Self::String(s) => write_string(fmt, s),
+            Self::Array(values) if values.is_empty() => write!(fmt, "[]"),
+            Self::Array(values) => {
+                writeln!(fmt, "[")?;
+                for (i, val) in values.iter().enumerate() {
+                    write!(fmt, "{:indent$}", "")?;
+                    val.write(fmt, level + 1)?;
+                    if i + 1 < values.len() {
+                        write!(fmt, ",")?;
+                    }
+                    writeln!(fmt)?;
+                }
+                write!(fmt, "{:1$}]", "", level * INDENT)
+            }
+            Self::Object(entries) if entries.is_empty() => write!(fmt, "{{}}"),
+            Self::Object(entries) => {
+                writeln!(fmt, "{{")?;
+                for (i, (key, val)) in entries.iter().enumerate() {
+                    write!(fmt, "{:indent$}", "")?;
+                    write_string(fmt, key)?;
+                    write!(fmt, ": ")?;
+                    val.write(fmt, level + 1)?;
+                    if i + 1 < entries.len() {
+                        write!(fmt, ",")?;
+                    }
+                    writeln!(fmt)?;
+                }
+                write!(fmt, "{:1$}}}", "", level * INDENT)
+            }
+        }
+    }
+}
+
+/// Pretty-prints the value.
+impl fmt::Display for Value {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(fmt, 0)
+    }
+}
+
+/// Writes `s` as a JSON string, with quotes and escapes.
+fn write_string(fmt: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    fmt.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => fmt.write_str("\\\"")?,
+            '\\' => fmt.write_str("\\\\")?,
+            '\n' => fmt.write_str("\\n")?,
+            '\r' => fmt.write_str("\\r")?,
+            '\t' => fmt.write_str("\\t")?,
+            c if c.is_control() => write!(fmt, "\\u{:04x}", c as u32)?,
+            c => fmt.write_char(c)?,
+        }
+    }
+    fmt.write_char('"')
+}
+
+/// A JSON parser.
+struct Parser<'s> {
+    /// The remaining characters.
+    chars: Peekable<Chars<'s>>,
+    /// The current line number.
+    line: usize,
+}
+
+impl Parser<'_> {
+    /// Consumes the next character.
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skips whitespaces.
+    fn skip_whitespaces(&mut self) {
+        while let Some(c) = self.chars.next_if(char::is_ascii_whitespace) {
+            if c == '\n' {
+                self.line += 1;
+            }
+        }
+    }
+
+    /// Consumes the next non-whitespace character, which must be `expected`.
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespaces();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}`, found `{c}`")),
+            None => Err(format!("expected `{expected}`, found end of input")),
+        }
+    }
+
+    /// Consumes the keyword `word`, whose first character has already been consumed.
+    fn keyword(&mut self, word: &str, val: Value) -> Result<Value, String> {
+        for expected in word.chars().skip(1) {
+            if self.next() != Some(expected) {
+                return Err(format!("invalid keyword, expected `{word}`"));
+            }
+        }
+        Ok(val)
+    }
+
+    /// Parses a value, nested in `depth` arrays or objects.
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        self.skip_whitespaces();
+        if depth >= MAX_DEPTH && matches!(self.chars.peek(), Some('[' | '{')) {
+            return Err("too deeply nested".to_owned());
+        }
+        match self.next() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                let mut values = vec![];
+                self.skip_whitespaces();
+                if self.chars.next_if_eq(&']').is_some() {
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth + 1)?);
+                    self.skip_whitespaces();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some(']') => break,
+                        _ => return Err("expected `,` or `]`".to_owned()),
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            Some('{') => {
+                let mut entries = vec![];
+                self.skip_whitespaces();
+                if self.chars.next_if_eq(&'}').is_some() {
+                    return Ok(Value::Object(entries));
+                }
+                loop {
+                    self.expect('"')?;
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    entries.push((key, self.value(depth + 1)?));
+                    self.skip_whitespaces();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some('}') => break,
+                        _ => return Err("expected `,` or `}`".to_owned()),
+                    }
+                }
+                Ok(Value::Object(entries))
+            }
+            Some(c @ '0'..='9') => {
+                let mut n = c.to_digit(10).unwrap() as u64;
+                while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+                    n = n
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(c.to_digit(10).unwrap() as u64))
+                        .ok_or_else(|| "number too large".to_owned())?;
+                }
+                if self
+                    .chars
+                    .next_if(|c| matches!(c, '.' | 'e' | 'E'))
+                    .is_some()
+                {
+                    return Err("only integers are supported".to_owned());
+                }
+                Ok(Value::Number(n))
+            }
+            Some('-') => Err("negative numbers are not supported".to_owned()),
+            Some(c) => Err(format!("unexpected character `{c}`")),
+            None => Err("unexpected end of input".to_owned()),
+        }
+    }
+
+    /// Parses the four hexadecimal digits of a `\u` escape.
+    fn hex4(&mut self) -> Result<u32, String> {
+        (0..4).try_fold(0, |n, _| {
+            self.next()
+                .and_then(|c| c.to_digit(16))
+                .map(|d| n * 16 + d)
+                .ok_or_else(|| "invalid unicode escape".to_owned())
+        })
+    }
+
+    /// Parses a string, whose opening quote has already been consumed.
+    fn string(&mut self) -> Result<String, String> {
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\x08',
+                        Some('f') => '\x0c',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pair
+                            if (0xd800..0xdc00).contains(&code) {
+                                if self.next() != Some('\\') || self.next() != Some('u') {
+                                    return Err("invalid unicode escape".to_owned());
+                                }
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err("invalid unicode escape".to_owned());
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| "invalid unicode escape".to_owned())?
+                        }
+                        _ => return Err("invalid escape sequence".to_owned()),
+                    };
+                    s.push(c);
+                }
+                Some(c) if c.is_control() => {
+                    return Err("control character in string".to_owned());
+                }
+                Some(c) => s.push(c),
+                None => return Err("unterminated string".to_owned()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json_parse() {
+        let val = Value::parse(
+            "{\n  \"a\": [1, true, null],\n  \"b\": \"x\\\"\\u00e9\\ud83d\\ude00\",\n  \"c\": {}\n}",
+        )
+        .unwrap();
+        assert_eq!(
+            val.get("a").unwrap().as_array().unwrap()[0].as_u64(),
+            Some(1)
+        );
+        assert_eq!(val.get("b").unwrap().as_str(), Some("x\"é😀"));
+        assert_eq!(Value::parse(&val.to_string()).unwrap(), val);
+        // Errors carry the line number
+        assert_eq!(Value::parse("{\n\"a\": 1.5}").unwrap_err().0, 2);
+        assert_eq!(Value::parse("[1, 2").unwrap_err().0, 1);
+        assert!(Value::parse("{} x").is_err());
+        assert!(Value::parse("\"\\ud83d\"").is_err());
+        // Nesting is limited
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Value::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Value::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Value::parse(&"[".repeat(1 << 20)).is_err());
+    }
+}
@@ -23,11 +23,16 @@ use std::path::PathBuf;
 use std::process::exit;
 use std::{env, fmt};
 
+pub mod auth;
 pub mod crc32;
 pub mod disk;
 pub mod fhs;
+pub mod json;
+pub mod nss;
 pub mod partition;
 pub mod prompt;
+pub mod pwquality;
+pub mod subid;
 pub mod user;
 pub mod util;
 
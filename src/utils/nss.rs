@@ -0,0 +1,339 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Name service switch.
+//!
+//! [`NSSWITCH_PATH`] tells, for each database, the list of sources to look entries up in, in
+//! order. Lines have the form `database: source...`, with `#` starting comments. Actions in
+//! brackets and unknown sources are ignored.
+//!
+//! Supported sources:
+//! - `files` (or `compat`): the files in `/etc`
+//! - `extrausers`: the files in [`EXTRAUSERS_PATH`]
+//!
+//! A database that is not configured uses `files` only.
+
+use crate::user::{Group, Shadow, User, root_path};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// The path to the name service switch configuration file.
+pub const NSSWITCH_PATH: &str = "/etc/nsswitch.conf";
+/// The path to the directory containing the files of the `extrausers` source.
+pub const EXTRAUSERS_PATH: &str = "/var/lib/extrausers";
+
+/// A database.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Database {
+    /// Users.
+    Passwd,
+    /// Groups.
+    Group,
+    /// Users' passwords.
+    Shadow,
+}
+
+impl Database {
+    /// Returns the name of the database, which is also the name of its files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Passwd => "passwd",
+            Self::Group => "group",
+            Self::Shadow => "shadow",
+        }
+    }
+}
+
+/// A source of entries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Source {
+    /// The files in `/etc`.
+    Files,
+    /// The files in [`EXTRAUSERS_PATH`].
+    ExtraUsers,
+}
+
+impl Source {
+    /// Returns the source with the given name, if supported.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "files" | "compat" => Some(Self::Files),
+            "extrausers" => Some(Self::ExtraUsers),
+            _ => None,
+        }
+    }
+
+    /// Returns the absolute path of the file holding `db` for the source.
+    pub fn path(self, db: Database) -> String {
+        let dir = match self {
+            Self::Files => "/etc",
+            Self::ExtraUsers => EXTRAUSERS_PATH,
+        };
+        format!("{dir}/{}", db.name())
+    }
+}
+
+/// The name service switch configuration.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Sources for the passwd database.
+    pub passwd: Vec<Source>,
+    /// Sources for the group database.
+    pub group: Vec<Source>,
+    /// Sources for the shadow database.
+    pub shadow: Vec<Source>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            passwd: vec![Source::Files],
+            group: vec![Source::Files],
+            shadow: vec![Source::Files],
+        }
+    }
+}
+
+impl Config {
+    /// Parses the configuration from the content of a configuration file.
+    pub fn parse(buf: &str) -> Self {
+        let mut config = Self::default();
+        for line in buf.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((db, sources)) = line.split_once(':') else {
+                continue;
+            };
+            let list = match db.trim() {
+                "passwd" => &mut config.passwd,
+                "group" => &mut config.group,
+                "shadow" => &mut config.shadow,
+                _ => continue,
+            };
+            let sources: Vec<_> = sources
+                .split_whitespace()
+                .filter_map(Source::from_name)
+                .collect();
+            // Keep the default if no source is supported
+            if !sources.is_empty() {
+                *list = sources;
+            }
+        }
+        config
+    }
+
+    /// Returns the sources for the database `db`, in order.
+    pub fn sources(&self, db: Database) -> &[Source] {
+        match db {
+            Database::Passwd => &self.passwd,
+            Database::Group => &self.group,
+            Database::Shadow => &self.shadow,
+        }
+    }
+}
+
+/// Reads the file at `path`. If the file does not exist, the function returns an empty string.
+fn read_optional(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(buff) => Ok(buff),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The name service switch, giving access to the databases of a system.
+pub struct Nss {
+    /// The root directory of the system.
+    root: PathBuf,
+    /// The configuration.
+    config: Config,
+}
+
+impl Nss {
+    /// Loads the configuration of the current system.
+    pub fn load() -> io::Result<Self> {
+        Self::load_in(Path::new("/"))
+    }
+
+    /// Loads the configuration of the system whose root directory is at `root`.
+    ///
+    /// Sources are read relative to `root` too.
+    pub fn load_in(root: &Path) -> io::Result<Self> {
+        let buf = read_optional(&root_path(root, NSSWITCH_PATH))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            config: Config::parse(&buf),
+        })
+    }
+
+    /// Returns the databases of the system whose root directory is at `root`, using only the
+    /// files in `/etc` whatever the configuration.
+    pub fn files_in(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            config: Config::default(),
+        }
+    }
+
+    /// Reads the content of all the sources of `db`, in order.
+    ///
+    /// Missing files are treated as empty.
+    fn read(&self, db: Database) -> io::Result<Vec<(Source, String)>> {
+        self.config
+            .sources(db)
+            .iter()
+            .map(|src| Ok((*src, read_optional(&root_path(&self.root, &src.path(db)))?)))
+            .collect()
+    }
+
+    /// Reads the passwd database.
+    pub fn passwd(&self) -> io::Result<Passwd> {
+        self.read(Database::Passwd).map(Passwd)
+    }
+
+    /// Reads the group database.
+    pub fn group(&self) -> io::Result<Groups> {
+        self.read(Database::Group).map(Groups)
+    }
+
+    /// Reads the shadow database.
+    pub fn shadow(&self) -> io::Result<Shadows> {
+        self.read(Database::Shadow).map(Shadows)
+    }
+}
+
+/// The content of the passwd database.
+pub struct Passwd(Vec<(Source, String)>);
+
+impl Passwd {
+    /// Returns an iterator over the valid entries, in the order of the sources.
+    pub fn iter(&self) -> impl Iterator<Item = User<'_>> {
+        self.0
+            .iter()
+            .flat_map(|(_, buf)| User::deserialize(buf).filter_map(Result::ok))
+    }
+
+    /// Returns the first user with the login name `name`.
+    pub fn by_name(&self, name: &str) -> Option<User<'_>> {
+        self.iter().find(|u| u.login_name == name)
+    }
+
+    /// Returns the source of the first user with the login name `name`.
+    pub fn source_of(&self, name: &str) -> Option<Source> {
+        self.0
+            .iter()
+            .find(|(_, buf)| {
+                User::deserialize(buf)
+                    .filter_map(Result::ok)
+                    .any(|u| u.login_name == name)
+            })
+            .map(|(src, _)| *src)
+    }
+
+    /// Returns the first user with the ID `uid`.
+    pub fn by_uid(&self, uid: u32) -> Option<User<'_>> {
+        self.iter().find(|u| u.uid == uid)
+    }
+}
+
+/// The content of the group database.
+pub struct Groups(Vec<(Source, String)>);
+
+impl Groups {
+    /// Returns an iterator over the valid entries, in the order of the sources.
+    pub fn iter(&self) -> impl Iterator<Item = Group<'_>> {
+        self.0
+            .iter()
+            .flat_map(|(_, buf)| Group::deserialize(buf).filter_map(Result::ok))
+    }
+
+    /// Returns the first group with the name `name`.
+    pub fn by_name(&self, name: &str) -> Option<Group<'_>> {
+        self.iter().find(|g| g.group_name == name)
+    }
+
+    /// Returns the first group with the ID `gid`.
+    pub fn by_gid(&self, gid: u32) -> Option<Group<'_>> {
+        self.iter().find(|g| g.gid == gid)
+    }
+}
+
+/// The content of the shadow database.
+pub struct Shadows(Vec<(Source, String)>);
+
+impl Shadows {
+    /// Returns an iterator over the valid entries, in the order of the sources.
+    pub fn iter(&self) -> impl Iterator<Item = Shadow<'_>> {
+        self.0
+            .iter()
+            .flat_map(|(_, buf)| Shadow::deserialize(buf).filter_map(Result::ok))
+    }
+
+    /// Returns the first entry for the user with the login name `name`.
+    pub fn by_name(&self, name: &str) -> Option<Shadow<'_>> {
+        self.iter().find(|s| s.login_name == name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn nsswitch_parse() {
+        let config = Config::parse(
+            "# comment\npasswd: files extrausers\ngroup:  extrausers [NOTFOUND=return] files\n\
+             shadow: sss\nhosts: files dns\n",
+        );
+        assert_eq!(config.passwd, [Source::Files, Source::ExtraUsers]);
+        assert_eq!(config.group, [Source::ExtraUsers, Source::Files]);
+        // No supported source
+        assert_eq!(config.shadow, [Source::Files]);
+        assert_eq!(Config::parse(""), Config::default());
+    }
+
+    #[test]
+    fn nss_chain() {
+        let root = std::env::temp_dir().join(format!("mutils-nss-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("etc")).unwrap();
+        fs::create_dir_all(root.join("var/lib/extrausers")).unwrap();
+        fs::write(root.join("etc/nsswitch.conf"), "passwd: files extrausers\n").unwrap();
+        fs::write(root.join("etc/passwd"), "root:x:0:0::/root:/bin/sh\n").unwrap();
+        fs::write(
+            root.join("var/lib/extrausers/passwd"),
+            "root:x:1:1::/:\nalice:x:1000:1000::/home/alice:/bin/sh\n",
+        )
+        .unwrap();
+        let nss = Nss::load_in(&root).unwrap();
+        let passwd = nss.passwd().unwrap();
+        assert_eq!(passwd.iter().count(), 3);
+        // The first source wins
+        assert_eq!(passwd.by_name("root").unwrap().uid, 0);
+        assert_eq!(passwd.by_uid(1000).unwrap().login_name, "alice");
+        assert_eq!(passwd.source_of("alice"), Some(Source::ExtraUsers));
+        assert_eq!(passwd.source_of("root"), Some(Source::Files));
+        // Only the files in `/etc`
+        let passwd = Nss::files_in(&root).passwd().unwrap();
+        assert!(passwd.by_name("alice").is_none());
+        // Missing files are empty
+        assert_eq!(nss.group().unwrap().iter().count(), 0);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
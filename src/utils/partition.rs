@@ -19,24 +19,23 @@
 //! Partition tables handling utilities.
 
 use super::crc32;
-use super::prompt::prompt;
+use super::json::Value;
+use super::prompt::{choice, end_in_range, number_in_range, parse_size};
 use std::cmp::max;
 use std::cmp::min;
 use std::fmt;
-use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
 use std::io::Write;
 use std::mem::size_of;
-use std::path::Path;
+use std::ops::BitOr;
+use std::path::{Path, PathBuf};
 use std::slice;
 use std::str::FromStr;
 use uuid::Uuid;
 
-// TODO adapt to disks whose sector size is different than 512
-
 /// The signature of the MBR partition table.
 const MBR_SIGNATURE: u16 = 0xaa55;
 
@@ -44,6 +43,34 @@ const MBR_SIGNATURE: u16 = 0xaa55;
 const GPT_SIGNATURE: &[u8] = b"EFI PART";
 /// The polynom used in the computation of the CRC32 checksum.
 const GPT_CHECKSUM_POLYNOM: u32 = 0xedb88320;
+/// The maximum size of the GPT entries array in bytes, to avoid allocating too much memory for
+/// corrupt headers.
+const GPT_ENTRIES_MAX_SIZE: usize = 1 << 20;
+
+/// Returns the path to the device file of the partition with the given number, starting at `1`,
+/// on the disk whose device file is at `dev`.
+///
+/// If the name of the disk ends with a digit, a `p` is inserted before the number, as for
+/// `/dev/nvme0n1p1`.
+pub fn partition_path(dev: &Path, n: usize) -> PathBuf {
+    let mut path = dev.as_os_str().to_owned();
+    if path
+        .as_encoded_bytes()
+        .last()
+        .is_some_and(u8::is_ascii_digit)
+    {
+        path.push("p");
+    }
+    path.push(n.to_string());
+    path.into()
+}
+
+/// Returns the number of the partition whose device file is at `path`, as the digits at the end
+/// of the path. This is the inverse of [`partition_path`].
+fn partition_number(path: &str) -> Option<usize> {
+    let digits = path.len() - path.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    path[path.len() - digits..].parse().ok().filter(|n| *n > 0)
+}
 
 /// Translates the given LBA value `lba` into a positive LBA value.
 ///
@@ -58,6 +85,664 @@ fn translate_lba(lba: i64, storage_size: u64) -> Option<u64> {
     }
 }
 
+/// Known MBR partition types, with their names.
+const MBR_TYPES: &[(u8, &str)] = &[
+    (0x00, "Empty"),
+    (0x01, "FAT12"),
+    (0x02, "XENIX root"),
+    (0x03, "XENIX usr"),
+    (0x04, "FAT16 <32M"),
+    (0x05, "Extended"),
+    (0x06, "FAT16"),
+    (0x07, "HPFS/NTFS/exFAT"),
+    (0x08, "AIX"),
+    (0x09, "AIX bootable"),
+    (0x0a, "OS/2 Boot Manager"),
+    (0x0b, "W95 FAT32"),
+    (0x0c, "W95 FAT32 (LBA)"),
+    (0x0e, "W95 FAT16 (LBA)"),
+    (0x0f, "W95 Ext'd (LBA)"),
+    (0x10, "OPUS"),
+    (0x11, "Hidden FAT12"),
+    (0x12, "Compaq diagnostics"),
+    (0x14, "Hidden FAT16 <3"),
+    (0x16, "Hidden FAT16"),
+    (0x17, "Hidden HPFS/NTFS"),
+    (0x18, "AST SmartSleep"),
+    (0x1b, "Hidden W95 FAT3"),
+    (0x1c, "Hidden W95 FAT3"),
+    (0x1e, "Hidden W95 FAT1"),
+    (0x24, "NEC DOS"),
+    (0x27, "Hidden NTFS Win"),
+    (0x39, "Plan 9"),
+    (0x3c, "PartitionMagic"),
+    (0x40, "Venix 80286"),
+    (0x41, "PPC PReP Boot"),
+    (0x42, "SFS"),
+    (0x4d, "QNX4.x"),
+    (0x4e, "QNX4.x 2nd part"),
+    (0x4f, "QNX4.x 3rd part"),
+    (0x50, "OnTrack DM"),
+    (0x51, "OnTrack DM6 Aux"),
+    (0x52, "CP/M"),
+    (0x53, "OnTrack DM6 Aux"),
+    (0x54, "OnTrackDM6"),
+    (0x55, "EZ-Drive"),
+    (0x56, "Golden Bow"),
+    (0x5c, "Priam Edisk"),
+    (0x61, "SpeedStor"),
+    (0x63, "GNU HURD or Sys"),
+    (0x64, "Novell Netware"),
+    (0x65, "Novell Netware"),
+    (0x70, "DiskSecure Mult"),
+    (0x75, "PC/IX"),
+    (0x80, "Old Minix"),
+    (0x81, "Minix / old Linux"),
+    (0x82, "Linux swap / Solaris"),
+    (0x83, "Linux"),
+    (0x84, "OS/2 hidden"),
+    (0x85, "Linux extended"),
+    (0x86, "NTFS volume set"),
+    (0x87, "NTFS volume set"),
+    (0x88, "Linux plaintext"),
+    (0x8e, "Linux LVM"),
+    (0x93, "Amoeba"),
+    (0x94, "Amoeba BBT"),
+    (0x9f, "BSD/OS"),
+    (0xa0, "IBM Thinkpad"),
+    (0xa5, "FreeBSD"),
+    (0xa6, "OpenBSD"),
+    (0xa7, "NeXTSTEP"),
+    (0xa8, "Darwin UFS"),
+    (0xa9, "NetBSD"),
+    (0xab, "Darwin boot"),
+    (0xaf, "HFS / HFS+"),
+    (0xb7, "BSDI fs"),
+    (0xb8, "BSDI swap"),
+    (0xbb, "Boot Wizard hidden"),
+    (0xbc, "Acronis FAT32"),
+    (0xbe, "Solaris boot"),
+    (0xbf, "Solaris"),
+    (0xc1, "DRDOS/sec"),
+    (0xc4, "DRDOS/sec"),
+    (0xc6, "DRDOS/sec"),
+    (0xc7, "Syrinx"),
+    (0xda, "Non-FS data"),
+    (0xdb, "CP/M / CTOS / ."),
+    (0xde, "Dell Utility"),
+    (0xdf, "BootIt"),
+    (0xe0, "ST AVFS"),
+    (0xe1, "DOS access"),
+    (0xe3, "DOS R/O"),
+    (0xe4, "SpeedStor"),
+    (0xea, "Linux extended"),
+    (0xeb, "BeOS fs"),
+    (0xee, "GPT"),
+    (0xef, "EFI (FAT-12/16/32)"),
+    (0xf0, "Linux/PA-RISC bootloader"),
+    (0xf1, "SpeedStor"),
+    (0xf2, "DOS secondary"),
+    (0xf4, "SpeedStor"),
+    (0xf8, "EBBR protective"),
+    (0xfb, "VMware VMFS"),
+    (0xfc, "VMware VMKCORE"),
+    (0xfd, "Linux raid auto"),
+    (0xfe, "LANstep"),
+    (0xff, "BBT"),
+];
+
+/// Known GPT partition types, with their names.
+const GPT_TYPES: &[(&str, &str)] = &[
+    ("EFI System", "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"),
+    (
+        "MBR partition scheme",
+        "024dee41-33e7-11d3-9d69-0008c781f39f",
+    ),
+    ("Intel Fast Flash", "d3bfe2de-3daf-11df-ba40-e3a556d89593"),
+    ("BIOS boot", "21686148-6449-6e6f-744e-656564454649"),
+    (
+        "Sony boot partition",
+        "f4019732-066e-4e12-8273-346c5641494f",
+    ),
+    (
+        "Lenovo boot partition",
+        "bfbfafe7-a34f-448a-9a5b-6213eb736c22",
+    ),
+    ("PowerPC PReP boot", "9e1a2d38-c612-4316-aa26-8b49521e5a8b"),
+    ("ONIE boot", "7412f7d5-a156-4b13-81dc-867174929325"),
+    ("ONIE config", "d4e6e2cd-4469-46f3-b5cb-1bff57afc149"),
+    ("Microsoft reserved", "e3c9e316-0b5c-4db8-817d-f92df00215ae"),
+    (
+        "Microsoft basic data",
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+    ),
+    (
+        "Microsoft LDM metadata",
+        "5808c8aa-7e8f-42e0-85d2-e1e90434cfb3",
+    ),
+    ("Microsoft LDM data", "af9b60a0-1431-4f62-bc68-3311714a69ad"),
+    (
+        "Windows recovery environment",
+        "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
+    ),
+    (
+        "IBM General Parallel Fs",
+        "37affc90-ef7d-4e96-91c3-2d7ae055b174",
+    ),
+    (
+        "Microsoft Storage Spaces",
+        "e75caf8f-f680-4cee-afa3-b001e56efc2d",
+    ),
+    ("HP-UX data", "75894c1e-3aeb-11d3-b7c1-7b03a0000000"),
+    ("HP-UX service", "e2a1e728-32e3-11d6-a682-7b03a0000000"),
+    ("Linux swap", "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"),
+    ("Linux filesystem", "0fc63daf-8483-4772-8e79-3d69d8477de4"),
+    ("Linux server data", "3b8f8425-20e0-4f3b-907f-1a25a76f98e8"),
+    ("Linux root (x86)", "44479540-f297-41b2-9af7-d131d5f0458a"),
+    (
+        "Linux root (x86-64)",
+        "4f68bce3-e8cd-4db1-96e7-fbcaf984b709",
+    ),
+    ("Linux root (Alpha)", "6523f8ae-3eb1-4e2a-a05a-18b695ae656f"),
+    ("Linux root (ARC)", "d27f46ed-2919-4cb8-bd25-9531f3c16534"),
+    ("Linux root (ARM)", "69dad710-2ce4-4e3c-b16c-21a1d49abed3"),
+    (
+        "Linux root (ARM-64)",
+        "b921b045-1df0-41c3-af44-4c6f280d3fae",
+    ),
+    ("Linux root (IA-64)", "993d8d3d-f80e-4225-855a-9daf8ed7ea97"),
+    (
+        "Linux root (LoongArch-64)",
+        "77055800-792c-4f94-b39a-98c91b762bb6",
+    ),
+    (
+        "Linux root (MIPS-32 LE)",
+        "37c58c8a-d913-4156-a25f-48b1b64e07f0",
+    ),
+    (
+        "Linux root (MIPS-64 LE)",
+        "700bda43-7a34-4507-b179-eeb93d7a7ca3",
+    ),
+    ("Linux root (PPC)", "1de3f1ef-fa98-47b5-8dcd-4a860a654d78"),
+    ("Linux root (PPC64)", "912ade1d-a839-4913-8964-a10eee08fbd2"),
+    (
+        "Linux root (PPC64LE)",
+        "c31c45e6-3f39-412e-80fb-4809c4980599",
+    ),
+    (
+        "Linux root (RISC-V-32)",
+        "60d5a7fe-8e7d-435c-b714-3dd8162144e1",
+    ),
+    (
+        "Linux root (RISC-V-64)",
+        "72ec70a6-cf74-40e6-bd49-4bda08e8f224",
+    ),
+    ("Linux root (S390)", "08a7acea-624c-4a20-91e8-6e0fa67d23f9"),
+    ("Linux root (S390X)", "5eead9a9-fe09-4a1e-a1d7-520d00531306"),
+    (
+        "Linux root (TILE-Gx)",
+        "c50cdd70-3862-4cc3-90e1-809a8c93ee2c",
+    ),
+    ("Linux reserved", "8da63339-0007-60c0-c436-083ac8230908"),
+    ("Linux home", "933ac7e1-2eb4-4f13-b844-0e14e2aef915"),
+    ("Linux RAID", "a19d880f-05fc-4d3b-a006-743f0f84911e"),
+    ("Linux LVM", "e6d6d379-f507-44c2-a23c-238f2a3df928"),
+    (
+        "Linux variable data",
+        "4d21b016-b534-45c2-a9fb-5c16e091fd2d",
+    ),
+    (
+        "Linux temporary data",
+        "7ec6f557-3bc5-4aca-b293-16ef5df639d1",
+    ),
+    ("Linux /usr (x86)", "75250d76-8cc6-458e-bd66-bd47cc81a812"),
+    (
+        "Linux /usr (x86-64)",
+        "8484680c-9521-48c6-9c11-b0720656f69e",
+    ),
+    ("Linux /usr (Alpha)", "e18cf08c-33ec-4c0d-8246-c6c6fb3da024"),
+    ("Linux /usr (ARC)", "7978a683-6316-4922-bbee-38bff5a2fecc"),
+    ("Linux /usr (ARM)", "7d0359a3-02b3-4f0a-865c-654403e70625"),
+    (
+        "Linux /usr (ARM-64)",
+        "b0e01050-ee5f-4390-949a-9101b17104e9",
+    ),
+    ("Linux /usr (IA-64)", "4301d2a6-4e3b-4b2a-bb94-9e0b2c4225ea"),
+    (
+        "Linux /usr (LoongArch-64)",
+        "e611c702-575c-4cbe-9a46-434fa0bf7e3f",
+    ),
+    (
+        "Linux /usr (MIPS-32 LE)",
+        "0f4868e9-9952-4706-979f-3ed3a473e947",
+    ),
+    (
+        "Linux /usr (MIPS-64 LE)",
+        "c97c1f32-ba06-40b4-9f22-236061b08aa8",
+    ),
+    ("Linux /usr (PPC)", "7d14fec5-cc71-415d-9d6c-06bf0b3c3eaf"),
+    ("Linux /usr (PPC64)", "2c9739e2-f068-46b3-9fd0-01c5a9afbcca"),
+    (
+        "Linux /usr (PPC64LE)",
+        "15bb03af-77e7-4d4a-b12b-c0d084f7491c",
+    ),
+    (
+        "Linux /usr (RISC-V-32)",
+        "b933fb22-5c3f-4f91-af90-e2bb0fa50702",
+    ),
+    (
+        "Linux /usr (RISC-V-64)",
+        "beaec34b-8442-439b-a40b-984381ed097d",
+    ),
+    ("Linux /usr (S390)", "cd0f869b-d0fb-4ca0-b141-9ea87cc78d66"),
+    ("Linux /usr (S390X)", "8a4f5770-50aa-4ed3-874a-99b710db6fea"),
+    (
+        "Linux /usr (TILE-Gx)",
+        "55497029-c7c1-44cc-aa39-815ed1558630",
+    ),
+    (
+        "Linux root verity (x86)",
+        "d13c5d3b-b5d1-422a-b29f-9454fdc89d76",
+    ),
+    (
+        "Linux root verity (x86-64)",
+        "2c7357ed-ebd2-46d9-aec1-23d437ec2bf5",
+    ),
+    (
+        "Linux root verity (Alpha)",
+        "fc56d9e9-e6e5-4c06-be32-e74407ce09a5",
+    ),
+    (
+        "Linux root verity (ARC)",
+        "24b2d975-0f97-4521-afa1-cd531e421b8d",
+    ),
+    (
+        "Linux root verity (ARM)",
+        "7386cdf2-203c-47a9-a498-f2ecce45a2d6",
+    ),
+    (
+        "Linux root verity (ARM-64)",
+        "df3300ce-d69f-4c92-978c-9bfb0f38d820",
+    ),
+    (
+        "Linux root verity (IA-64)",
+        "86ed10d5-b607-45bb-8957-d350f23d0571",
+    ),
+    (
+        "Linux root verity (LoongArch-64)",
+        "f3393b22-e9af-4613-a948-9d3bfbd0c535",
+    ),
+    (
+        "Linux root verity (MIPS-32 LE)",
+        "d7d150d2-2a04-4a33-8f12-16651205ff7b",
+    ),
+    (
+        "Linux root verity (MIPS-64 LE)",
+        "16b417f8-3e06-4f57-8dd2-9b5232f41aa6",
+    ),
+    (
+        "Linux root verity (PPC)",
+        "98cfe649-1588-46dc-b2f0-add147424925",
+    ),
+    (
+        "Linux root verity (PPC64)",
+        "9225a9a3-3c19-4d89-b4f6-eeff88f17631",
+    ),
+    (
+        "Linux root verity (PPC64LE)",
+        "906bd944-4589-4aae-a4e4-dd983917446a",
+    ),
+    (
+        "Linux root verity (RISC-V-32)",
+        "ae0253be-1167-4007-ac68-43926c14c5de",
+    ),
+    (
+        "Linux root verity (RISC-V-64)",
+        "b6ed5582-440b-4209-b8da-5ff7c419ea3d",
+    ),
+    (
+        "Linux root verity (S390)",
+        "7ac63b47-b25c-463b-8df8-b4a94e6c90e1",
+    ),
+    (
+        "Linux root verity (S390X)",
+        "b325bfbe-c7be-4ab8-8357-139e652d2f6b",
+    ),
+    (
+        "Linux root verity (TILE-Gx)",
+        "966061ec-28e4-4b2e-b4a5-1f0a825a1d84",
+    ),
+    (
+        "Linux /usr verity (x86)",
+        "8f461b0d-14ee-4e81-9aa9-049b6fb97abd",
+    ),
+    (
+        "Linux /usr verity (x86-64)",
+        "77ff5f63-e7b6-4633-acf4-1565b864c0e6",
+    ),
+    (
+        "Linux /usr verity (Alpha)",
+        "8cce0d25-c0d0-4a44-bd87-46331bf1df67",
+    ),
+    (
+        "Linux /usr verity (ARC)",
+        "fca0598c-d880-4591-8c16-4eda05c7347c",
+    ),
+    (
+        "Linux /usr verity (ARM)",
+        "c215d751-7bcd-4649-be90-6627490a4c05",
+    ),
+    (
+        "Linux /usr verity (ARM-64)",
+        "6e11a4e7-fbca-4ded-b9e9-e1a512bb664e",
+    ),
+    (
+        "Linux /usr verity (IA-64)",
+        "6a491e03-3be7-4545-8e38-83320e0ea880",
+    ),
+    (
+        "Linux /usr verity (LoongArch-64)",
+        "f46b2c26-59ae-48f0-9106-c50ed47f673d",
+    ),
+    (
+        "Linux /usr verity (MIPS-32 LE)",
+        "46b98d8d-b55c-4e8f-aab3-37fca7f80752",
+    ),
+    (
+        "Linux /usr verity (MIPS-64 LE)",
+        "3c3d61fe-b5f3-414d-bb71-8739a694a4ef",
+    ),
+    (
+        "Linux /usr verity (PPC)",
+        "df765d00-270e-49e5-bc75-f47bb2118b09",
+    ),
+    (
+        "Linux /usr verity (PPC64)",
+        "bdb528a5-a259-475f-a87d-da53fa736a07",
+    ),
+    (
+        "Linux /usr verity (PPC64LE)",
+        "ee2b9983-21e8-4153-86d9-b6901a54d1ce",
+    ),
+    (
+        "Linux /usr verity (RISC-V-32)",
+        "cb1ee4e3-8cd0-4136-a0a4-aa61a32e8730",
+    ),
+    (
+        "Linux /usr verity (RISC-V-64)",
+        "8f1056be-9b05-47c4-81d6-be53128e5b54",
+    ),
+    (
+        "Linux /usr verity (S390)",
+        "b663c618-e7bc-4d6d-90aa-11b756bb1797",
+    ),
+    (
+        "Linux /usr verity (S390X)",
+        "31741cc4-1a2a-4111-a581-e00b447d2d06",
+    ),
+    (
+        "Linux /usr verity (TILE-Gx)",
+        "2fb4bf56-07fa-42da-8132-6b139f2026ae",
+    ),
+    (
+        "Linux root verity sign. (x86)",
+        "5996fc05-109c-48de-808b-23fa0830b676",
+    ),
+    (
+        "Linux root verity sign. (x86-64)",
+        "41092b05-9fc8-4523-994f-2def0408b176",
+    ),
+    (
+        "Linux root verity sign. (Alpha)",
+        "d46495b7-a053-414f-80f7-700c99921ef8",
+    ),
+    (
+        "Linux root verity sign. (ARC)",
+        "143a70ba-cbd3-4f06-919f-6c05683a78bc",
+    ),
+    (
+        "Linux root verity sign. (ARM)",
+        "42b0455f-eb11-491d-98d3-56145ba9d037",
+    ),
+    (
+        "Linux root verity sign. (ARM-64)",
+        "6db69de6-29f4-4758-a7a5-962190f00ce3",
+    ),
+    (
+        "Linux root verity sign. (IA-64)",
+        "e98b36ee-32ba-4882-9b12-0ce14655f46a",
+    ),
+    (
+        "Linux root verity sign. (LoongArch-64)",
+        "5afb67eb-ecc8-4f85-ae8e-ac1e7c50e7d0",
+    ),
+    (
+        "Linux root verity sign. (MIPS-32 LE)",
+        "c919cc1f-4456-4eff-918c-f75e94525ca5",
+    ),
+    (
+        "Linux root verity sign. (MIPS-64 LE)",
+        "904e58ef-5c65-4a31-9c57-6af5fc7c5de7",
+    ),
+    (
+        "Linux root verity sign. (PPC)",
+        "1b31b5aa-add9-463a-b2ed-bd467fc857e7",
+    ),
+    (
+        "Linux root verity sign. (PPC64)",
+        "f5e2c20c-45b2-4ffa-bce9-2a60737e1aaf",
+    ),
+    (
+        "Linux root verity sign. (PPC64LE)",
+        "d4a236e7-e873-4c07-bf1d-bf6cf7f1c3c6",
+    ),
+    (
+        "Linux root verity sign. (RISC-V-32)",
+        "3a112a75-8729-4380-b4cf-764d79934448",
+    ),
+    (
+        "Linux root verity sign. (RISC-V-64)",
+        "efe0f087-ea8d-4469-821a-4c2a96a8386a",
+    ),
+    (
+        "Linux root verity sign. (S390)",
+        "3482388e-4254-435a-a241-766a065f9960",
+    ),
+    (
+        "Linux root verity sign. (S390X)",
+        "c80187a5-73a3-491a-901a-017c3fa953e9",
+    ),
+    (
+        "Linux root verity sign. (TILE-Gx)",
+        "b3671439-97b0-4a53-90f7-2d5a8f3ad47b",
+    ),
+    (
+        "Linux /usr verity sign. (x86)",
+        "974a71c0-de41-43c3-be5d-5c5ccd1ad2c0",
+    ),
+    (
+        "Linux /usr verity sign. (x86-64)",
+        "e7bb33fb-06cf-4e81-8273-e543b413e2e2",
+    ),
+    (
+        "Linux /usr verity sign. (Alpha)",
+        "5c6e1c76-076a-457a-a0fe-f3b4cd21ce6e",
+    ),
+    (
+        "Linux /usr verity sign. (ARC)",
+        "94f9a9a1-9971-427a-a400-50cb297f0f35",
+    ),
+    (
+        "Linux /usr verity sign. (ARM)",
+        "d7ff812f-37d1-4902-a810-d76ba57b975a",
+    ),
+    (
+        "Linux /usr verity sign. (ARM-64)",
+        "c23ce4ff-44bd-4b00-b2d4-b41b3419e02a",
+    ),
+    (
+        "Linux /usr verity sign. (IA-64)",
+        "8de58bc2-2a43-460d-b14e-a76e4a17b47f",
+    ),
+    (
+        "Linux /usr verity sign. (LoongArch-64)",
+        "b024f315-d330-444c-8461-44bbde524e99",
+    ),
+    (
+        "Linux /usr verity sign. (MIPS-32 LE)",
+        "3e23ca0b-a4bc-4b4e-8087-5ab6a26aa8a9",
+    ),
+    (
+        "Linux /usr verity sign. (MIPS-64 LE)",
+        "f2c2c7ee-adcc-4351-b5c6-ee9816b66e16",
+    ),
+    (
+        "Linux /usr verity sign. (PPC)",
+        "7007891d-d371-4a80-86a4-5cb875b9302e",
+    ),
+    (
+        "Linux /usr verity sign. (PPC64)",
+        "0b888863-d7f8-4d9e-9766-239fce4d58af",
+    ),
+    (
+        "Linux /usr verity sign. (PPC64LE)",
+        "c8bfbd1e-268e-4521-8bba-bf314c399557",
+    ),
+    (
+        "Linux /usr verity sign. (RISC-V-32)",
+        "c3836a13-3137-45ba-b583-b16c50fe5eb4",
+    ),
+    (
+        "Linux /usr verity sign. (RISC-V-64)",
+        "d2f9000a-7a18-453f-b5cd-4d32f77a7b32",
+    ),
+    (
+        "Linux /usr verity sign. (S390)",
+        "17440e4f-a8d0-467f-a46e-3912ae6ef2c5",
+    ),
+    (
+        "Linux /usr verity sign. (S390X)",
+        "3f324816-667b-46ae-86ee-9b0c0c6c11b4",
+    ),
+    (
+        "Linux /usr verity sign. (TILE-Gx)",
+        "4ede75e2-6ccc-4cc8-b9c7-70334b087510",
+    ),
+    (
+        "Linux extended boot",
+        "bc13c2ff-59e6-4262-a352-b275fd6f7172",
+    ),
+    ("Linux user's home", "773f91ef-66d4-49b5-bd83-d683bf40ad16"),
+    ("FreeBSD data", "516e7cb4-6ecf-11d6-8ff8-00022d09712b"),
+    ("FreeBSD boot", "83bd6b9d-7f41-11dc-be0b-001560b84f0f"),
+    ("FreeBSD swap", "516e7cb5-6ecf-11d6-8ff8-00022d09712b"),
+    ("FreeBSD UFS", "516e7cb6-6ecf-11d6-8ff8-00022d09712b"),
+    ("FreeBSD ZFS", "516e7cba-6ecf-11d6-8ff8-00022d09712b"),
+    ("FreeBSD Vinum", "516e7cb8-6ecf-11d6-8ff8-00022d09712b"),
+    ("Apple HFS/HFS+", "48465300-0000-11aa-aa11-00306543ecac"),
+    ("Apple APFS", "7c3457ef-0000-11aa-aa11-00306543ecac"),
+    ("Apple UFS", "55465300-0000-11aa-aa11-00306543ecac"),
+    ("Apple RAID", "52414944-0000-11aa-aa11-00306543ecac"),
+    ("Apple RAID offline", "52414944-5f4f-11aa-aa11-00306543ecac"),
+    ("Apple boot", "426f6f74-0000-11aa-aa11-00306543ecac"),
+    ("Apple label", "4c616265-6c00-11aa-aa11-00306543ecac"),
+    ("Apple TV recovery", "5265636f-7665-11aa-aa11-00306543ecac"),
+    ("Apple Core storage", "53746f72-6167-11aa-aa11-00306543ecac"),
+    ("Apple Silicon boot", "69646961-6700-11aa-aa11-00306543ecac"),
+    (
+        "Apple Silicon recovery",
+        "52637672-7900-11aa-aa11-00306543ecac",
+    ),
+    ("Solaris boot", "6a82cb45-1dd2-11b2-99a6-080020736631"),
+    ("Solaris root", "6a85cf4d-1dd2-11b2-99a6-080020736631"),
+    (
+        "Solaris /usr & Apple ZFS",
+        "6a898cc3-1dd2-11b2-99a6-080020736631",
+    ),
+    ("Solaris swap", "6a87c46f-1dd2-11b2-99a6-080020736631"),
+    ("Solaris backup", "6a8b642b-1dd2-11b2-99a6-080020736631"),
+    ("Solaris /var", "6a8ef2e9-1dd2-11b2-99a6-080020736631"),
+    ("Solaris /home", "6a90ba39-1dd2-11b2-99a6-080020736631"),
+    (
+        "Solaris alternate sector",
+        "6a9283a5-1dd2-11b2-99a6-080020736631",
+    ),
+    ("Solaris reserved 1", "6a945a3b-1dd2-11b2-99a6-080020736631"),
+    ("Solaris reserved 2", "6a9630d1-1dd2-11b2-99a6-080020736631"),
+    ("Solaris reserved 3", "6a980767-1dd2-11b2-99a6-080020736631"),
+    ("Solaris reserved 4", "6a96237f-1dd2-11b2-99a6-080020736631"),
+    ("Solaris reserved 5", "6a8d2ac7-1dd2-11b2-99a6-080020736631"),
+    ("NetBSD swap", "49f48d32-b10e-11dc-b99b-0019d1879648"),
+    ("NetBSD FFS", "49f48d5a-b10e-11dc-b99b-0019d1879648"),
+    ("NetBSD LFS", "49f48d82-b10e-11dc-b99b-0019d1879648"),
+    (
+        "NetBSD concatenated",
+        "2db519c4-b10f-11dc-b99b-0019d1879648",
+    ),
+    ("NetBSD encrypted", "2db519ec-b10f-11dc-b99b-0019d1879648"),
+    ("NetBSD RAID", "49f48daa-b10e-11dc-b99b-0019d1879648"),
+    ("ChromeOS kernel", "fe3a2a5d-4f32-41a7-b725-accc3285a309"),
+    ("ChromeOS root fs", "3cb8e202-3b7e-47dd-8a3c-7ff2a13cfcec"),
+    ("ChromeOS reserved", "2e0a753d-9e48-43b0-8337-b15192cb1b5e"),
+    ("MidnightBSD data", "85d5e45a-237c-11e1-b4b3-e89a8f7fc3a7"),
+    ("MidnightBSD boot", "85d5e45e-237c-11e1-b4b3-e89a8f7fc3a7"),
+    ("MidnightBSD swap", "85d5e45b-237c-11e1-b4b3-e89a8f7fc3a7"),
+    ("MidnightBSD UFS", "0394ef8b-237e-11e1-b4b3-e89a8f7fc3a7"),
+    ("MidnightBSD ZFS", "85d5e45d-237c-11e1-b4b3-e89a8f7fc3a7"),
+    ("MidnightBSD Vinum", "85d5e45c-237c-11e1-b4b3-e89a8f7fc3a7"),
+    ("Ceph Journal", "45b0969e-9b03-4f30-b4c6-b4b80ceff106"),
+    (
+        "Ceph Encrypted Journal",
+        "45b0969e-9b03-4f30-b4c6-5ec00ceff106",
+    ),
+    ("Ceph OSD", "4fbd7e29-9d25-41b8-afd0-062c0ceff05d"),
+    ("Ceph crypt OSD", "4fbd7e29-9d25-41b8-afd0-5ec00ceff05d"),
+    (
+        "Ceph disk in creation",
+        "89c57f98-2fe5-4dc0-89c1-f3ad0ceff2be",
+    ),
+    (
+        "Ceph crypt disk in creation",
+        "89c57f98-2fe5-4dc0-89c1-5ec00ceff2be",
+    ),
+    ("VMware VMFS", "aa31e02a-400f-11db-9590-000c2911d1b8"),
+    ("VMware Diagnostic", "9d275380-40ad-11db-bf97-000c2911d1b8"),
+    ("VMware Virtual SAN", "381cfccc-7288-11e0-92ee-000c2911d0b2"),
+    ("VMware Virsto", "77719a0c-a4a0-11e3-a47e-000c29745a24"),
+    ("VMware Reserved", "9198effc-31c0-11db-8f78-000c2911d1b8"),
+    ("OpenBSD data", "824cc7a0-36a8-11e3-890a-952519ad3f61"),
+    ("QNX6 file system", "cef5a9ad-73bc-4601-89f3-cdeeeee321a1"),
+    ("Plan 9 partition", "c91818f9-8025-47af-89d2-f030d7000c2c"),
+    ("HiFive FSBL", "5b193300-fc78-40cd-8002-e86c45580b47"),
+    ("HiFive BBL", "2e54b353-1271-4842-806f-e436d6af6985"),
+    ("Haiku BFS", "42465331-3ba3-10f1-802a-4861696b7521"),
+    (
+        "Marvell Armada 3700 Boot partition",
+        "6828311a-ba55-42a4-bcde-a89bb5edecae",
+    ),
+];
+
+/// Names of GPT partition attribute flags.
+const GPT_ATTRS: &[(&str, PartitionAttrs)] = &[
+    ("RequiredPartition", PartitionAttrs::REQUIRED),
+    ("NoBlockIOProtocol", PartitionAttrs::NO_BLOCK_IO),
+    ("LegacyBIOSBootable", PartitionAttrs::LEGACY_BIOS_BOOTABLE),
+];
+/// The maximum length of a GPT partition name, in UTF-16 code units.
+const GPT_NAME_MAX_LEN: usize = 36;
+
+/// Headers of sfdisk scripts.
+const SCRIPT_HEADERS: &[&str] = &[
+    "label",
+    "label-id",
+    "device",
+    "unit",
+    "first-lba",
+    "last-lba",
+    "table-length",
+    "sector-size",
+];
+
 /// A MBR partition.
 #[repr(C, packed)]
 #[derive(Clone, Copy, Default)]
@@ -99,12 +784,14 @@ pub struct MbrTable {
 }
 
 /// A GPT entry.
+///
+/// GUIDs are stored in the mixed-endian layout, see [`Uuid::to_bytes_le`].
 #[repr(C, packed)]
 struct GptEntry {
     /// The partition type's GUID.
-    partition_type: Uuid,
+    partition_type: [u8; 16],
     /// The partition's GUID.
-    guid: Uuid,
+    guid: [u8; 16],
     /// The starting LBA.
     start: i64,
     /// The ending LBA.
@@ -112,7 +799,28 @@ struct GptEntry {
     /// Entry's attributes.
     attributes: u64,
     /// The partition's name.
-    name: [u16; 36],
+    name: [u16; GPT_NAME_MAX_LEN],
+}
+
+impl GptEntry {
+    /// Returns the partition's name, if not empty.
+    fn name(&self) -> Option<String> {
+        let name = self.name;
+        let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        let units: Vec<u16> = name[..len].iter().map(|c| u16::from_le(*c)).collect();
+        (len > 0).then(|| String::from_utf16_lossy(&units))
+    }
+}
+
+/// Encodes `name` as a GPT partition name, in UTF-16LE padded with zeros.
+///
+/// If the name is too long, the function returns `None`.
+fn encode_gpt_name(name: &str) -> Option<[u16; GPT_NAME_MAX_LEN]> {
+    let mut buff = [0; GPT_NAME_MAX_LEN];
+    for (i, c) in name.encode_utf16().enumerate() {
+        *buff.get_mut(i)? = c.to_le();
+    }
+    Some(buff)
 }
 
 /// The GPT header.
@@ -137,8 +845,8 @@ pub struct Gpt {
     first_usable: i64,
     /// The last usable sector.
     last_usable: i64,
-    /// The disk's GUID.
-    disk_guid: Uuid,
+    /// The disk's GUID, in the mixed-endian layout.
+    disk_guid: [u8; 16],
     /// The LBA of the beginning of the GUID partition entries array.
     entries_start: i64,
     /// The number of entries in the table.
@@ -149,8 +857,30 @@ pub struct Gpt {
     entries_checksum: u32,
 }
 
+impl Gpt {
+    /// Computes the checksum of the header, as if the `checksum` field were zero.
+    fn compute_checksum(&self, crc32_table: &[u32; 256]) -> u32 {
+        let mut hdr = *self;
+        hdr.checksum = 0;
+        let hdr_slice =
+            unsafe { slice::from_raw_parts(&hdr as *const _ as *const u8, size_of::<Gpt>()) };
+        crc32::compute(hdr_slice, crc32_table)
+    }
+}
+
+/// The reason why a GPT header cannot be used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GptError {
+    /// The header does not have the GPT signature.
+    NoSignature,
+    /// The header is corrupt.
+    InvalidHeader,
+    /// The entries array is corrupt.
+    InvalidEntries,
+}
+
 /// Enumeration of partition table types.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum PartitionTableType {
     /// Master Boot Record.
     Mbr,
@@ -163,737 +893,140 @@ impl PartitionTableType {
     pub fn print_partition_types(&self) {
         match self {
             Self::Mbr => {
-                let types = &[
-                    (0x00, "Empty"),
-                    (0x01, "FAT12"),
-                    (0x02, "XENIX root"),
-                    (0x03, "XENIX usr"),
-                    (0x04, "FAT16 <32M"),
-                    (0x05, "Extended"),
-                    (0x06, "FAT16"),
-                    (0x07, "HPFS/NTFS/exFAT"),
-                    (0x08, "AIX"),
-                    (0x09, "AIX bootable"),
-                    (0x0a, "OS/2 Boot Manager"),
-                    (0x0b, "W95 FAT32"),
-                    (0x0c, "W95 FAT32 (LBA)"),
-                    (0x0e, "W95 FAT16 (LBA)"),
-                    (0x0f, "W95 Ext'd (LBA)"),
-                    (0x10, "OPUS"),
-                    (0x11, "Hidden FAT12"),
-                    (0x12, "Compaq diagnostics"),
-                    (0x14, "Hidden FAT16 <3"),
-                    (0x16, "Hidden FAT16"),
-                    (0x17, "Hidden HPFS/NTFS"),
-                    (0x18, "AST SmartSleep"),
-                    (0x1b, "Hidden W95 FAT3"),
-                    (0x1c, "Hidden W95 FAT3"),
-                    (0x1e, "Hidden W95 FAT1"),
-                    (0x24, "NEC DOS"),
-                    (0x27, "Hidden NTFS Win"),
-                    (0x39, "Plan 9"),
-                    (0x3c, "PartitionMagic"),
-                    (0x40, "Venix 80286"),
-                    (0x41, "PPC PReP Boot"),
-                    (0x42, "SFS"),
-                    (0x4d, "QNX4.x"),
-                    (0x4e, "QNX4.x 2nd part"),
-                    (0x4f, "QNX4.x 3rd part"),
-                    (0x50, "OnTrack DM"),
-                    (0x51, "OnTrack DM6 Aux"),
-                    (0x52, "CP/M"),
-                    (0x53, "OnTrack DM6 Aux"),
-                    (0x54, "OnTrackDM6"),
-                    (0x55, "EZ-Drive"),
-                    (0x56, "Golden Bow"),
-                    (0x5c, "Priam Edisk"),
-                    (0x61, "SpeedStor"),
-                    (0x63, "GNU HURD or Sys"),
-                    (0x64, "Novell Netware"),
-                    (0x65, "Novell Netware"),
-                    (0x70, "DiskSecure Mult"),
-                    (0x75, "PC/IX"),
-                    (0x80, "Old Minix"),
-                    (0x81, "Minix / old Linux"),
-                    (0x82, "Linux swap / Solaris"),
-                    (0x83, "Linux"),
-                    (0x84, "OS/2 hidden"),
-                    (0x85, "Linux extended"),
-                    (0x86, "NTFS volume set"),
-                    (0x87, "NTFS volume set"),
-                    (0x88, "Linux plaintext"),
-                    (0x8e, "Linux LVM"),
-                    (0x93, "Amoeba"),
-                    (0x94, "Amoeba BBT"),
-                    (0x9f, "BSD/OS"),
-                    (0xa0, "IBM Thinkpad"),
-                    (0xa5, "FreeBSD"),
-                    (0xa6, "OpenBSD"),
-                    (0xa7, "NeXTSTEP"),
-                    (0xa8, "Darwin UFS"),
-                    (0xa9, "NetBSD"),
-                    (0xab, "Darwin boot"),
-                    (0xaf, "HFS / HFS+"),
-                    (0xb7, "BSDI fs"),
-                    (0xb8, "BSDI swap"),
-                    (0xbb, "Boot Wizard hidden"),
-                    (0xbc, "Acronis FAT32"),
-                    (0xbe, "Solaris boot"),
-                    (0xbf, "Solaris"),
-                    (0xc1, "DRDOS/sec"),
-                    (0xc4, "DRDOS/sec"),
-                    (0xc6, "DRDOS/sec"),
-                    (0xc7, "Syrinx"),
-                    (0xda, "Non-FS data"),
-                    (0xdb, "CP/M / CTOS / ."),
-                    (0xde, "Dell Utility"),
-                    (0xdf, "BootIt"),
-                    (0xe0, "ST AVFS"),
-                    (0xe1, "DOS access"),
-                    (0xe3, "DOS R/O"),
-                    (0xe4, "SpeedStor"),
-                    (0xea, "Linux extended"),
-                    (0xeb, "BeOS fs"),
-                    (0xee, "GPT"),
-                    (0xef, "EFI (FAT-12/16/32)"),
-                    (0xf0, "Linux/PA-RISC bootloader"),
-                    (0xf1, "SpeedStor"),
-                    (0xf2, "DOS secondary"),
-                    (0xf4, "SpeedStor"),
-                    (0xf8, "EBBR protective"),
-                    (0xfb, "VMware VMFS"),
-                    (0xfc, "VMware VMKCORE"),
-                    (0xfd, "Linux raid auto"),
-                    (0xfe, "LANstep"),
-                    (0xff, "BBT"),
-                ];
-                let max_len = types.iter().map(|(_, name)| name.len()).max().unwrap_or(0);
+                let max_len = MBR_TYPES
+                    .iter()
+                    .map(|(_, name)| name.len())
+                    .max()
+                    .unwrap_or(0);
                 let term_width = 80; // TODO get from ioctl
                 let entries_per_line = max(term_width / (max_len + 5), 1);
 
-                for (i, (id, name)) in types.iter().enumerate() {
+                for (i, (id, name)) in MBR_TYPES.iter().enumerate() {
                     print!("  {id:02x} {name:max_len$}");
                     if i % entries_per_line == entries_per_line - 1 {
                         println!();
                     }
                 }
+                if !MBR_TYPES.len().is_multiple_of(entries_per_line) {
+                    println!();
+                }
             }
 
             Self::Gpt => {
-                let types = &[
-                    ("EFI System", "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"),
-                    (
-                        "MBR partition scheme",
-                        "024dee41-33e7-11d3-9d69-0008c781f39f",
-                    ),
-                    ("Intel Fast Flash", "d3bfe2de-3daf-11df-ba40-e3a556d89593"),
-                    ("BIOS boot", "21686148-6449-6e6f-744e-656564454649"),
-                    (
-                        "Sony boot partition",
-                        "f4019732-066e-4e12-8273-346c5641494f",
-                    ),
-                    (
-                        "Lenovo boot partition",
-                        "bfbfafe7-a34f-448a-9a5b-6213eb736c22",
-                    ),
-                    ("PowerPC PReP boot", "9e1a2d38-c612-4316-aa26-8b49521e5a8b"),
-                    ("ONIE boot", "7412f7d5-a156-4b13-81dc-867174929325"),
-                    ("ONIE config", "d4e6e2cd-4469-46f3-b5cb-1bff57afc149"),
-                    ("Microsoft reserved", "e3c9e316-0b5c-4db8-817d-f92df00215ae"),
-                    (
-                        "Microsoft basic data",
-                        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
-                    ),
-                    (
-                        "Microsoft LDM metadata",
-                        "5808c8aa-7e8f-42e0-85d2-e1e90434cfb3",
-                    ),
-                    ("Microsoft LDM data", "af9b60a0-1431-4f62-bc68-3311714a69ad"),
-                    (
-                        "Windows recovery environment",
-                        "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
-                    ),
-                    (
-                        "IBM General Parallel Fs",
-                        "37affc90-ef7d-4e96-91c3-2d7ae055b174",
-                    ),
-                    (
-                        "Microsoft Storage Spaces",
-                        "e75caf8f-f680-4cee-afa3-b001e56efc2d",
-                    ),
-                    ("HP-UX data", "75894c1e-3aeb-11d3-b7c1-7b03a0000000"),
-                    ("HP-UX service", "e2a1e728-32e3-11d6-a682-7b03a0000000"),
-                    ("Linux swap", "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"),
-                    ("Linux filesystem", "0fc63daf-8483-4772-8e79-3d69d8477de4"),
-                    ("Linux server data", "3b8f8425-20e0-4f3b-907f-1a25a76f98e8"),
-                    ("Linux root (x86)", "44479540-f297-41b2-9af7-d131d5f0458a"),
-                    (
-                        "Linux root (x86-64)",
-                        "4f68bce3-e8cd-4db1-96e7-fbcaf984b709",
-                    ),
-                    ("Linux root (Alpha)", "6523f8ae-3eb1-4e2a-a05a-18b695ae656f"),
-                    ("Linux root (ARC)", "d27f46ed-2919-4cb8-bd25-9531f3c16534"),
-                    ("Linux root (ARM)", "69dad710-2ce4-4e3c-b16c-21a1d49abed3"),
-                    (
-                        "Linux root (ARM-64)",
-                        "b921b045-1df0-41c3-af44-4c6f280d3fae",
-                    ),
-                    ("Linux root (IA-64)", "993d8d3d-f80e-4225-855a-9daf8ed7ea97"),
-                    (
-                        "Linux root (LoongArch-64)",
-                        "77055800-792c-4f94-b39a-98c91b762bb6",
-                    ),
-                    (
-                        "Linux root (MIPS-32 LE)",
-                        "37c58c8a-d913-4156-a25f-48b1b64e07f0",
-                    ),
-                    (
-                        "Linux root (MIPS-64 LE)",
-                        "700bda43-7a34-4507-b179-eeb93d7a7ca3",
-                    ),
-                    ("Linux root (PPC)", "1de3f1ef-fa98-47b5-8dcd-4a860a654d78"),
-                    ("Linux root (PPC64)", "912ade1d-a839-4913-8964-a10eee08fbd2"),
-                    (
-                        "Linux root (PPC64LE)",
-                        "c31c45e6-3f39-412e-80fb-4809c4980599",
-                    ),
-                    (
-                        "Linux root (RISC-V-32)",
-                        "60d5a7fe-8e7d-435c-b714-3dd8162144e1",
-                    ),
-                    (
-                        "Linux root (RISC-V-64)",
-                        "72ec70a6-cf74-40e6-bd49-4bda08e8f224",
-                    ),
-                    ("Linux root (S390)", "08a7acea-624c-4a20-91e8-6e0fa67d23f9"),
-                    ("Linux root (S390X)", "5eead9a9-fe09-4a1e-a1d7-520d00531306"),
-                    (
-                        "Linux root (TILE-Gx)",
-                        "c50cdd70-3862-4cc3-90e1-809a8c93ee2c",
-                    ),
-                    ("Linux reserved", "8da63339-0007-60c0-c436-083ac8230908"),
-                    ("Linux home", "933ac7e1-2eb4-4f13-b844-0e14e2aef915"),
-                    ("Linux RAID", "a19d880f-05fc-4d3b-a006-743f0f84911e"),
-                    ("Linux LVM", "e6d6d379-f507-44c2-a23c-238f2a3df928"),
-                    (
-                        "Linux variable data",
-                        "4d21b016-b534-45c2-a9fb-5c16e091fd2d",
-                    ),
-                    (
-                        "Linux temporary data",
-                        "7ec6f557-3bc5-4aca-b293-16ef5df639d1",
-                    ),
-                    ("Linux /usr (x86)", "75250d76-8cc6-458e-bd66-bd47cc81a812"),
-                    (
-                        "Linux /usr (x86-64)",
-                        "8484680c-9521-48c6-9c11-b0720656f69e",
-                    ),
-                    ("Linux /usr (Alpha)", "e18cf08c-33ec-4c0d-8246-c6c6fb3da024"),
-                    ("Linux /usr (ARC)", "7978a683-6316-4922-bbee-38bff5a2fecc"),
-                    ("Linux /usr (ARM)", "7d0359a3-02b3-4f0a-865c-654403e70625"),
-                    (
-                        "Linux /usr (ARM-64)",
-                        "b0e01050-ee5f-4390-949a-9101b17104e9",
-                    ),
-                    ("Linux /usr (IA-64)", "4301d2a6-4e3b-4b2a-bb94-9e0b2c4225ea"),
-                    (
-                        "Linux /usr (LoongArch-64)",
-                        "e611c702-575c-4cbe-9a46-434fa0bf7e3f",
-                    ),
-                    (
-                        "Linux /usr (MIPS-32 LE)",
-                        "0f4868e9-9952-4706-979f-3ed3a473e947",
-                    ),
-                    (
-                        "Linux /usr (MIPS-64 LE)",
-                        "c97c1f32-ba06-40b4-9f22-236061b08aa8",
-                    ),
-                    ("Linux /usr (PPC)", "7d14fec5-cc71-415d-9d6c-06bf0b3c3eaf"),
-                    ("Linux /usr (PPC64)", "2c9739e2-f068-46b3-9fd0-01c5a9afbcca"),
-                    (
-                        "Linux /usr (PPC64LE)",
-                        "15bb03af-77e7-4d4a-b12b-c0d084f7491c",
-                    ),
-                    (
-                        "Linux /usr (RISC-V-32)",
-                        "b933fb22-5c3f-4f91-af90-e2bb0fa50702",
-                    ),
-                    (
-                        "Linux /usr (RISC-V-64)",
-                        "beaec34b-8442-439b-a40b-984381ed097d",
-                    ),
-                    ("Linux /usr (S390)", "cd0f869b-d0fb-4ca0-b141-9ea87cc78d66"),
-                    ("Linux /usr (S390X)", "8a4f5770-50aa-4ed3-874a-99b710db6fea"),
-                    (
-                        "Linux /usr (TILE-Gx)",
-                        "55497029-c7c1-44cc-aa39-815ed1558630",
-                    ),
-                    (
-                        "Linux root verity (x86)",
-                        "d13c5d3b-b5d1-422a-b29f-9454fdc89d76",
-                    ),
-                    (
-                        "Linux root verity (x86-64)",
-                        "2c7357ed-ebd2-46d9-aec1-23d437ec2bf5",
-                    ),
-                    (
-                        "Linux root verity (Alpha)",
-                        "fc56d9e9-e6e5-4c06-be32-e74407ce09a5",
-                    ),
-                    (
-                        "Linux root verity (ARC)",
-                        "24b2d975-0f97-4521-afa1-cd531e421b8d",
-                    ),
-                    (
-                        "Linux root verity (ARM)",
-                        "7386cdf2-203c-47a9-a498-f2ecce45a2d6",
-                    ),
-                    (
-                        "Linux root verity (ARM-64)",
-                        "df3300ce-d69f-4c92-978c-9bfb0f38d820",
-                    ),
-                    (
-                        "Linux root verity (IA-64)",
-                        "86ed10d5-b607-45bb-8957-d350f23d0571",
-                    ),
-                    (
-                        "Linux root verity (LoongArch-64)",
-                        "f3393b22-e9af-4613-a948-9d3bfbd0c535",
-                    ),
-                    (
-                        "Linux root verity (MIPS-32 LE)",
-                        "d7d150d2-2a04-4a33-8f12-16651205ff7b",
-                    ),
-                    (
-                        "Linux root verity (MIPS-64 LE)",
-                        "16b417f8-3e06-4f57-8dd2-9b5232f41aa6",
-                    ),
-                    (
-                        "Linux root verity (PPC)",
-                        "98cfe649-1588-46dc-b2f0-add147424925",
-                    ),
-                    (
-                        "Linux root verity (PPC64)",
-                        "9225a9a3-3c19-4d89-b4f6-eeff88f17631",
-                    ),
-                    (
-                        "Linux root verity (PPC64LE)",
-                        "906bd944-4589-4aae-a4e4-dd983917446a",
-                    ),
-                    (
-                        "Linux root verity (RISC-V-32)",
-                        "ae0253be-1167-4007-ac68-43926c14c5de",
-                    ),
-                    (
-                        "Linux root verity (RISC-V-64)",
-                        "b6ed5582-440b-4209-b8da-5ff7c419ea3d",
-                    ),
-                    (
-                        "Linux root verity (S390)",
-                        "7ac63b47-b25c-463b-8df8-b4a94e6c90e1",
-                    ),
-                    (
-                        "Linux root verity (S390X)",
-                        "b325bfbe-c7be-4ab8-8357-139e652d2f6b",
-                    ),
-                    (
-                        "Linux root verity (TILE-Gx)",
-                        "966061ec-28e4-4b2e-b4a5-1f0a825a1d84",
-                    ),
-                    (
-                        "Linux /usr verity (x86)",
-                        "8f461b0d-14ee-4e81-9aa9-049b6fb97abd",
-                    ),
-                    (
-                        "Linux /usr verity (x86-64)",
-                        "77ff5f63-e7b6-4633-acf4-1565b864c0e6",
-                    ),
-                    (
-                        "Linux /usr verity (Alpha)",
-                        "8cce0d25-c0d0-4a44-bd87-46331bf1df67",
-                    ),
-                    (
-                        "Linux /usr verity (ARC)",
-                        "fca0598c-d880-4591-8c16-4eda05c7347c",
-                    ),
-                    (
-                        "Linux /usr verity (ARM)",
-                        "c215d751-7bcd-4649-be90-6627490a4c05",
-                    ),
-                    (
-                        "Linux /usr verity (ARM-64)",
-                        "6e11a4e7-fbca-4ded-b9e9-e1a512bb664e",
-                    ),
-                    (
-                        "Linux /usr verity (IA-64)",
-                        "6a491e03-3be7-4545-8e38-83320e0ea880",
-                    ),
-                    (
-                        "Linux /usr verity (LoongArch-64)",
-                        "f46b2c26-59ae-48f0-9106-c50ed47f673d",
-                    ),
-                    (
-                        "Linux /usr verity (MIPS-32 LE)",
-                        "46b98d8d-b55c-4e8f-aab3-37fca7f80752",
-                    ),
-                    (
-                        "Linux /usr verity (MIPS-64 LE)",
-                        "3c3d61fe-b5f3-414d-bb71-8739a694a4ef",
-                    ),
-                    (
-                        "Linux /usr verity (PPC)",
-                        "df765d00-270e-49e5-bc75-f47bb2118b09",
-                    ),
-                    (
-                        "Linux /usr verity (PPC64)",
-                        "bdb528a5-a259-475f-a87d-da53fa736a07",
-                    ),
-                    (
-                        "Linux /usr verity (PPC64LE)",
-                        "ee2b9983-21e8-4153-86d9-b6901a54d1ce",
-                    ),
-                    (
-                        "Linux /usr verity (RISC-V-32)",
-                        "cb1ee4e3-8cd0-4136-a0a4-aa61a32e8730",
-                    ),
-                    (
-                        "Linux /usr verity (RISC-V-64)",
-                        "8f1056be-9b05-47c4-81d6-be53128e5b54",
-                    ),
-                    (
-                        "Linux /usr verity (S390)",
-                        "b663c618-e7bc-4d6d-90aa-11b756bb1797",
-                    ),
-                    (
-                        "Linux /usr verity (S390X)",
-                        "31741cc4-1a2a-4111-a581-e00b447d2d06",
-                    ),
-                    (
-                        "Linux /usr verity (TILE-Gx)",
-                        "2fb4bf56-07fa-42da-8132-6b139f2026ae",
-                    ),
-                    (
-                        "Linux root verity sign. (x86)",
-                        "5996fc05-109c-48de-808b-23fa0830b676",
-                    ),
-                    (
-                        "Linux root verity sign. (x86-64)",
-                        "41092b05-9fc8-4523-994f-2def0408b176",
-                    ),
-                    (
-                        "Linux root verity sign. (Alpha)",
-                        "d46495b7-a053-414f-80f7-700c99921ef8",
-                    ),
-                    (
-                        "Linux root verity sign. (ARC)",
-                        "143a70ba-cbd3-4f06-919f-6c05683a78bc",
-                    ),
-                    (
-                        "Linux root verity sign. (ARM)",
-                        "42b0455f-eb11-491d-98d3-56145ba9d037",
-                    ),
-                    (
-                        "Linux root verity sign. (ARM-64)",
-                        "6db69de6-29f4-4758-a7a5-962190f00ce3",
-                    ),
-                    (
-                        "Linux root verity sign. (IA-64)",
-                        "e98b36ee-32ba-4882-9b12-0ce14655f46a",
-                    ),
-                    (
-                        "Linux root verity sign. (LoongArch-64)",
-                        "5afb67eb-ecc8-4f85-ae8e-ac1e7c50e7d0",
-                    ),
-                    (
-                        "Linux root verity sign. (MIPS-32 LE)",
-                        "c919cc1f-4456-4eff-918c-f75e94525ca5",
-                    ),
-                    (
-                        "Linux root verity sign. (MIPS-64 LE)",
-                        "904e58ef-5c65-4a31-9c57-6af5fc7c5de7",
-                    ),
-                    (
-                        "Linux root verity sign. (PPC)",
-                        "1b31b5aa-add9-463a-b2ed-bd467fc857e7",
-                    ),
-                    (
-                        "Linux root verity sign. (PPC64)",
-                        "f5e2c20c-45b2-4ffa-bce9-2a60737e1aaf",
-                    ),
-                    (
-                        "Linux root verity sign. (PPC64LE)",
-                        "d4a236e7-e873-4c07-bf1d-bf6cf7f1c3c6",
-                    ),
-                    (
-                        "Linux root verity sign. (RISC-V-32)",
-                        "3a112a75-8729-4380-b4cf-764d79934448",
-                    ),
-                    (
-                        "Linux root verity sign. (RISC-V-64)",
-                        "efe0f087-ea8d-4469-821a-4c2a96a8386a",
-                    ),
-                    (
-                        "Linux root verity sign. (S390)",
-                        "3482388e-4254-435a-a241-766a065f9960",
-                    ),
-                    (
-                        "Linux root verity sign. (S390X)",
-                        "c80187a5-73a3-491a-901a-017c3fa953e9",
-                    ),
-                    (
-                        "Linux root verity sign. (TILE-Gx)",
-                        "b3671439-97b0-4a53-90f7-2d5a8f3ad47b",
-                    ),
-                    (
-                        "Linux /usr verity sign. (x86)",
-                        "974a71c0-de41-43c3-be5d-5c5ccd1ad2c0",
-                    ),
-                    (
-                        "Linux /usr verity sign. (x86-64)",
-                        "e7bb33fb-06cf-4e81-8273-e543b413e2e2",
-                    ),
-                    (
-                        "Linux /usr verity sign. (Alpha)",
-                        "5c6e1c76-076a-457a-a0fe-f3b4cd21ce6e",
-                    ),
-                    (
-                        "Linux /usr verity sign. (ARC)",
-                        "94f9a9a1-9971-427a-a400-50cb297f0f35",
-                    ),
-                    (
-                        "Linux /usr verity sign. (ARM)",
-                        "d7ff812f-37d1-4902-a810-d76ba57b975a",
-                    ),
-                    (
-                        "Linux /usr verity sign. (ARM-64)",
-                        "c23ce4ff-44bd-4b00-b2d4-b41b3419e02a",
-                    ),
-                    (
-                        "Linux /usr verity sign. (IA-64)",
-                        "8de58bc2-2a43-460d-b14e-a76e4a17b47f",
-                    ),
-                    (
-                        "Linux /usr verity sign. (LoongArch-64)",
-                        "b024f315-d330-444c-8461-44bbde524e99",
-                    ),
-                    (
-                        "Linux /usr verity sign. (MIPS-32 LE)",
-                        "3e23ca0b-a4bc-4b4e-8087-5ab6a26aa8a9",
-                    ),
-                    (
-                        "Linux /usr verity sign. (MIPS-64 LE)",
-                        "f2c2c7ee-adcc-4351-b5c6-ee9816b66e16",
-                    ),
-                    (
-                        "Linux /usr verity sign. (PPC)",
-                        "7007891d-d371-4a80-86a4-5cb875b9302e",
-                    ),
-                    (
-                        "Linux /usr verity sign. (PPC64)",
-                        "0b888863-d7f8-4d9e-9766-239fce4d58af",
-                    ),
-                    (
-                        "Linux /usr verity sign. (PPC64LE)",
-                        "c8bfbd1e-268e-4521-8bba-bf314c399557",
-                    ),
-                    (
-                        "Linux /usr verity sign. (RISC-V-32)",
-                        "c3836a13-3137-45ba-b583-b16c50fe5eb4",
-                    ),
-                    (
-                        "Linux /usr verity sign. (RISC-V-64)",
-                        "d2f9000a-7a18-453f-b5cd-4d32f77a7b32",
-                    ),
-                    (
-                        "Linux /usr verity sign. (S390)",
-                        "17440e4f-a8d0-467f-a46e-3912ae6ef2c5",
-                    ),
-                    (
-                        "Linux /usr verity sign. (S390X)",
-                        "3f324816-667b-46ae-86ee-9b0c0c6c11b4",
-                    ),
-                    (
-                        "Linux /usr verity sign. (TILE-Gx)",
-                        "4ede75e2-6ccc-4cc8-b9c7-70334b087510",
-                    ),
-                    (
-                        "Linux extended boot",
-                        "bc13c2ff-59e6-4262-a352-b275fd6f7172",
-                    ),
-                    ("Linux user's home", "773f91ef-66d4-49b5-bd83-d683bf40ad16"),
-                    ("FreeBSD data", "516e7cb4-6ecf-11d6-8ff8-00022d09712b"),
-                    ("FreeBSD boot", "83bd6b9d-7f41-11dc-be0b-001560b84f0f"),
-                    ("FreeBSD swap", "516e7cb5-6ecf-11d6-8ff8-00022d09712b"),
-                    ("FreeBSD UFS", "516e7cb6-6ecf-11d6-8ff8-00022d09712b"),
-                    ("FreeBSD ZFS", "516e7cba-6ecf-11d6-8ff8-00022d09712b"),
-                    ("FreeBSD Vinum", "516e7cb8-6ecf-11d6-8ff8-00022d09712b"),
-                    ("Apple HFS/HFS+", "48465300-0000-11aa-aa11-00306543ecac"),
-                    ("Apple APFS", "7c3457ef-0000-11aa-aa11-00306543ecac"),
-                    ("Apple UFS", "55465300-0000-11aa-aa11-00306543ecac"),
-                    ("Apple RAID", "52414944-0000-11aa-aa11-00306543ecac"),
-                    ("Apple RAID offline", "52414944-5f4f-11aa-aa11-00306543ecac"),
-                    ("Apple boot", "426f6f74-0000-11aa-aa11-00306543ecac"),
-                    ("Apple label", "4c616265-6c00-11aa-aa11-00306543ecac"),
-                    ("Apple TV recovery", "5265636f-7665-11aa-aa11-00306543ecac"),
-                    ("Apple Core storage", "53746f72-6167-11aa-aa11-00306543ecac"),
-                    ("Apple Silicon boot", "69646961-6700-11aa-aa11-00306543ecac"),
-                    (
-                        "Apple Silicon recovery",
-                        "52637672-7900-11aa-aa11-00306543ecac",
-                    ),
-                    ("Solaris boot", "6a82cb45-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris root", "6a85cf4d-1dd2-11b2-99a6-080020736631"),
-                    (
-                        "Solaris /usr & Apple ZFS",
-                        "6a898cc3-1dd2-11b2-99a6-080020736631",
-                    ),
-                    ("Solaris swap", "6a87c46f-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris backup", "6a8b642b-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris /var", "6a8ef2e9-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris /home", "6a90ba39-1dd2-11b2-99a6-080020736631"),
-                    (
-                        "Solaris alternate sector",
-                        "6a9283a5-1dd2-11b2-99a6-080020736631",
-                    ),
-                    ("Solaris reserved 1", "6a945a3b-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris reserved 2", "6a9630d1-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris reserved 3", "6a980767-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris reserved 4", "6a96237f-1dd2-11b2-99a6-080020736631"),
-                    ("Solaris reserved 5", "6a8d2ac7-1dd2-11b2-99a6-080020736631"),
-                    ("NetBSD swap", "49f48d32-b10e-11dc-b99b-0019d1879648"),
-                    ("NetBSD FFS", "49f48d5a-b10e-11dc-b99b-0019d1879648"),
-                    ("NetBSD LFS", "49f48d82-b10e-11dc-b99b-0019d1879648"),
-                    (
-                        "NetBSD concatenated",
-                        "2db519c4-b10f-11dc-b99b-0019d1879648",
-                    ),
-                    ("NetBSD encrypted", "2db519ec-b10f-11dc-b99b-0019d1879648"),
-                    ("NetBSD RAID", "49f48daa-b10e-11dc-b99b-0019d1879648"),
-                    ("ChromeOS kernel", "fe3a2a5d-4f32-41a7-b725-accc3285a309"),
-                    ("ChromeOS root fs", "3cb8e202-3b7e-47dd-8a3c-7ff2a13cfcec"),
-                    ("ChromeOS reserved", "2e0a753d-9e48-43b0-8337-b15192cb1b5e"),
-                    ("MidnightBSD data", "85d5e45a-237c-11e1-b4b3-e89a8f7fc3a7"),
-                    ("MidnightBSD boot", "85d5e45e-237c-11e1-b4b3-e89a8f7fc3a7"),
-                    ("MidnightBSD swap", "85d5e45b-237c-11e1-b4b3-e89a8f7fc3a7"),
-                    ("MidnightBSD UFS", "0394ef8b-237e-11e1-b4b3-e89a8f7fc3a7"),
-                    ("MidnightBSD ZFS", "85d5e45d-237c-11e1-b4b3-e89a8f7fc3a7"),
-                    ("MidnightBSD Vinum", "85d5e45c-237c-11e1-b4b3-e89a8f7fc3a7"),
-                    ("Ceph Journal", "45b0969e-9b03-4f30-b4c6-b4b80ceff106"),
-                    (
-                        "Ceph Encrypted Journal",
-                        "45b0969e-9b03-4f30-b4c6-5ec00ceff106",
-                    ),
-                    ("Ceph OSD", "4fbd7e29-9d25-41b8-afd0-062c0ceff05d"),
-                    ("Ceph crypt OSD", "4fbd7e29-9d25-41b8-afd0-5ec00ceff05d"),
-                    (
-                        "Ceph disk in creation",
-                        "89c57f98-2fe5-4dc0-89c1-f3ad0ceff2be",
-                    ),
-                    (
-                        "Ceph crypt disk in creation",
-                        "89c57f98-2fe5-4dc0-89c1-5ec00ceff2be",
-                    ),
-                    ("VMware VMFS", "aa31e02a-400f-11db-9590-000c2911d1b8"),
-                    ("VMware Diagnostic", "9d275380-40ad-11db-bf97-000c2911d1b8"),
-                    ("VMware Virtual SAN", "381cfccc-7288-11e0-92ee-000c2911d0b2"),
-                    ("VMware Virsto", "77719a0c-a4a0-11e3-a47e-000c29745a24"),
-                    ("VMware Reserved", "9198effc-31c0-11db-8f78-000c2911d1b8"),
-                    ("OpenBSD data", "824cc7a0-36a8-11e3-890a-952519ad3f61"),
-                    ("QNX6 file system", "cef5a9ad-73bc-4601-89f3-cdeeeee321a1"),
-                    ("Plan 9 partition", "c91818f9-8025-47af-89d2-f030d7000c2c"),
-                    ("HiFive FSBL", "5b193300-fc78-40cd-8002-e86c45580b47"),
-                    ("HiFive BBL", "2e54b353-1271-4842-806f-e436d6af6985"),
-                    ("Haiku BFS", "42465331-3ba3-10f1-802a-4861696b7521"),
-                    (
-                        "Marvell Armada 3700 Boot partition",
-                        "6828311a-ba55-42a4-bcde-a89bb5edecae",
-                    ),
-                ];
-                let max_len = types.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
-                for (i, (name, uuid)) in types.iter().enumerate() {
-                    print!("{i:3} {name:max_len$} {uuid}");
+                let max_len = GPT_TYPES
+                    .iter()
+                    .map(|(name, _)| name.len())
+                    .max()
+                    .unwrap_or(0);
+                for (i, (name, uuid)) in GPT_TYPES.iter().enumerate() {
+                    println!("{:3} {name:max_len$} {uuid}", i + 1);
                 }
             }
         }
     }
 
-    // TODO Return result instead
-    /// Prompts for information related to a new partition to be created.
-    pub fn prompt_new_partition(&self) -> Partition {
-        let (_extended, max_partition_count) = match self {
-            Self::Mbr => {
-                // TODO get info from disk, to be passed as argument
-                println!("Partition type");
-                println!("   p   primary (TODO primary, TODO extended, TODO free)"); // TODO
-                println!("   e   extended (container for logical partitions)"); // TODO
-
-                let extended = prompt("Select (default p): ", false)
-                    .map(|s| s == "e") // TODO handle invalid prompt (other than `p` and `e`)
-                    .unwrap_or(false);
-                (extended, 4)
-            }
-            Self::Gpt => (false, 128),
-        };
+    /// Returns the maximum number of partitions in a table of this type.
+    pub fn max_partitions(&self) -> usize {
+        match self {
+            Self::Mbr => 4,
+            Self::Gpt => 128,
+        }
+    }
 
-        // Ask partition number
-        let first = 1; // TODO get from disk
-        let prompt_str =
-            format!("Partition number ({first}-{max_partition_count}, default {first}): ");
-        let partition_number = prompt(&prompt_str, false)
-            .filter(|s| !s.is_empty())
-            .map(|s| s.parse::<usize>())
-            .transpose()
-            .unwrap() // TODO handle error
-            .unwrap_or(first);
-
-        // Ask first sector
-        let first_available = 2048; // TODO
-        let last_available = 0; // TODO
-        let prompt_str = format!(
-            "First sector ({first_available}-{last_available}, default {first_available}): ",
-        );
-        let start = prompt(&prompt_str, false)
-            .filter(|s| !s.is_empty())
-            .map(|s| s.parse::<u64>())
-            .transpose()
-            .unwrap() // TODO handle error
-            .unwrap_or(first_available);
+    /// Returns the first and last sectors (inclusive) usable by partitions on a disk with
+    /// `sectors_count` sectors of `sector_size` bytes.
+    pub fn usable_range(&self, sectors_count: u64, sector_size: u64) -> (u64, u64) {
+        self.usable_range_for(self.max_partitions(), sectors_count, sector_size)
+    }
 
-        // Ask last sector
-        let end = prompt(format_args!(
-            "Last sector, +/-sectors or +/-size{{K,M,G,T,P}} ({start}-{last_available}, default {last_available}): ",
-        ), false)
-            .map(|s| {
-                // TODO parse suffix
-                s.parse::<u64>()
-            })
-            .transpose()
-            .unwrap() // TODO handle error
-            .unwrap_or(last_available);
+    /// Same as [`Self::usable_range`], with a GPT entries array of `entries_number` entries.
+    fn usable_range_for(
+        &self,
+        entries_number: usize,
+        sectors_count: u64,
+        sector_size: u64,
+    ) -> (u64, u64) {
+        match self {
+            // Sectors are addressed on 32 bits
+            Self::Mbr => (1, min(sectors_count.saturating_sub(1), u32::MAX as u64)),
+            // The GPT header and entries are at both ends, plus the protective MBR
+            Self::Gpt => {
+                let entries_size = (entries_number * size_of::<GptEntry>()) as u64;
+                let reserved = 2 + entries_size.div_ceil(sector_size);
+                (reserved, sectors_count.saturating_sub(reserved))
+            }
+        }
+    }
 
-        let sector_size = 512; // TODO get from disk?
-        let size = (end - start) / sector_size as u64;
+    /// Returns the type of new partitions.
+    pub fn default_partition_type(&self) -> PartitionType {
+        match self {
+            // Linux
+            Self::Mbr => PartitionType::Mbr(0x83),
+            // Linux filesystem
+            Self::Gpt => {
+                PartitionType::Gpt(Uuid::from_u128(0x0fc63daf_8483_4772_8e79_3d69d8477de4))
+            }
+        }
+    }
 
-        // TODO use other values?
-        let part_type = match self {
-            Self::Mbr => PartitionType::Mbr(0),
-            Self::Gpt => PartitionType::Gpt(Uuid::nil()),
+    /// Returns the partition type with the given alias, as used by sfdisk.
+    pub fn partition_type_alias(&self, alias: &str) -> Option<PartitionType> {
+        let alias = match alias {
+            "L" => "linux",
+            "S" => "swap",
+            "U" => "uefi",
+            "E" => "extended",
+            _ => alias,
         };
+        match (self, alias.to_lowercase().as_str()) {
+            (Self::Mbr, "linux") => Some(PartitionType::Mbr(0x83)),
+            (Self::Mbr, "swap") => Some(PartitionType::Mbr(0x82)),
+            (Self::Mbr, "uefi") => Some(PartitionType::Mbr(0xef)),
+            (Self::Mbr, "extended") => Some(PartitionType::Mbr(0x05)),
+            (Self::Gpt, "linux") => Some(self.default_partition_type()),
+            (Self::Gpt, "swap") => Some(PartitionType::Gpt(Uuid::from_u128(
+                0x0657fd6d_a4ab_43c4_84e5_0933c84b4f4f,
+            ))),
+            (Self::Gpt, "uefi") => Some(PartitionType::Gpt(Uuid::from_u128(
+                0xc12a7328_f81f_11d2_ba4b_00a0c93ec93b,
+            ))),
+            _ => None,
+        }
+    }
 
-        Partition {
-            start,
-            size,
-
-            part_type,
-
-            uuid: None, // TODO
-
-            bootable: false,
+    /// Parses a partition type entered by the user.
+    ///
+    /// This is either an alias (see [`Self::partition_type_alias`]), or for MBR, the hexadecimal
+    /// ID of the type. For GPT, this is either a UUID or the number of the type in the list
+    /// printed by [`Self::print_partition_types`].
+    pub fn parse_partition_type(&self, s: &str) -> Option<PartitionType> {
+        if let Some(t) = self.partition_type_alias(s) {
+            return Some(t);
+        }
+        match self {
+            Self::Mbr => u8::from_str_radix(s, 16).ok().map(PartitionType::Mbr),
+            Self::Gpt => {
+                if let Ok(uuid) = Uuid::from_str(s) {
+                    return Some(PartitionType::Gpt(uuid));
+                }
+                let i = s.parse::<usize>().ok()?.checked_sub(1)?;
+                let (_, uuid) = GPT_TYPES.get(i)?;
+                Uuid::from_str(uuid).ok().map(PartitionType::Gpt)
+            }
         }
     }
 
-    /// Reads partitions from the storage device represented by `dev` and returns the list.
-    pub fn read(&self, dev: &mut File, sectors_count: u64) -> io::Result<Option<Vec<Partition>>> {
+    /// Reads the partition table from the storage device represented by `dev`, which has
+    /// `sectors_count` sectors of `sector_size` bytes.
+    ///
+    /// If the device does not contain a table of this type, the function returns `None`.
+    pub fn read(
+        &self,
+        dev: &mut (impl Read + Seek),
+        sectors_count: u64,
+        sector_size: u64,
+    ) -> io::Result<Option<PartitionTable>> {
         match self {
             Self::Mbr => {
                 let mut buff: [u8; size_of::<MbrTable>()] = [0; size_of::<MbrTable>()];
@@ -905,157 +1038,266 @@ impl PartitionTableType {
                     return Ok(None);
                 }
 
+                let disk_signature = mbr.disk_signature;
+                let mut table = PartitionTable {
+                    id: (disk_signature != 0).then_some(LabelId::Mbr(disk_signature)),
+                    ..PartitionTable::new(Self::Mbr)
+                };
+                // Unused entries are kept as holes so that partitions keep their numbers
                 let parts = mbr
                     .partitions
                     .iter()
-                    .filter(|p| p.sectors_count > 0)
-                    .map(|p| Partition {
-                        start: p.lba_start as _,
-                        size: p.sectors_count as _,
+                    .enumerate()
+                    .filter(|(_, p)| p.sectors_count > 0)
+                    .map(|(i, p)| {
+                        (
+                            i + 1,
+                            Partition {
+                                start: p.lba_start as _,
+                                size: p.sectors_count as _,
 
-                        part_type: PartitionType::Mbr(p.partition_type),
+                                part_type: PartitionType::Mbr(p.partition_type),
 
-                        uuid: None,
+                                uuid: None,
 
-                        bootable: p.is_active(),
-                    })
-                    .collect();
-                Ok(Some(parts))
+                                bootable: p.is_active(),
+
+                                name: None,
+                                attrs: PartitionAttrs::default(),
+                            },
+                        )
+                    });
+                for (n, part) in parts {
+                    table.set(n, part);
+                }
+                Ok(Some(table))
             }
 
             Self::Gpt => {
-                let mut buff: [u8; size_of::<Gpt>()] = [0; size_of::<Gpt>()];
-                dev.seek(SeekFrom::Start(512))?;
-                dev.read_exact(&mut buff)?;
-
-                let hdr = unsafe { &mut *(buff.as_mut_ptr() as *mut Gpt) };
-                // Check signature
-                if hdr.signature != GPT_SIGNATURE {
-                    return Ok(None);
-                }
+                // Fall back onto the backup table only if the disk looks like a GPT disk
+                let (hdr, entries, from_backup) =
+                    match Self::read_gpt(dev, sectors_count, sector_size, 1)? {
+                        Ok((hdr, entries)) => (hdr, entries, false),
+                        Err(GptError::NoSignature) if !Self::has_protective_mbr(dev)? => {
+                            return Ok(None);
+                        }
+                        Err(_) => {
+                            let backup_lba = sectors_count.saturating_sub(1);
+                            match Self::read_gpt(dev, sectors_count, sector_size, backup_lba)? {
+                                Ok((hdr, entries)) => (hdr, entries, true),
+                                Err(_) => return Ok(None),
+                            }
+                        }
+                    };
 
-                let mut crc32_table: [u32; 256] = [0; 256];
-                crc32::compute_lookuptable(&mut crc32_table, GPT_CHECKSUM_POLYNOM);
+                let entry_size = hdr.entry_size as usize;
+                let parts = entries
+                    .chunks_exact(entry_size)
+                    .map(|buff| unsafe { &*(buff.as_ptr() as *const GptEntry) })
+                    .enumerate()
+                    // If entry is unused, skip
+                    .filter(|(_, entry)| entry.partition_type != [0; 16])
+                    .map(|(i, entry)| {
+                        (
+                            i + 1,
+                            Partition {
+                                start: entry.start as _,
+                                size: (entry.end - entry.start + 1) as _,
+
+                                part_type: PartitionType::Gpt(Uuid::from_bytes_le(
+                                    entry.partition_type,
+                                )),
+
+                                uuid: Some(Uuid::from_bytes_le(entry.guid)),
+
+                                bootable: false,
+
+                                name: entry.name(),
+                                attrs: PartitionAttrs(entry.attributes),
+                            },
+                        )
+                    });
 
-                // Check header checksum
-                let checksum = hdr.checksum;
-                hdr.checksum = 0;
-                // TODO computation must be done with the size of the header (dynamic)
-                if crc32::compute(&buff, &crc32_table) != checksum {
-                    // TODO invalid table
-                    todo!();
+                // Keep the layout only if it differs from the default
+                let entries_number = hdr.entries_number as usize;
+                let (first, last) =
+                    self.usable_range_for(entries_number, sectors_count, sector_size);
+                let first_usable = hdr.first_usable as u64;
+                let last_usable = hdr.last_usable as u64;
+                let mut table = PartitionTable {
+                    id: Some(LabelId::Gpt(Uuid::from_bytes_le(hdr.disk_guid))),
+                    first_lba: (first_usable != first).then_some(first_usable),
+                    last_lba: (last_usable != last).then_some(last_usable),
+                    table_length: (entries_number != self.max_partitions())
+                        .then_some(entries_number),
+                    from_backup,
+                    ..PartitionTable::new(Self::Gpt)
+                };
+                for (n, part) in parts {
+                    table.set(n, part);
                 }
+                Ok(Some(table))
+            }
+        }
+    }
 
-                // TODO check entries checksum
-                // TODO if entries checksum is invalid, use alternate table
-
-                let mut parts = Vec::new();
-
-                let sector_size = 512; // TODO
-                let entries_off =
-                    translate_lba(hdr.entries_start, sector_size).unwrap() * sector_size;
-
-                for i in 0..hdr.entries_number {
-                    let off = entries_off + i as u64 * hdr.entry_size as u64;
-
-                    let mut buff = vec![0; hdr.entry_size as usize];
-                    dev.seek(SeekFrom::Start(off as _))?;
-                    dev.read_exact(&mut buff)?;
-
-                    let entry = unsafe { &*(buff.as_ptr() as *const GptEntry) };
-
-                    // If entry is unused, skip
-                    if entry.guid.as_bytes().iter().all(|i| *i == 0) {
-                        continue;
-                    }
-
-                    // TODO handle negative lba
-                    parts.push(Partition {
-                        start: entry.start as _,
-                        size: (entry.end - entry.start) as _,
+    /// Tells whether the device has a MBR with a protective partition, meaning the disk uses GPT.
+    fn has_protective_mbr(dev: &mut (impl Read + Seek)) -> io::Result<bool> {
+        let mut buff: [u8; size_of::<MbrTable>()] = [0; size_of::<MbrTable>()];
+        dev.seek(SeekFrom::Start(0))?;
+        dev.read_exact(&mut buff)?;
+        let mbr = unsafe { &*(buff.as_ptr() as *const MbrTable) };
+        let partitions = mbr.partitions;
+        Ok(mbr.signature == MBR_SIGNATURE && partitions.iter().any(|p| p.partition_type == 0xee))
+    }
 
-                        part_type: PartitionType::Gpt(entry.partition_type),
+    /// Reads and validates the GPT header at `lba`, along with its entries array.
+    ///
+    /// The disk has `sectors_count` sectors of `sector_size` bytes.
+    ///
+    /// On success, the function returns the header and the content of the entries array.
+    fn read_gpt(
+        dev: &mut (impl Read + Seek),
+        sectors_count: u64,
+        sector_size: u64,
+        lba: u64,
+    ) -> io::Result<Result<(Gpt, Vec<u8>), GptError>> {
+        let mut buff = vec![0u8; sector_size as usize];
+        dev.seek(SeekFrom::Start(lba * sector_size))?;
+        dev.read_exact(&mut buff)?;
+        let hdr = unsafe { *(buff.as_ptr() as *const Gpt) };
+        if hdr.signature != GPT_SIGNATURE {
+            return Ok(Err(GptError::NoSignature));
+        }
 
-                        uuid: Some(entry.guid),
+        let mut crc32_table: [u32; 256] = [0; 256];
+        crc32::compute_lookuptable(&mut crc32_table, GPT_CHECKSUM_POLYNOM);
 
-                        bootable: false,
-                    });
-                }
+        // Check header checksum, computed with the checksum field zeroed
+        let hdr_size = hdr.hdr_size as usize;
+        if !(size_of::<Gpt>()..=buff.len()).contains(&hdr_size) {
+            return Ok(Err(GptError::InvalidHeader));
+        }
+        buff[16..20].fill(0);
+        if crc32::compute(&buff[..hdr_size], &crc32_table) != hdr.checksum {
+            return Ok(Err(GptError::InvalidHeader));
+        }
+        if translate_lba(hdr.hdr_lba, sectors_count) != Some(lba) {
+            return Ok(Err(GptError::InvalidHeader));
+        }
 
-                Ok(Some(parts))
-            }
+        // Read and check entries
+        let entry_size = hdr.entry_size as usize;
+        if entry_size < size_of::<GptEntry>() || !entry_size.is_multiple_of(8) {
+            return Ok(Err(GptError::InvalidHeader));
+        }
+        let entries_len = (hdr.entries_number as usize)
+            .checked_mul(entry_size)
+            .filter(|len| *len <= GPT_ENTRIES_MAX_SIZE)
+            .ok_or(GptError::InvalidHeader);
+        let entries_lba = translate_lba(hdr.entries_start, sectors_count)
+            .filter(|lba| *lba < sectors_count)
+            .ok_or(GptError::InvalidHeader);
+        let (entries_len, entries_lba) = match (entries_len, entries_lba) {
+            (Ok(len), Ok(lba)) => (len, lba),
+            (Err(e), _) | (_, Err(e)) => return Ok(Err(e)),
+        };
+        let mut entries = vec![0u8; entries_len];
+        dev.seek(SeekFrom::Start(entries_lba * sector_size))?;
+        if let Err(e) = dev.read_exact(&mut entries) {
+            return match e.kind() {
+                io::ErrorKind::UnexpectedEof => Ok(Err(GptError::InvalidEntries)),
+                _ => Err(e),
+            };
         }
+        if crc32::compute(&entries, &crc32_table) != hdr.entries_checksum {
+            return Ok(Err(GptError::InvalidEntries));
+        }
+        Ok(Ok((hdr, entries)))
     }
 
-    /// Writes a GPT header and partitions.
+    /// Writes a GPT header and its entries array.
+    ///
+    /// Arguments:
+    /// - `dev` is the device to write on.
+    /// - `hdr` is the header, whose `hdr_lba` and `entries_start` fields give the location of
+    ///   the writes.
+    /// - `entries` is the content of the entries array.
+    /// - `sector_size` is the size of a sector in bytes.
     fn write_gpt(
-        dev: &mut File,
-        storage_size: u64,
-        hdr_off: i64,
+        dev: &mut (impl Write + Seek),
         hdr: &Gpt,
-        parts: &[GptEntry],
+        entries: &[u8],
+        sector_size: u64,
     ) -> io::Result<()> {
-        let sector_size = 512; // TODO
-
-        let hdr_off = translate_lba(hdr_off, storage_size).unwrap() * sector_size;
-        let entries_off = translate_lba(hdr.entries_start, storage_size).unwrap() * sector_size;
-
-        for (i, entry) in parts.iter().enumerate() {
-            let off = entries_off + i as u64 * size_of::<GptEntry>() as u64;
-
-            let entry_slice = unsafe {
-                slice::from_raw_parts(entry as *const _ as *const _, size_of::<GptEntry>())
-            };
-            dev.seek(SeekFrom::Start(off))?;
-            dev.write_all(entry_slice)?;
-        }
+        dev.seek(SeekFrom::Start(hdr.entries_start as u64 * sector_size))?;
+        dev.write_all(entries)?;
 
+        // The rest of the header's sector is reserved and must be zero
+        let mut buff = vec![0u8; sector_size as usize];
         let hdr_slice =
-            unsafe { slice::from_raw_parts(hdr as *const _ as *const _, size_of::<Gpt>()) };
-        dev.seek(SeekFrom::Start(hdr_off))?;
-        dev.write_all(hdr_slice)?;
-
-        Ok(())
+            unsafe { slice::from_raw_parts(hdr as *const _ as *const u8, size_of::<Gpt>()) };
+        buff[..hdr_slice.len()].copy_from_slice(hdr_slice);
+        dev.seek(SeekFrom::Start(hdr.hdr_lba as u64 * sector_size))?;
+        dev.write_all(&buff)
     }
 
-    /// Writes the partitions table to the storage device represented by `dev`.
+    /// Writes the partitions table `table` to the storage device represented by `dev`.
     ///
     /// Arguments:
     /// - `dev` is the file representing the device.
-    /// - `partitions` is the list of partitions to be written.
+    /// - `table` is the table to write. If it has no identifier, a random one is used.
     /// - `sectors_count` is the number of sectors on the disk.
+    /// - `sector_size` is the size of a sector in bytes.
     pub fn write(
         &self,
-        dev: &mut File,
-        partitions: &[Partition],
+        dev: &mut (impl Write + Seek),
+        table: &PartitionTable,
         sectors_count: u64,
+        sector_size: u64,
     ) -> io::Result<()> {
+        let partitions = &table.partitions;
+        if partitions.len() > table.max_partitions() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "too many partitions",
+            ));
+        }
         match self {
             Self::Mbr => {
                 let mut mbr = MbrTable {
                     boot: [0; 440],
-                    disk_signature: 0,
+                    disk_signature: match table.id {
+                        Some(LabelId::Mbr(signature)) => signature,
+                        _ => 0,
+                    },
                     zero: 0,
                     partitions: [MbrPartition::default(); 4],
                     signature: MBR_SIGNATURE,
                 };
 
-                if partitions.len() > mbr.partitions.len() {
-                    // TODO error
-                    todo!();
-                }
-
-                for (i, p) in partitions.iter().enumerate() {
+                for (n, p) in table.iter() {
                     let PartitionType::Mbr(partition_type) = p.part_type else {
-                        panic!("invalid partition type of MBR table");
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "invalid partition type of MBR table",
+                        ));
+                    };
+                    let (Ok(lba_start), Ok(sectors_count)) =
+                        (u32::try_from(p.start), u32::try_from(p.size))
+                    else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "partition too large for a MBR table",
+                        ));
                     };
-                    mbr.partitions[i] = MbrPartition {
-                        attrs: 0,
+                    mbr.partitions[n - 1] = MbrPartition {
+                        attrs: if p.bootable { 1 << 7 } else { 0 },
                         chs_start: [0; 3],
                         partition_type,
                         chs_end: [0; 3],
-                        lba_start: p.start as _,
-                        sectors_count: p.size as _,
+                        lba_start,
+                        sectors_count,
                     };
                 }
 
@@ -1070,27 +1312,87 @@ impl PartitionTableType {
             }
 
             Self::Gpt => {
-                if partitions.len() > 128 {
-                    // TODO error
-                    todo!();
+                // Compute the layout of the disk
+                let entries_number = table.max_partitions();
+                let entries_size = entries_number * size_of::<GptEntry>();
+                let entries_sectors = (entries_size as u64).div_ceil(sector_size);
+                let (first_usable, last_usable) = table.usable_range(sectors_count, sector_size);
+                let backup_lba = sectors_count - 1;
+                let backup_entries_start = backup_lba - entries_sectors;
+                if first_usable < 2 + entries_sectors
+                    || last_usable >= backup_entries_start
+                    || first_usable > last_usable
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "the usable range overlaps with the GPT tables",
+                    ));
                 }
 
                 // Write protective MBR
                 Self::Mbr.write(
                     dev,
-                    &[Partition {
-                        start: 1,
-                        size: min(u32::MAX as u64, sectors_count - 1),
+                    &PartitionTable {
+                        partitions: vec![Some(Partition {
+                            start: 1,
+                            size: min(u32::MAX as u64, sectors_count - 1),
 
-                        part_type: PartitionType::Mbr(0xee),
+                            part_type: PartitionType::Mbr(0xee),
 
-                        uuid: None,
+                            uuid: None,
 
-                        bootable: true,
-                    }],
+                            bootable: false,
+
+                            name: None,
+                            attrs: PartitionAttrs::default(),
+                        })],
+                        ..PartitionTable::new(Self::Mbr)
+                    },
                     sectors_count,
+                    sector_size,
                 )?;
 
+                // Entries array, unused entries being zero
+                let mut entries = vec![0u8; entries_size];
+                for (p, buff) in partitions
+                    .iter()
+                    .zip(entries.chunks_exact_mut(size_of::<GptEntry>()))
+                    .filter_map(|(p, buff)| Some((p.as_ref()?, buff)))
+                {
+                    let PartitionType::Gpt(partition_type) = p.part_type else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "invalid partition type of GPT table",
+                        ));
+                    };
+                    let name = encode_gpt_name(p.name.as_deref().unwrap_or_default()).ok_or_else(
+                        || {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "partition name is too long",
+                            )
+                        },
+                    )?;
+                    let entry = GptEntry {
+                        partition_type: partition_type.to_bytes_le(),
+                        guid: p.uuid.unwrap_or_else(Uuid::new_v4).to_bytes_le(),
+                        start: p.start as _,
+                        end: (p.start + p.size - 1) as _,
+                        attributes: p.attrs.0,
+                        name,
+                    };
+                    let entry_slice = unsafe {
+                        slice::from_raw_parts(
+                            &entry as *const _ as *const u8,
+                            size_of::<GptEntry>(),
+                        )
+                    };
+                    buff.copy_from_slice(entry_slice);
+                }
+
+                let mut crc32_table: [u32; 256] = [0; 256];
+                crc32::compute_lookuptable(&mut crc32_table, GPT_CHECKSUM_POLYNOM);
+
                 // Primary table
                 let mut gpt = Gpt {
                     signature: [0; 8],
@@ -1099,63 +1401,29 @@ impl PartitionTableType {
                     checksum: 0,
                     reserved: 0,
                     hdr_lba: 1,
-                    alternate_hdr_lba: -1,
-                    first_usable: 34,
-                    last_usable: -34,
-                    disk_guid: Uuid::new_v4(),
+                    alternate_hdr_lba: backup_lba as _,
+                    first_usable: first_usable as _,
+                    last_usable: last_usable as _,
+                    disk_guid: match table.id {
+                        Some(LabelId::Gpt(guid)) => guid,
+                        _ => Uuid::new_v4(),
+                    }
+                    .to_bytes_le(),
                     entries_start: 2,
-                    entries_number: partitions.len() as _,
-                    entry_size: 128,
-                    entries_checksum: 0,
+                    entries_number: entries_number as _,
+                    entry_size: size_of::<GptEntry>() as _,
+                    entries_checksum: crc32::compute(&entries, &crc32_table),
                 };
                 gpt.signature.copy_from_slice(GPT_SIGNATURE);
+                gpt.checksum = gpt.compute_checksum(&crc32_table);
+                Self::write_gpt(dev, &gpt, &entries, sector_size)?;
 
-                let parts: Vec<GptEntry> = partitions
-                    .iter()
-                    .map(|p| {
-                        let PartitionType::Gpt(partition_type) = p.part_type else {
-                            panic!("invalid partition type of GPT table");
-                        };
-                        GptEntry {
-                            partition_type,
-                            guid: p.uuid.unwrap(),
-                            start: p.start as _,
-                            end: (p.start + p.size) as _,
-                            attributes: 0, // TODO
-                            name: [0; 36], // TODO
-                        }
-                    })
-                    .collect();
-
-                let mut crc32_table: [u32; 256] = [0; 256];
-                crc32::compute_lookuptable(&mut crc32_table, GPT_CHECKSUM_POLYNOM);
-
-                let parts_slice = unsafe {
-                    slice::from_raw_parts(
-                        parts.as_ptr() as *const u8,
-                        parts.len() * size_of::<GptEntry>(),
-                    )
-                };
-                gpt.entries_checksum = crc32::compute(parts_slice, &crc32_table);
-
-                let hdr_slice = unsafe {
-                    slice::from_raw_parts(&gpt as *const _ as *const u8, size_of::<Gpt>())
-                };
-                gpt.checksum = crc32::compute(hdr_slice, &crc32_table);
-
-                Self::write_gpt(dev, sectors_count, 1, &gpt, &parts)?;
-
-                // Alternate table
-                gpt.checksum = 0;
+                // Backup table
+                gpt.hdr_lba = backup_lba as _;
                 gpt.alternate_hdr_lba = 1;
-                gpt.entries_start = -33;
-                let hdr_slice = unsafe {
-                    slice::from_raw_parts(&gpt as *const _ as *const u8, size_of::<Gpt>())
-                };
-                gpt.checksum = crc32::compute(hdr_slice, &crc32_table);
-                Self::write_gpt(dev, sectors_count, -1, &gpt, &parts)?;
-
-                Ok(())
+                gpt.entries_start = backup_entries_start as _;
+                gpt.checksum = gpt.compute_checksum(&crc32_table);
+                Self::write_gpt(dev, &gpt, &entries, sector_size)
             }
         }
     }
@@ -1196,6 +1464,19 @@ impl FromStr for PartitionType {
     }
 }
 
+impl PartitionType {
+    /// Returns the name of the type, if known.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::Mbr(id) => MBR_TYPES.iter().find(|(i, _)| i == id).map(|(_, n)| *n),
+            Self::Gpt(uuid) => GPT_TYPES
+                .iter()
+                .find(|(_, u)| Uuid::from_str(u).is_ok_and(|u| u == *uuid))
+                .map(|(n, _)| *n),
+        }
+    }
+}
+
 impl fmt::Display for PartitionType {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1205,6 +1486,119 @@ impl fmt::Display for PartitionType {
     }
 }
 
+/// The identifier of a partition table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LabelId {
+    /// MBR disk signature.
+    Mbr(u32),
+    /// GPT disk GUID.
+    Gpt(Uuid),
+}
+
+impl LabelId {
+    /// Parses the identifier of a table of type `table_type`.
+    pub fn parse(table_type: PartitionTableType, s: &str) -> Option<Self> {
+        match table_type {
+            PartitionTableType::Mbr => {
+                let s = s.strip_prefix("0x").unwrap_or(s);
+                u32::from_str_radix(s, 16).ok().map(Self::Mbr)
+            }
+            PartitionTableType::Gpt => Uuid::from_str(s).ok().map(Self::Gpt),
+        }
+    }
+}
+
+impl fmt::Display for LabelId {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mbr(n) => write!(fmt, "0x{n:08x}"),
+            Self::Gpt(n) => write!(fmt, "{n}"),
+        }
+    }
+}
+
+/// GPT partition attribute flags.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PartitionAttrs(pub u64);
+
+impl PartitionAttrs {
+    /// The partition is required for the platform to function.
+    pub const REQUIRED: Self = Self(1 << 0);
+    /// The firmware must not provide block I/O access to the partition.
+    pub const NO_BLOCK_IO: Self = Self(1 << 1);
+    /// The partition may be bootable by legacy BIOS firmware.
+    pub const LEGACY_BIOS_BOOTABLE: Self = Self(1 << 2);
+    /// The bits whose meaning depends on the partition type.
+    pub const TYPE_SPECIFIC: Self = Self(0xffff << 48);
+
+    /// Tells whether no flag is set.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Tells whether all the flags of `other` are set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the type-specific bits (48 to 63).
+    pub fn type_specific(self) -> u16 {
+        (self.0 >> 48) as u16
+    }
+}
+
+impl BitOr for PartitionAttrs {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Parses GPT partition attribute flags, in the sfdisk format.
+///
+/// Flags are separated by spaces. Each flag is either the name of a flag, or `GUID:` followed by
+/// a comma-separated list of bit numbers.
+impl FromStr for PartitionAttrs {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut attrs = Self::default();
+        for flag in s.split_whitespace() {
+            if let Some(bits) = flag.strip_prefix("GUID:") {
+                for bit in bits.split(',') {
+                    let bit: u32 = bit.parse().ok().filter(|b| *b < 64).ok_or(())?;
+                    attrs.0 |= 1 << bit;
+                }
+            } else {
+                let (_, flag) = GPT_ATTRS.iter().find(|(name, _)| *name == flag).ok_or(())?;
+                attrs = attrs | *flag;
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+/// Formats GPT partition attribute flags, in the sfdisk format.
+impl fmt::Display for PartitionAttrs {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags: Vec<String> = GPT_ATTRS
+            .iter()
+            .filter(|(_, flag)| self.contains(*flag))
+            .map(|(name, _)| (*name).to_owned())
+            .collect();
+        let named = GPT_ATTRS.iter().fold(0, |bits, (_, flag)| bits | flag.0);
+        let bits: Vec<String> = (0..64)
+            .filter(|bit| self.0 & !named & (1 << bit) != 0)
+            .map(|bit: u32| bit.to_string())
+            .collect();
+        if !bits.is_empty() {
+            flags.push(format!("GUID:{}", bits.join(",")));
+        }
+        write!(fmt, "{}", flags.join(" "))
+    }
+}
+
 /// Information about a partition.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Partition {
@@ -1221,6 +1615,18 @@ pub struct Partition {
 
     /// Tells whether the partition is bootable.
     pub bootable: bool,
+
+    /// The partition's name (GPT only).
+    pub name: Option<String>,
+    /// The partition's attribute flags (GPT only).
+    pub attrs: PartitionAttrs,
+}
+
+impl Partition {
+    /// Tells whether the partition is a MBR extended partition, containing logical partitions.
+    pub fn is_extended(&self) -> bool {
+        matches!(self.part_type, PartitionType::Mbr(0x05 | 0x0f | 0x85))
+    }
 }
 
 impl fmt::Display for Partition {
@@ -1236,42 +1642,116 @@ impl fmt::Display for Partition {
         if let Some(ref uuid) = self.uuid {
             write!(fmt, ", uuid={uuid}")?;
         }
+        if let Some(name) = &self.name {
+            write!(fmt, ", name=\"{name}\"")?;
+        }
+        if !self.attrs.is_empty() {
+            write!(fmt, ", attrs=\"{}\"", self.attrs)?;
+        }
         Ok(())
     }
 }
 
-/// A partition table.
+/// A difference between two versions of a partition table.
+///
+/// Partitions are numbered from `1`.
 #[derive(Debug, Eq, PartialEq)]
+pub enum Change<'t> {
+    /// The type of the table changed.
+    TableType {
+        old: PartitionTableType,
+        new: PartitionTableType,
+    },
+    /// The identifier of the table changed.
+    TableId {
+        old: Option<LabelId>,
+        new: Option<LabelId>,
+    },
+    /// The first or last usable sector, or the maximum number of partitions changed.
+    TableLayout {
+        old: &'t PartitionTable,
+        new: &'t PartitionTable,
+    },
+    /// A partition has been added. `n` is its number in the new table.
+    Added { n: usize, part: &'t Partition },
+    /// A partition has been removed. `n` is its number in the old table.
+    Removed { n: usize, part: &'t Partition },
+    /// The size of a partition changed. `n` is its number in the new table.
+    Resized {
+        n: usize,
+        old: &'t Partition,
+        new: &'t Partition,
+    },
+    /// The type of a partition changed. `n` is its number in the new table.
+    Retyped {
+        n: usize,
+        old: &'t PartitionType,
+        new: &'t PartitionType,
+    },
+    /// Other attributes of a partition changed. `n` is its number in the new table.
+    Modified {
+        n: usize,
+        old: &'t Partition,
+        new: &'t Partition,
+    },
+}
+
+/// A partition table.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PartitionTable {
     /// The type of the partition table.
     pub table_type: PartitionTableType,
-    /// The list of partitions in the table.
-    pub partitions: Vec<Partition>,
+    /// The identifier of the table. If `None`, one is chosen when writing.
+    pub id: Option<LabelId>,
+    /// The first sector usable by partitions, if different from the default (GPT only).
+    pub first_lba: Option<u64>,
+    /// The last sector usable by partitions, if different from the default (GPT only).
+    pub last_lba: Option<u64>,
+    /// The maximum number of partitions, if different from the default (GPT only).
+    pub table_length: Option<usize>,
+    /// Tells whether the table has been read from the backup GPT header, because the primary one
+    /// is corrupt.
+    pub from_backup: bool,
+    /// The slots of the table. The partition in slot `i` has the number `i + 1`, and `None` is an
+    /// unused slot. The last slot is never `None`.
+    pub partitions: Vec<Option<Partition>>,
 }
 
 impl PartitionTable {
+    /// Returns a new empty table of the given type.
+    pub fn new(table_type: PartitionTableType) -> Self {
+        Self {
+            table_type,
+            id: None,
+            first_lba: None,
+            last_lba: None,
+            table_length: None,
+            from_backup: false,
+            partitions: vec![],
+        }
+    }
+
     /// Reads the partition table from the given device file.
     ///
     /// Arguments:
     /// - `dev` is the device to read from.
     /// - `sectors_count` is the number of sectors on the device.
+    /// - `sector_size` is the size of a sector in bytes.
     ///
     /// The cursor of the device might be changed by the function.
     ///
     /// If the table is invalid, the function returns an empty MBR table.
-    pub fn read(dev: &mut File, sectors_count: u64) -> io::Result<Self> {
+    pub fn read(
+        dev: &mut (impl Read + Seek),
+        sectors_count: u64,
+        sector_size: u64,
+    ) -> io::Result<Self> {
         for t in [PartitionTableType::Gpt, PartitionTableType::Mbr] {
-            if let Some(partitions) = t.read(dev, sectors_count)? {
-                return Ok(PartitionTable {
-                    table_type: t,
-                    partitions,
-                });
+            if let Some(table) = t.read(dev, sectors_count, sector_size)? {
+                return Ok(table);
             }
         }
-        Ok(PartitionTable {
-            table_type: PartitionTableType::Mbr,
-            partitions: vec![],
-        })
+        Ok(PartitionTable::new(PartitionTableType::Mbr))
     }
 
     /// Writes the partition table to the disk device.
@@ -1279,8 +1759,621 @@ impl PartitionTable {
     /// Arguments:
     /// - `dev` is the device to write on.
     /// - `sectors_count` is the number of sectors on the device.
-    pub fn write(&self, dev: &mut File, sectors_count: u64) -> io::Result<()> {
-        self.table_type.write(dev, &self.partitions, sectors_count)
+    /// - `sector_size` is the size of a sector in bytes.
+    pub fn write(
+        &self,
+        dev: &mut (impl Write + Seek),
+        sectors_count: u64,
+        sector_size: u64,
+    ) -> io::Result<()> {
+        self.table_type.write(dev, self, sectors_count, sector_size)
+    }
+
+    /// Returns an iterator over the partitions of the table with their numbers, starting at `1`.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Partition)> {
+        self.partitions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| Some((i + 1, p.as_ref()?)))
+    }
+
+    /// Returns the number of partitions in the table.
+    pub fn count(&self) -> usize {
+        self.partitions.iter().flatten().count()
+    }
+
+    /// Returns the partition with the number `n`, starting at `1`.
+    pub fn get(&self, n: usize) -> Option<&Partition> {
+        self.partitions.get(n.checked_sub(1)?)?.as_ref()
+    }
+
+    /// Returns a mutable reference to the partition with the number `n`, starting at `1`.
+    pub fn get_mut(&mut self, n: usize) -> Option<&mut Partition> {
+        self.partitions.get_mut(n.checked_sub(1)?)?.as_mut()
+    }
+
+    /// Returns the number of the first unused slot, starting at `1`.
+    pub fn first_free_slot(&self) -> usize {
+        self.partitions
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or(self.partitions.len())
+            + 1
+    }
+
+    /// Places `part` in the slot of number `n`, starting at `1`, replacing the previous partition
+    /// in that slot if any.
+    pub fn set(&mut self, n: usize, part: Partition) {
+        if self.partitions.len() < n {
+            self.partitions.resize(n, None);
+        }
+        self.partitions[n - 1] = Some(part);
+    }
+
+    /// Adds `part` in the first unused slot and returns its number.
+    pub fn add(&mut self, part: Partition) -> usize {
+        let n = self.first_free_slot();
+        self.set(n, part);
+        n
+    }
+
+    /// Removes the partition with the number `n`, starting at `1`. The numbers of the other
+    /// partitions do not change.
+    pub fn remove(&mut self, n: usize) -> Option<Partition> {
+        let part = self.partitions.get_mut(n.checked_sub(1)?)?.take();
+        while self.partitions.last().is_some_and(Option::is_none) {
+            self.partitions.pop();
+        }
+        part
+    }
+
+    /// Returns the first and last sectors (inclusive) usable by partitions on a disk with
+    /// `sectors_count` sectors of `sector_size` bytes.
+    pub fn usable_range(&self, sectors_count: u64, sector_size: u64) -> (u64, u64) {
+        let (first, last) =
+            self.table_type
+                .usable_range_for(self.max_partitions(), sectors_count, sector_size);
+        (
+            self.first_lba.unwrap_or(first),
+            self.last_lba.unwrap_or(last),
+        )
+    }
+
+    /// Returns the maximum number of partitions in the table.
+    pub fn max_partitions(&self) -> usize {
+        match self.table_type {
+            PartitionTableType::Mbr => 4,
+            PartitionTableType::Gpt => self
+                .table_length
+                .unwrap_or(self.table_type.max_partitions()),
+        }
+    }
+
+    /// Returns the list of changes to go from `self` to `new`.
+    ///
+    /// Partitions of both tables starting on the same sector are considered to be the same
+    /// partition. Removed partitions come first.
+    pub fn diff<'t>(&'t self, new: &'t Self) -> Vec<Change<'t>> {
+        let mut changes = vec![];
+        if self.table_type != new.table_type {
+            changes.push(Change::TableType {
+                old: self.table_type,
+                new: new.table_type,
+            });
+        }
+        if self.id != new.id {
+            changes.push(Change::TableId {
+                old: self.id,
+                new: new.id,
+            });
+        }
+        if (self.first_lba, self.last_lba, self.table_length)
+            != (new.first_lba, new.last_lba, new.table_length)
+        {
+            changes.push(Change::TableLayout { old: self, new });
+        }
+        let mut matched = vec![false; self.partitions.len() + 1];
+        let mut new_changes = vec![];
+        for (n, part) in new.iter() {
+            let old = self
+                .iter()
+                .find(|(j, old)| !matched[*j] && old.start == part.start);
+            let Some((j, old)) = old else {
+                new_changes.push(Change::Added { n, part });
+                continue;
+            };
+            matched[j] = true;
+            if old.size != part.size {
+                new_changes.push(Change::Resized { n, old, new: part });
+            }
+            if old.part_type != part.part_type {
+                new_changes.push(Change::Retyped {
+                    n,
+                    old: &old.part_type,
+                    new: &part.part_type,
+                });
+            }
+            if old.uuid != part.uuid
+                || old.bootable != part.bootable
+                || old.name != part.name
+                || old.attrs != part.attrs
+            {
+                new_changes.push(Change::Modified { n, old, new: part });
+            }
+        }
+        changes.extend(
+            self.iter()
+                .filter(|(n, _)| !matched[*n])
+                .map(|(n, part)| Change::Removed { n, part }),
+        );
+        changes.extend(new_changes);
+        changes
+    }
+
+    /// Returns the ranges of sectors (inclusive) that are not used by any partition, on a disk
+    /// with `sectors_count` sectors of `sector_size` bytes.
+    pub fn free_ranges(&self, sectors_count: u64, sector_size: u64) -> Vec<(u64, u64)> {
+        let (first, last) = self.usable_range(sectors_count, sector_size);
+        // Partitions whose start or size is not known yet are ignored
+        let mut parts: Vec<_> = self
+            .iter()
+            .map(|(_, p)| p)
+            .filter(|p| p.start > 0 && p.size > 0)
+            .map(|p| (p.start, p.start + p.size - 1))
+            .collect();
+        parts.sort_unstable();
+        let mut ranges = vec![];
+        let mut cur = first;
+        for (start, end) in parts {
+            if start > cur {
+                ranges.push((cur, min(start - 1, last)));
+            }
+            cur = max(cur, end.saturating_add(1));
+            if cur > last {
+                break;
+            }
+        }
+        if cur <= last {
+            ranges.push((cur, last));
+        }
+        ranges
+    }
+
+    /// Checks the consistency of the table for a disk with `sectors_count` sectors of
+    /// `sector_size` bytes.
+    ///
+    /// The function returns the list of problems found. Partitions are numbered from `1`.
+    pub fn verify(&self, sectors_count: u64, sector_size: u64) -> Vec<String> {
+        let mut problems = vec![];
+        let max_partitions = self.max_partitions();
+        if self.count() > max_partitions {
+            problems.push(format!(
+                "Too many partitions ({}, maximum is {max_partitions}).",
+                self.count()
+            ));
+        }
+        let (first, last) = self.usable_range(sectors_count, sector_size);
+        for (n, p) in self.iter() {
+            if n > max_partitions {
+                problems.push(format!(
+                    "Partition {n}: number is above the maximum ({max_partitions})."
+                ));
+            }
+            if p.size == 0 {
+                problems.push(format!("Partition {n}: empty partition."));
+                continue;
+            }
+            let valid_type = matches!(
+                (&self.table_type, &p.part_type),
+                (PartitionTableType::Mbr, PartitionType::Mbr(_))
+                    | (PartitionTableType::Gpt, PartitionType::Gpt(_))
+            );
+            if !valid_type {
+                problems.push(format!(
+                    "Partition {n}: type `{}` is invalid for a {} table.",
+                    p.part_type, self.table_type
+                ));
+            }
+            if p.name
+                .as_deref()
+                .is_some_and(|name| encode_gpt_name(name).is_none())
+            {
+                problems.push(format!(
+                    "Partition {n}: name is longer than {GPT_NAME_MAX_LEN} characters."
+                ));
+            }
+            let end = p.start + p.size - 1;
+            if self.table_type == PartitionTableType::Mbr && end > u32::MAX as u64 {
+                problems.push(format!(
+                    "Partition {n}: ends after sector {}, the last one addressable by a MBR table.",
+                    u32::MAX
+                ));
+            }
+            if p.start < first {
+                problems.push(format!(
+                    "Partition {n}: starts before the first usable sector ({first})."
+                ));
+            }
+            if end > last {
+                problems.push(format!(
+                    "Partition {n}: ends after the last usable sector ({last})."
+                ));
+            }
+            for (j, other) in self.iter().filter(|(j, _)| *j > n) {
+                if other.size > 0 && p.start < other.start + other.size && other.start <= end {
+                    problems.push(format!("Partition {n}: overlaps with partition {j}."));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Prompts for information related to a new partition to be created.
+    ///
+    /// Arguments:
+    /// - `sectors_count` is the number of sectors on the disk.
+    /// - `sector_size` is the size of a sector in bytes.
+    ///
+    /// The partition is not inserted in the table. It is meant to be added with [`Self::add`].
+    pub fn prompt_new_partition(
+        &self,
+        sectors_count: u64,
+        sector_size: u64,
+    ) -> io::Result<Partition> {
+        let n = self.first_free_slot();
+        if n > self.max_partitions() {
+            return Err(io::Error::other("all partitions are already in use"));
+        }
+        let free = self.free_ranges(sectors_count, sector_size);
+        let (Some(&(first_available, _)), Some(&(_, last_available))) = (free.first(), free.last())
+        else {
+            return Err(io::Error::other("no free sectors available"));
+        };
+
+        let part_type = match self.table_type {
+            PartitionTableType::Mbr => {
+                let extended = self.iter().filter(|(_, p)| p.is_extended()).count();
+                let primary = self.count() - extended;
+                let free = 4 - self.count();
+                println!("Partition type");
+                println!("   p   primary ({primary} primary, {extended} extended, {free} free)");
+                println!("   e   extended (container for logical partitions)");
+
+                if choice("Select", &["p", "e"], 0)? == 1 {
+                    if extended > 0 {
+                        return Err(io::Error::other("an extended partition already exists"));
+                    }
+                    PartitionType::Mbr(0x05)
+                } else {
+                    self.table_type.default_partition_type()
+                }
+            }
+            PartitionTableType::Gpt => self.table_type.default_partition_type(),
+        };
+
+        // The partition takes the first unused slot
+        println!("Selected partition {n}");
+
+        // Ask first sector, aligned on 1 MiB by default
+        let align = max((1 << 20) / sector_size, 1);
+        let default_start = free
+            .iter()
+            .find_map(|&(start, end)| Some(start.next_multiple_of(align)).filter(|s| *s <= end))
+            .unwrap_or(first_available);
+        let (start, range_end) = loop {
+            let start = number_in_range(
+                "First sector",
+                first_available,
+                last_available,
+                default_start,
+            )?;
+            if let Some(&(_, end)) = free.iter().find(|(s, e)| (*s..=*e).contains(&start)) {
+                break (start, end);
+            }
+            eprintln!("Sector {start} is already allocated.");
+        };
+
+        // Ask last sector
+        let end = end_in_range(
+            "Last sector, +/-sectors or +/-size{K,M,G,T,P}",
+            start,
+            range_end,
+            range_end,
+            sector_size,
+        )?;
+        let size = end - start + 1;
+
+        let uuid = match self.table_type {
+            PartitionTableType::Mbr => None,
+            PartitionTableType::Gpt => Some(Uuid::new_v4()),
+        };
+        Ok(Partition {
+            start,
+            size,
+
+            part_type,
+
+            uuid,
+
+            bootable: false,
+
+            name: None,
+            attrs: PartitionAttrs::default(),
+        })
+    }
+
+    /// Parses a sfdisk script.
+    ///
+    /// Arguments:
+    /// - `script` is the content of the script.
+    /// - `table_type` is the type of the table if the script has no `label` header.
+    /// - `sector_size` is the size of a sector in bytes, if the script has no `sector-size`
+    ///   header. It is used to convert sizes with a suffix into sectors.
+    ///
+    /// Omitted starts and sizes are set to zero. They are to be filled by
+    /// [`Self::fill_defaults`] once the disk is known.
+    ///
+    /// A partition takes the number at the end of its device, or the number following the
+    /// previous partition if the line has no device.
+    ///
+    /// If the script starts with `{`, it is read as JSON (see [`Self::from_json`]).
+    ///
+    /// On error, the function returns the line number and a description.
+    pub fn from_script(
+        script: &str,
+        table_type: PartitionTableType,
+        sector_size: u64,
+    ) -> Result<Self, (usize, String)> {
+        if script.trim_start().starts_with('{') {
+            return Self::from_json(script, table_type);
+        }
+        let mut table = Self::new(table_type);
+        let mut sector_size = sector_size;
+        let mut label_id = None;
+        let mut in_header = true;
+        for (i, line) in script.lines().enumerate() {
+            let n = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let header = line
+                .split_once(':')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(key, _)| SCRIPT_HEADERS.contains(key));
+            if let Some((key, value)) = header {
+                if !in_header {
+                    return Err((n, "headers must come before partitions".to_owned()));
+                }
+                let number = || {
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| (n, format!("invalid value for `{key}`: `{value}`")))
+                };
+                match key {
+                    "label" => {
+                        table.table_type = match value {
+                            "dos" => PartitionTableType::Mbr,
+                            "gpt" => PartitionTableType::Gpt,
+                            _ => return Err((n, format!("unsupported label `{value}`"))),
+                        };
+                    }
+                    "label-id" => label_id = Some((n, value)),
+                    "unit" if value != "sectors" => {
+                        return Err((n, format!("unsupported unit `{value}`")));
+                    }
+                    "first-lba" => table.first_lba = Some(number()?),
+                    "last-lba" => table.last_lba = Some(number()?),
+                    "table-length" => {
+                        let len = number()? as usize;
+                        let size = len.checked_mul(size_of::<GptEntry>());
+                        if len == 0 || size.is_none_or(|size| size > GPT_ENTRIES_MAX_SIZE) {
+                            return Err((n, format!("invalid value for `{key}`: `{value}`")));
+                        }
+                        table.table_length = Some(len);
+                    }
+                    "sector-size" => {
+                        sector_size = number()?;
+                        if sector_size == 0 {
+                            return Err((n, "the sector size cannot be zero".to_owned()));
+                        }
+                    }
+                    // `device` and `unit` are informative
+                    _ => {}
+                }
+                continue;
+            }
+            in_header = false;
+            let part = parse_script_partition(line, table.table_type, sector_size)
+                .map_err(|msg| (n, msg))?;
+            // Without a device, the partition follows the previous one
+            let number = match line.split_once(':') {
+                Some((dev, _)) if !dev.contains('=') => partition_number(dev.trim())
+                    .ok_or_else(|| (n, format!("invalid device `{}`", dev.trim())))?,
+                _ => table.partitions.len() + 1,
+            };
+            if table.get(number).is_some() {
+                return Err((n, format!("partition {number} is defined twice")));
+            }
+            table.set(number, part);
+        }
+        if let Some((n, id)) = label_id {
+            let id = LabelId::parse(table.table_type, id)
+                .ok_or_else(|| (n, format!("invalid value for `label-id`: `{id}`")))?;
+            table.id = Some(id);
+        }
+        Ok(table)
+    }
+
+    /// Fills the values omitted in a script (see [`Self::from_script`]), for a disk with
+    /// `sectors_count` sectors of `sector_size` bytes.
+    ///
+    /// A partition without start begins at the first free sector after the previous partition,
+    /// aligned on 1 MiB if possible. A partition without size takes all the free space after
+    /// its start. GPT partitions without UUID get a random one.
+    pub fn fill_defaults(&mut self, sectors_count: u64, sector_size: u64) -> Result<(), String> {
+        let align = max((1 << 20) / sector_size, 1);
+        let (first, _) = self.usable_range(sectors_count, sector_size);
+        let gpt = self.table_type == PartitionTableType::Gpt;
+        let mut prev_end = first;
+        for n in 1..=self.partitions.len() {
+            let free = self.free_ranges(sectors_count, sector_size);
+            let Some(p) = self.get_mut(n) else {
+                continue;
+            };
+            if p.start == 0 {
+                let Some(&(start, end)) = free.iter().find(|(_, end)| *end >= prev_end) else {
+                    return Err(format!("partition {n}: no free space left"));
+                };
+                let start = max(start, prev_end);
+                let aligned = start.next_multiple_of(align);
+                p.start = if aligned <= end { aligned } else { start };
+            }
+            if p.size == 0 {
+                let Some(&(_, end)) = free.iter().find(|(s, e)| (*s..=*e).contains(&p.start))
+                else {
+                    return Err(format!("partition {n}: sector {} is not free", p.start));
+                };
+                p.size = end - p.start + 1;
+            }
+            if gpt {
+                p.uuid.get_or_insert_with(Uuid::new_v4);
+            }
+            prev_end = p.start + p.size;
+        }
+        Ok(())
+    }
+
+    /// Parses a table in the JSON format of `sfdisk --json` (see [`Self::to_json`]).
+    ///
+    /// `table_type` is the type of the table if the `label` field is missing. Omitted values are
+    /// handled as in [`Self::from_script`].
+    ///
+    /// On error, the function returns the line number and a description. Errors in the content
+    /// of the table are reported on the first line.
+    pub fn from_json(json: &str, table_type: PartitionTableType) -> Result<Self, (usize, String)> {
+        let root = Value::parse(json)?;
+        Self::from_json_value(&root, table_type).map_err(|msg| (1, msg))
+    }
+
+    /// Parses a table from a JSON value. See [`Self::from_json`].
+    fn from_json_value(root: &Value, table_type: PartitionTableType) -> Result<Self, String> {
+        let Some(table_val) = root.get("partitiontable") else {
+            return Err("missing `partitiontable`".to_owned());
+        };
+        let invalid = |name: &str| format!("invalid value for `{name}`");
+        let string = |val: &Value, name: &str| -> Result<Option<String>, String> {
+            val.get(name)
+                .map(|v| v.as_str().map(str::to_owned).ok_or_else(|| invalid(name)))
+                .transpose()
+        };
+        let number = |val: &Value, name: &str| -> Result<Option<u64>, String> {
+            val.get(name)
+                .map(|v| v.as_u64().ok_or_else(|| invalid(name)))
+                .transpose()
+        };
+        let mut table = Self::new(table_type);
+        if let Some(label) = string(table_val, "label")? {
+            table.table_type = match label.as_str() {
+                "dos" => PartitionTableType::Mbr,
+                "gpt" => PartitionTableType::Gpt,
+                _ => return Err(format!("unsupported label `{label}`")),
+            };
+        }
+        if let Some(id) = string(table_val, "id")? {
+            table.id = Some(LabelId::parse(table.table_type, &id).ok_or_else(|| invalid("id"))?);
+        }
+        if string(table_val, "unit")?.is_some_and(|unit| unit != "sectors") {
+            return Err(invalid("unit"));
+        }
+        table.first_lba = number(table_val, "firstlba")?;
+        table.last_lba = number(table_val, "lastlba")?;
+        let partitions = match table_val.get("partitions") {
+            Some(val) => val.as_array().ok_or_else(|| invalid("partitions"))?,
+            None => &[],
+        };
+        for val in partitions {
+            let mut part = Partition {
+                start: number(val, "start")?.unwrap_or(0),
+                size: number(val, "size")?.unwrap_or(0),
+                part_type: table.table_type.default_partition_type(),
+                bootable: match val.get("bootable") {
+                    Some(b) => b.as_bool().ok_or_else(|| invalid("bootable"))?,
+                    None => false,
+                },
+                name: string(val, "name")?.filter(|name| !name.is_empty()),
+                ..Default::default()
+            };
+            if let Some(t) = string(val, "type")? {
+                part.part_type =
+                    parse_script_type(&t, table.table_type).ok_or_else(|| invalid("type"))?;
+            }
+            if let Some(uuid) = string(val, "uuid")? {
+                part.uuid = Some(Uuid::from_str(&uuid).map_err(|_| invalid("uuid"))?);
+            }
+            if let Some(attrs) = string(val, "attrs")? {
+                part.attrs = attrs.parse().map_err(|_| invalid("attrs"))?;
+            }
+            // Without a node, the partition follows the previous one
+            let number = match string(val, "node")? {
+                Some(node) => partition_number(&node).ok_or_else(|| invalid("node"))?,
+                None => table.partitions.len() + 1,
+            };
+            if table.get(number).is_some() {
+                return Err(format!("partition {number} is defined twice"));
+            }
+            table.set(number, part);
+        }
+        Ok(table)
+    }
+
+    /// Serializes the table in the JSON format of `sfdisk --json`.
+    ///
+    /// Arguments:
+    /// - `dev` is the path to the device file of the disk.
+    /// - `sectors_count` is the number of sectors on the disk.
+    /// - `sector_size` is the size of a sector in bytes.
+    pub fn to_json(&self, dev: &Path, sectors_count: u64, sector_size: u64) -> Value {
+        let string = |s: String| Value::String(s);
+        let mut table = vec![("label".to_owned(), string(self.table_type.to_string()))];
+        if let Some(id) = self.id {
+            table.push(("id".to_owned(), string(id.to_string())));
+        }
+        table.push(("device".to_owned(), string(dev.display().to_string())));
+        table.push(("unit".to_owned(), string("sectors".to_owned())));
+        if self.table_type == PartitionTableType::Gpt {
+            let (first, last) = self.usable_range(sectors_count, sector_size);
+            table.push(("firstlba".to_owned(), Value::Number(first)));
+            table.push(("lastlba".to_owned(), Value::Number(last)));
+        }
+        table.push(("sectorsize".to_owned(), Value::Number(sector_size)));
+        let partitions = self
+            .iter()
+            .map(|(n, p)| {
+                let node = partition_path(dev, n).display().to_string();
+                let mut part = vec![
+                    ("node".to_owned(), string(node)),
+                    ("start".to_owned(), Value::Number(p.start)),
+                    ("size".to_owned(), Value::Number(p.size)),
+                    ("type".to_owned(), string(p.part_type.to_string())),
+                ];
+                if p.bootable {
+                    part.push(("bootable".to_owned(), Value::Bool(true)));
+                }
+                if let Some(uuid) = p.uuid {
+                    part.push(("uuid".to_owned(), string(uuid.to_string())));
+                }
+                if let Some(name) = &p.name {
+                    part.push(("name".to_owned(), string(name.clone())));
+                }
+                if !p.attrs.is_empty() {
+                    part.push(("attrs".to_owned(), string(p.attrs.to_string())));
+                }
+                Value::Object(part)
+            })
+            .collect();
+        table.push(("partitions".to_owned(), Value::Array(partitions)));
+        Value::Object(vec![("partitiontable".to_owned(), Value::Object(table))])
     }
 
     /// Serializes a partitions list into a sfdisk script.
@@ -1292,120 +2385,180 @@ impl PartitionTable {
         let mut script = String::new();
 
         // Write header
-        // TODO label
-        // TODO label-id
+        script += format!("label: {}\n", self.table_type).as_str();
+        if let Some(id) = self.id {
+            script += format!("label-id: {id}\n").as_str();
+        }
         script += format!("device: {}\n", dev.display()).as_str();
         script += "unit: sectors\n";
+        if let Some(first_lba) = self.first_lba {
+            script += format!("first-lba: {first_lba}\n").as_str();
+        }
+        if let Some(last_lba) = self.last_lba {
+            script += format!("last-lba: {last_lba}\n").as_str();
+        }
+        if let Some(table_length) = self.table_length {
+            script += format!("table-length: {table_length}\n").as_str();
+        }
         script += "\n";
 
         // Write partitions
-        for (i, p) in self.partitions.iter().enumerate() {
-            script += &format!("{}{i} : {p}\n", dev.display());
+        for (n, p) in self.iter() {
+            script += &format!("{} : {p}\n", partition_path(dev, n).display());
         }
 
         script
     }
 }
 
-impl FromStr for PartitionTable {
-    type Err = String;
-
-    /// Deserializes a partitions list from a given sfdisk script.
-    fn from_str(script: &str) -> Result<Self, Self::Err> {
-        // Skip header
-        let mut iter = script.split('\n');
-        for line in iter.by_ref() {
-            if line.trim().is_empty() {
-                break;
+/// Splits `s` on commas that are not between double quotes.
+fn split_fields(s: &str) -> Vec<&str> {
+    let mut fields = vec![];
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(&s[start..i]);
+                start = i + 1;
             }
+            _ => {}
         }
+    }
+    fields.push(&s[start..]);
+    fields
+}
 
-        // Parse partitions
-        let partitions = iter
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| {
-                let mut split = line.split(':').skip(1);
-                let Some(values) = split.next() else {
-                    return Err("Invalid syntax".to_owned());
-                };
-
-                // Fill partition structure
-                let mut part = Partition::default();
-                for v in values.split(',') {
-                    let mut split = v.split('=');
-                    let Some(name) = split.next() else {
-                        return Err("Invalid syntax".to_owned());
-                    };
-
-                    let name = name.trim();
-                    let value = split.next().map(str::trim);
-
-                    match name {
-                        "start" => {
-                            let Some(val) = value else {
-                                return Err("`start` requires a value".into());
-                            };
-                            let Ok(v) = val.parse() else {
-                                return Err(format!("Invalid value for `start`: {val}"));
-                            };
-                            part.start = v;
-                        }
-
-                        "size" => {
-                            let Some(val) = value else {
-                                return Err("`size` requires a value".into());
-                            };
-                            let Ok(v) = val.parse() else {
-                                return Err(format!("Invalid value for `size`: {val}"));
-                            };
-                            part.size = v;
-                        }
+/// Parses a number of sectors in a script. A number with a size suffix is a number of bytes.
+///
+/// An empty value, `-` or `+` means the default and is returned as zero.
+fn parse_script_sectors(value: &str, sector_size: u64) -> Option<u64> {
+    match value {
+        "" | "-" | "+" => Some(0),
+        _ if value.bytes().all(|b| b.is_ascii_digit()) => value.parse().ok(),
+        _ => parse_size(value).map(|size| size / sector_size),
+    }
+}
 
-                        "type" => {
-                            let Some(val) = value else {
-                                return Err("`type` requires a value".into());
-                            };
-                            let Ok(v) = PartitionType::from_str(val) else {
-                                return Err(format!("Invalid value for `type`: {val}"));
-                            };
-                            part.part_type = v;
-                        }
+/// Parses a partition type in a script. An empty value or `-` means the default type.
+fn parse_script_type(value: &str, table_type: PartitionTableType) -> Option<PartitionType> {
+    match (value, table_type) {
+        ("" | "-", _) => Some(table_type.default_partition_type()),
+        // Indexes in the list of GPT types would be mistaken for MBR types
+        (_, PartitionTableType::Gpt) if value.bytes().all(|b| b.is_ascii_digit()) => None,
+        // Only types of the table's kind are accepted
+        _ => table_type.parse_partition_type(value.strip_prefix("0x").unwrap_or(value)),
+    }
+}
 
-                        "uuid" => {
-                            let Some(val) = value else {
-                                return Err("`uuid` requires a value".into());
-                            };
-                            let Ok(val) = Uuid::from_str(val) else {
-                                return Err(format!("Invalid value for `uuid`: {val}"));
-                            };
-                            part.uuid = Some(val);
-                        }
+/// Parses a partition line of a sfdisk script.
+///
+/// The line is either a list of named fields, optionally prefixed by the device and a colon
+/// (`/dev/sda1 : start=2048, size=+1G, type=L`), or the shorthand `start,size,type,bootable`
+/// (`,1G,L,*`), whose fields may also be separated by spaces.
+fn parse_script_partition(
+    line: &str,
+    table_type: PartitionTableType,
+    sector_size: u64,
+) -> Result<Partition, String> {
+    let mut part = Partition {
+        part_type: table_type.default_partition_type(),
+        ..Default::default()
+    };
+    let sectors = |name: &str, value: &str| {
+        parse_script_sectors(value, sector_size)
+            .ok_or_else(|| format!("invalid value for `{name}`: `{value}`"))
+    };
+    let part_type = |value: &str| {
+        parse_script_type(value, table_type)
+            .ok_or_else(|| format!("invalid value for `type`: `{value}`"))
+    };
+    if !line.contains(['=', ':']) {
+        let fields: Vec<_> = if line.contains(',') {
+            line.split(',').map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        if fields.len() > 4 {
+            return Err("too many fields".to_owned());
+        }
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+        part.start = sectors("start", field(0))?;
+        part.size = sectors("size", field(1).strip_prefix('+').unwrap_or(field(1)))?;
+        part.part_type = part_type(field(2))?;
+        part.bootable = match field(3) {
+            "" | "-" => false,
+            "*" => true,
+            value => return Err(format!("invalid value for `bootable`: `{value}`")),
+        };
+        return Ok(part);
+    }
+    // Skip the device
+    let fields = match line.split_once(':') {
+        Some((dev, fields)) if !dev.contains('=') => fields,
+        _ => line,
+    };
+    for field in split_fields(fields) {
+        let field = field.trim();
+        if field.is_empty() {
+            continue;
+        }
+        let (name, value) = match field.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (field, None),
+        };
+        if name == "bootable" {
+            part.bootable = true;
+            continue;
+        }
+        let Some(value) = value else {
+            return Err(format!("invalid field `{field}`"));
+        };
+        let unquoted = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        match name {
+            "start" => part.start = sectors(name, value)?,
+            "size" => part.size = sectors(name, value.strip_prefix('+').unwrap_or(value))?,
+            "type" | "Id" => part.part_type = part_type(unquoted)?,
+            "uuid" => {
+                let uuid = Uuid::from_str(unquoted)
+                    .map_err(|_| format!("invalid value for `uuid`: `{value}`"))?;
+                part.uuid = Some(uuid);
+            }
+            "name" => part.name = Some(unquoted.to_owned()).filter(|name| !name.is_empty()),
+            "attrs" => {
+                part.attrs = unquoted
+                    .parse()
+                    .map_err(|_| format!("invalid value for `attrs`: `{value}`"))?;
+            }
+            _ => return Err(format!("unknown field `{name}`")),
+        }
+    }
+    Ok(part)
+}
 
-                        "bootable" => part.bootable = true,
+impl FromStr for PartitionTable {
+    type Err = (usize, String);
 
-                        _ => return Err(format!("Unknown attribute: `{name}`")),
-                    }
-                }
-                Ok(part)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Self {
-            table_type: PartitionTableType::Mbr, // TODO
-            partitions,
-        })
+    /// Parses a sfdisk script for a MBR table by default, with 512 bytes sectors.
+    ///
+    /// See [`PartitionTable::from_script`].
+    fn from_str(script: &str) -> Result<Self, Self::Err> {
+        Self::from_script(script, PartitionTableType::Mbr, 512)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io::Cursor;
 
     #[test]
     fn partitions_serialize0() {
-        let table0 = PartitionTable {
-            table_type: PartitionTableType::Mbr,
-            partitions: vec![],
-        };
+        let table0 = PartitionTable::new(PartitionTableType::Mbr);
 
         let script = table0.serialize(Path::new("/dev/sda"));
         let table1 = PartitionTable::from_str(&script).unwrap();
@@ -1416,8 +2569,7 @@ mod test {
     #[test]
     fn partitions_serialize1() {
         let table0 = PartitionTable {
-            table_type: PartitionTableType::Mbr,
-            partitions: vec![Partition {
+            partitions: vec![Some(Partition {
                 start: 0,
                 size: 1,
 
@@ -1428,7 +2580,11 @@ mod test {
                 ])),
 
                 bootable: false,
-            }],
+
+                name: Some("root".to_owned()),
+                attrs: PartitionAttrs::LEGACY_BIOS_BOOTABLE,
+            })],
+            ..PartitionTable::new(PartitionTableType::Mbr)
         };
 
         let script = table0.serialize(Path::new("/dev/sda"));
@@ -1437,5 +2593,361 @@ mod test {
         assert_eq!(table0, table1);
     }
 
+    #[test]
+    fn partitions_free_ranges() {
+        let part = |start, size| Partition {
+            start,
+            size,
+            part_type: PartitionType::Mbr(0x83),
+            ..Default::default()
+        };
+        let mut table = PartitionTable {
+            partitions: vec![Some(part(4096, 2048)), Some(part(2048, 1024))],
+            ..PartitionTable::new(PartitionTableType::Mbr)
+        };
+        assert_eq!(
+            table.free_ranges(10000, 512),
+            [(1, 2047), (3072, 4095), (6144, 9999)]
+        );
+        assert!(table.verify(10000, 512).is_empty());
+        // Overlap and out of bounds
+        table.add(part(6000, 5000));
+        assert_eq!(table.free_ranges(10000, 512), [(1, 2047), (3072, 4095)]);
+        assert_eq!(table.verify(10000, 512).len(), 2);
+    }
+
+    #[test]
+    fn partitions_diff() {
+        let part = |start, size, id| Partition {
+            start,
+            size,
+            part_type: PartitionType::Mbr(id),
+            ..Default::default()
+        };
+        let old = PartitionTable {
+            partitions: vec![Some(part(2048, 1024, 0x83)), Some(part(4096, 1024, 0x83))],
+            ..PartitionTable::new(PartitionTableType::Mbr)
+        };
+        assert!(old.diff(&old).is_empty());
+        // The freed slot is reused by the new partition
+        let mut new = old.clone();
+        new.remove(1);
+        let p = new.get_mut(2).unwrap();
+        p.size = 2048;
+        p.part_type = PartitionType::Mbr(0x82);
+        assert_eq!(new.add(part(8192, 1024, 0x83)), 1);
+        let (old1, old2) = (old.get(1).unwrap(), old.get(2).unwrap());
+        let (new1, new2) = (new.get(1).unwrap(), new.get(2).unwrap());
+        assert_eq!(
+            old.diff(&new),
+            [
+                Change::Removed { n: 1, part: old1 },
+                Change::Added { n: 1, part: new1 },
+                Change::Resized {
+                    n: 2,
+                    old: old2,
+                    new: new2
+                },
+                Change::Retyped {
+                    n: 2,
+                    old: &PartitionType::Mbr(0x83),
+                    new: &PartitionType::Mbr(0x82)
+                },
+            ]
+        );
+        // Names, attributes and layout
+        let mut new = old.clone();
+        new.get_mut(1).unwrap().name = Some("root".to_owned());
+        new.get_mut(2).unwrap().attrs = PartitionAttrs::REQUIRED;
+        new.first_lba = Some(4096);
+        assert_eq!(
+            old.diff(&new),
+            [
+                Change::TableLayout {
+                    old: &old,
+                    new: &new
+                },
+                Change::Modified {
+                    n: 1,
+                    old: old.get(1).unwrap(),
+                    new: new.get(1).unwrap()
+                },
+                Change::Modified {
+                    n: 2,
+                    old: old.get(2).unwrap(),
+                    new: new.get(2).unwrap()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn partitions_script() {
+        let script = "label: gpt\n\
+            label-id: 01234567-89ab-cdef-0123-456789abcdef\n\
+            device: /dev/sda\n\
+            unit: sectors\n\
+            first-lba: 2048\n\
+            sector-size: 4096\n\
+            \n\
+            # comment\n\
+            /dev/sda1 : size=+512M, type=U, name=\"EFI, system\", attrs=\"RequiredPartition GUID:60,63\"\n\
+            ,1G,S\n\
+            - - L\n";
+        let mut table = PartitionTable::from_str(script).unwrap();
+        assert_eq!(table.table_type, PartitionTableType::Gpt);
+        assert_eq!(
+            table.id,
+            LabelId::parse(
+                PartitionTableType::Gpt,
+                "01234567-89ab-cdef-0123-456789abcdef"
+            )
+        );
+        assert_eq!(table.first_lba, Some(2048));
+        let efi = table.get(1).unwrap();
+        assert_eq!((efi.start, efi.size), (0, 131072));
+        assert_eq!(efi.name.as_deref(), Some("EFI, system"));
+        assert!(efi.attrs.contains(PartitionAttrs::REQUIRED));
+        assert_eq!(efi.attrs.type_specific(), (1 << 12) | (1 << 15));
+        assert_eq!(efi.attrs.to_string(), "RequiredPartition GUID:60,63");
+        assert_eq!(table.get(2).unwrap().size, 262144);
+        assert_eq!(
+            table.get(3).unwrap().part_type,
+            PartitionTableType::Gpt.default_partition_type()
+        );
+        // Omitted values
+        table.fill_defaults(1 << 20, 4096).unwrap();
+        let ranges: Vec<_> = table.iter().map(|(_, p)| (p.start, p.size)).collect();
+        assert_eq!(
+            ranges,
+            [
+                (2048, 131072),
+                (133120, 262144),
+                (395264, 1048576 - 6 - 395264 + 1)
+            ]
+        );
+        assert!(table.iter().all(|(_, p)| p.uuid.is_some()));
+        // Errors
+        let err = |script| PartitionTable::from_str(script).unwrap_err();
+        assert_eq!(err("label: dos\nunit: bytes\n").0, 2);
+        assert_eq!(err("label: dos\n\n,,,*\nstart=abc\n").0, 4);
+        assert_eq!(err(",1G\nlabel: gpt\n").0, 2);
+        assert_eq!(err("start=2048, foo=1\n").0, 1);
+        assert_eq!(err("label: gpt\ntable-length: 0\n").0, 2);
+        // The entries array grows with the table length
+        let table = PartitionTable::from_str("label: gpt\ntable-length: 256\n").unwrap();
+        assert_eq!(table.usable_range(1 << 20, 512), (66, (1 << 20) - 66));
+        // Types of the other kind of table
+        assert_eq!(err("label: gpt\n\n,,83\n").0, 3);
+        assert_eq!(
+            err("label: dos\n,,0fc63daf-8483-4772-8e79-3d69d8477de4\n").0,
+            2
+        );
+        assert_eq!(err("/dev/sda1 : size=1\n/dev/sda1 : size=2\n").0, 2);
+        // Device numbers are kept, and following partitions come after them
+        let table = PartitionTable::from_str("/dev/sda2 : size=1\nsize=2\n").unwrap();
+        assert_eq!(table.partitions[0], None);
+        assert_eq!(table.get(3).unwrap().size, 2);
+        let script = table.serialize(Path::new("/dev/sda"));
+        assert!(script.contains("/dev/sda2 : "));
+        assert_eq!(PartitionTable::from_str(&script).unwrap(), table);
+    }
+
+    #[test]
+    fn partitions_json() {
+        let table0 = PartitionTable {
+            id: LabelId::parse(
+                PartitionTableType::Gpt,
+                "01234567-89ab-cdef-0123-456789abcdef",
+            ),
+            first_lba: Some(2048),
+            last_lba: Some(20446),
+            partitions: vec![Some(Partition {
+                start: 2048,
+                size: 4096,
+                part_type: PartitionTableType::Gpt.default_partition_type(),
+                uuid: Some(Uuid::from_u128(1)),
+                name: Some("root \"fs\"".to_owned()),
+                attrs: PartitionAttrs::LEGACY_BIOS_BOOTABLE,
+                ..Default::default()
+            })],
+            ..PartitionTable::new(PartitionTableType::Gpt)
+        };
+        let json = table0
+            .to_json(Path::new("/dev/nvme0n1"), 20480, 512)
+            .to_string();
+        assert!(json.contains("\"node\": \"/dev/nvme0n1p1\""));
+        let table1 = PartitionTable::from_str(&json).unwrap();
+        assert_eq!(table0, table1);
+        assert_eq!(
+            PartitionTable::from_str("{\"partitiontable\": {\"label\": \"sun\"}}")
+                .unwrap_err()
+                .0,
+            1
+        );
+    }
+
+    #[test]
+    fn partitions_mbr_slots() {
+        let part = |start| Partition {
+            start,
+            size: 1024,
+            part_type: PartitionType::Mbr(0x83),
+            ..Default::default()
+        };
+        let mut table = PartitionTable {
+            partitions: vec![Some(part(2048)), Some(part(4096)), Some(part(6144))],
+            ..PartitionTable::new(PartitionTableType::Mbr)
+        };
+        // Deleting a partition does not renumber the following ones
+        assert_eq!(table.remove(1), Some(part(2048)));
+        assert_eq!(table.get(2), Some(&part(4096)));
+        let mut dev = Cursor::new(vec![0u8; 10000 * 512]);
+        table.write(&mut dev, 10000, 512).unwrap();
+        assert_eq!(PartitionTable::read(&mut dev, 10000, 512).unwrap(), table);
+        // Trailing unused slots are dropped
+        table.remove(3);
+        assert_eq!(table.partitions.len(), 2);
+        assert_eq!(table.first_free_slot(), 1);
+        // Sectors are addressed on 32 bits, even on larger disks
+        let sectors_count = 1 << 33;
+        let (_, last) = table.usable_range(sectors_count, 512);
+        assert_eq!(last, u32::MAX as u64);
+        table.add(Partition {
+            size: 1 << 32,
+            ..part(8192)
+        });
+        assert_eq!(table.verify(sectors_count, 512).len(), 2);
+        assert!(table.write(&mut dev, sectors_count, 512).is_err());
+    }
+
+    /// Returns a GPT table with two partitions.
+    fn gpt_table() -> PartitionTable {
+        let part_type = PartitionTableType::Gpt.default_partition_type();
+        PartitionTable {
+            id: Some(LabelId::Gpt(Uuid::new_v4())),
+            partitions: vec![
+                Some(Partition {
+                    start: 2048 / 4,
+                    size: 512,
+
+                    part_type: part_type.clone(),
+
+                    uuid: Some(Uuid::new_v4()),
+
+                    bootable: false,
+
+                    name: Some("EFI système".to_owned()),
+                    attrs: PartitionAttrs::REQUIRED | PartitionAttrs(1 << 63),
+                }),
+                Some(Partition {
+                    start: 1024,
+                    size: 990,
+
+                    part_type,
+
+                    uuid: Some(Uuid::new_v4()),
+
+                    bootable: false,
+
+                    name: None,
+                    attrs: PartitionAttrs::default(),
+                }),
+            ],
+            ..PartitionTable::new(PartitionTableType::Gpt)
+        }
+    }
+
+    #[test]
+    fn partitions_gpt_roundtrip() {
+        let sectors_count = 2048;
+        let mut dev = Cursor::new(vec![0u8; sectors_count as usize * 512]);
+        let table = gpt_table();
+        table.write(&mut dev, sectors_count, 512).unwrap();
+        let read = PartitionTable::read(&mut dev, sectors_count, 512).unwrap();
+        assert_eq!(read, table);
+
+        let img = dev.get_ref();
+        // Protective MBR
+        assert_eq!(img[446], 0);
+        assert_eq!(img[446 + 4], 0xee);
+        // Absolute LBAs and full-sized entries array in both headers
+        for (lba, alternate, entries_start) in [(1, 2047, 2), (2047, 1, 2015)] {
+            let hdr = unsafe { *(img[lba * 512..].as_ptr() as *const Gpt) };
+            assert_eq!({ hdr.hdr_lba }, lba as i64);
+            assert_eq!({ hdr.alternate_hdr_lba }, alternate);
+            assert_eq!({ hdr.first_usable }, 34);
+            assert_eq!({ hdr.last_usable }, 2014);
+            assert_eq!({ hdr.entries_start }, entries_start);
+            assert_eq!({ hdr.entries_number }, 128);
+        }
+        // End LBAs are inclusive
+        let entry = unsafe { &*(img[2 * 512..].as_ptr() as *const GptEntry) };
+        assert_eq!({ entry.end }, 1023);
+        // GUIDs use the mixed-endian layout
+        let mut table = gpt_table();
+        table.get_mut(1).unwrap().part_type =
+            PartitionTableType::Gpt.parse_partition_type("U").unwrap();
+        table.write(&mut dev, sectors_count, 512).unwrap();
+        let img = dev.get_ref();
+        assert_eq!(
+            img[2 * 512..2 * 512 + 16],
+            [
+                0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e,
+                0xc9, 0x3b
+            ]
+        );
+
+        // A missing UUID is generated
+        let mut table = gpt_table();
+        table.get_mut(1).unwrap().uuid = None;
+        table.write(&mut dev, sectors_count, 512).unwrap();
+        let read = PartitionTable::read(&mut dev, sectors_count, 512).unwrap();
+        assert!(read.get(1).unwrap().uuid.is_some());
+        // Name too long
+        table.get_mut(1).unwrap().name = Some("x".repeat(37));
+        assert!(table.write(&mut dev, sectors_count, 512).is_err());
+        // Too many partitions
+        table.table_length = Some(1);
+        assert!(table.write(&mut dev, sectors_count, 512).is_err());
+    }
+
+    #[test]
+    fn partitions_gpt_4k() {
+        let sectors_count = 2048;
+        let mut dev = Cursor::new(vec![0u8; sectors_count as usize * 4096]);
+        let table = gpt_table();
+        table.write(&mut dev, sectors_count, 4096).unwrap();
+        let read = PartitionTable::read(&mut dev, sectors_count, 4096).unwrap();
+        assert_eq!(read, table);
+        // The header is in the second sector, and the entries array takes 4 sectors
+        let hdr = unsafe { *(dev.get_ref()[4096..].as_ptr() as *const Gpt) };
+        assert_eq!(&hdr.signature, GPT_SIGNATURE);
+        assert_eq!({ hdr.first_usable }, 6);
+        assert_eq!({ hdr.last_usable }, 2042);
+        // The table cannot be found with the wrong sector size
+        let read = PartitionTable::read(&mut dev, sectors_count * 8, 512).unwrap();
+        assert_eq!(read.table_type, PartitionTableType::Mbr);
+    }
+
+    #[test]
+    fn partitions_gpt_backup() {
+        let sectors_count = 2048;
+        let mut dev = Cursor::new(vec![0u8; sectors_count as usize * 512]);
+        let table = gpt_table();
+        table.write(&mut dev, sectors_count, 512).unwrap();
+        let read = PartitionTable::read(&mut dev, sectors_count, 512).unwrap();
+        assert!(!read.from_backup);
+        // Corrupt the primary header
+        dev.get_mut()[512 + 24] = 0xff;
+        let read = PartitionTable::read(&mut dev, sectors_count, 512).unwrap();
+        assert!(read.from_backup);
+        assert_eq!(read.partitions, table.partitions);
+        // Corrupt the backup entries too
+        dev.get_mut()[(sectors_count as usize - 33) * 512] = 0xff;
+        let read = PartitionTable::read(&mut dev, sectors_count, 512).unwrap();
+        assert_eq!(read.table_type, PartitionTableType::Mbr);
+    }
+
     // TODO More tests (especially invalid scripts)
 }
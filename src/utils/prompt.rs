@@ -17,54 +17,767 @@
  */
 
 //! This module implements prompting.
+//!
+//! When the standard input is a terminal, non-hidden prompts are read with a line editor
+//! supporting cursor movement, history and completion. Otherwise, the terminal's canonical mode
+//! is used.
 
 use libc::ECHO;
 use libc::ECHOE;
 use libc::ICANON;
+use libc::POLLIN;
+use libc::SIG_DFL;
+use libc::SIG_IGN;
+use libc::SIGHUP;
+use libc::SIGINT;
+use libc::SIGTERM;
 use libc::STDIN_FILENO;
 use libc::TCSANOW;
 use libc::VMIN;
+use libc::VTIME;
+use libc::c_int;
+use libc::pollfd;
+use libc::sighandler_t;
 use libc::tcgetattr;
 use libc::tcsetattr;
 use libc::termios;
 use std::io::BufRead;
+use std::io::ErrorKind;
+use std::io::Read;
 use std::io::Write;
 use std::mem::MaybeUninit;
+use std::ptr::null_mut;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::time::{Duration, Instant};
 use std::{fmt, io};
 
-// TODO Add line edition
+/// The maximum number of lines kept in an editor's history.
+const HISTORY_MAX: usize = 500;
+
+/// Signals upon which the terminal mode is restored before terminating.
+const RESTORE_SIGNALS: [c_int; 3] = [SIGINT, SIGTERM, SIGHUP];
+
+/// The terminal mode to restore from signal handlers. If null, there is nothing to restore.
+static SAVED_TERMIOS: AtomicPtr<termios> = AtomicPtr::new(null_mut());
+
+/// Signal handler restoring the terminal mode, then terminating with the default action of the
+/// signal.
+extern "C" fn restore_on_signal(sig: c_int) {
+    let saved = SAVED_TERMIOS.load(Ordering::SeqCst);
+    unsafe {
+        if !saved.is_null() {
+            tcsetattr(STDIN_FILENO, TCSANOW, saved);
+        }
+        libc::signal(sig, SIG_DFL);
+        libc::raise(sig);
+    }
+}
+
+/// Guard changing the mode of the terminal on the standard input.
+///
+/// The previous mode is restored when the guard is dropped, or if the process is terminated by
+/// `SIGINT`, `SIGTERM` or `SIGHUP` in the meantime.
+pub struct TermGuard {
+    /// The mode to restore. Boxed so that signal handlers can access it.
+    saved: Box<termios>,
+    /// The signal handlers to restore.
+    handlers: [sighandler_t; RESTORE_SIGNALS.len()],
+}
+
+impl TermGuard {
+    /// Sets the terminal mode to the current one, modified by `f`.
+    pub fn new<F: FnOnce(&mut termios)>(f: F) -> Self {
+        let saved = Box::new(unsafe {
+            let mut t: termios = MaybeUninit::zeroed().assume_init();
+            tcgetattr(STDIN_FILENO, &mut t);
+            t
+        });
+        let mut termios = *saved;
+        f(&mut termios);
+        // Install handlers before changing the mode
+        SAVED_TERMIOS.store(&*saved as *const _ as *mut _, Ordering::SeqCst);
+        let handler = restore_on_signal as extern "C" fn(c_int) as sighandler_t;
+        let handlers = RESTORE_SIGNALS.map(|sig| unsafe {
+            let prev = libc::signal(sig, handler);
+            // Keep ignored signals ignored
+            if prev == SIG_IGN {
+                libc::signal(sig, SIG_IGN);
+            }
+            prev
+        });
+        unsafe {
+            tcsetattr(STDIN_FILENO, TCSANOW, &termios);
+        }
+        Self { saved, handlers }
+    }
+}
+
+impl Drop for TermGuard {
+    fn drop(&mut self) {
+        unsafe {
+            tcsetattr(STDIN_FILENO, TCSANOW, &*self.saved);
+        }
+        SAVED_TERMIOS.store(null_mut(), Ordering::SeqCst);
+        for (sig, handler) in RESTORE_SIGNALS.into_iter().zip(self.handlers) {
+            unsafe {
+                libc::signal(sig, handler);
+            }
+        }
+    }
+}
+
 /// Show a prompt. This function returns when a newline is received.
 ///
 /// Arguments:
 /// - `prompt` is the prompt's text. If `None`, the function uses the default text.
 /// - `hidden` tells whether the input is hidden.
 pub fn prompt<P: fmt::Display>(prompt: P, hidden: bool) -> Option<String> {
-    // Save termios state
-    let saved_termios = unsafe {
-        let mut t: termios = MaybeUninit::zeroed().assume_init();
-        tcgetattr(STDIN_FILENO, &mut t);
-        t
-    };
+    prompt_timeout(prompt, hidden, None).ok().flatten()
+}
+
+/// Same as [`prompt`], except the function fails with [`ErrorKind::TimedOut`] if no line has
+/// been received before `timeout` expires.
+///
+/// If `timeout` is `None`, the function waits indefinitely.
+pub fn prompt_timeout<P: fmt::Display>(
+    prompt: P,
+    hidden: bool,
+    timeout: Option<Duration>,
+) -> io::Result<Option<String>> {
+    let deadline = timeout.map(|t| Instant::now() + t);
     if hidden {
-        // Set temporary termios
-        let mut termios = saved_termios;
-        termios.c_lflag &= !(ICANON | ECHO | ECHOE);
-        termios.c_cc[VMIN] = 1;
-        unsafe {
-            tcsetattr(STDIN_FILENO, TCSANOW, &termios);
+        read_canonical(prompt, true, deadline)
+    } else {
+        Editor::new().read(prompt, deadline)
+    }
+}
+
+/// Shows a prompt and reads a line, returning an error at the end of input.
+fn prompt_line<P: fmt::Display>(prompt_text: P) -> io::Result<String> {
+    prompt(prompt_text, false)
+        .map(|s| s.trim().to_owned())
+        .ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))
+}
+
+/// Asks a yes/no question, until a valid answer is given.
+///
+/// `msg` is followed by the possible answers, the uppercase one being `default`, which is
+/// selected on empty input.
+pub fn confirm<M: fmt::Display>(msg: M, default: bool) -> io::Result<bool> {
+    let answers = if default { "Y/n" } else { "y/N" };
+    loop {
+        let answer = prompt_line(format_args!("{msg} ({answers}) "))?;
+        match answer.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => eprintln!("Invalid answer `{answer}`."),
+        }
+    }
+}
+
+/// Prompts for a number between `min` and `max` (inclusive), until a valid one is given.
+///
+/// `msg` is followed by the range and `default`, which is selected on empty input.
+pub fn number_in_range<M, T>(msg: M, min: T, max: T, default: T) -> io::Result<T>
+where
+    M: fmt::Display,
+    T: Copy + fmt::Display + FromStr + PartialOrd,
+{
+    loop {
+        let answer = prompt_line(format_args!("{msg} ({min}-{max}, default {default}): "))?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match answer.parse::<T>() {
+            Ok(n) if (min..=max).contains(&n) => return Ok(n),
+            Ok(_) => eprintln!("Value out of range."),
+            Err(_) => eprintln!("Invalid number `{answer}`."),
+        }
+    }
+}
+
+/// Prompts for one of the `choices`, until a valid one is given. The function returns the
+/// index of the selected choice.
+///
+/// `msg` is followed by the default choice, at index `default`, which is selected on empty
+/// input.
+pub fn choice<M: fmt::Display>(msg: M, choices: &[&str], default: usize) -> io::Result<usize> {
+    loop {
+        let answer = prompt_line(format_args!("{msg} (default {}): ", choices[default]))?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match choices.iter().position(|c| *c == answer) {
+            Some(i) => return Ok(i),
+            None => eprintln!("Invalid choice `{answer}`."),
+        }
+    }
+}
+
+/// Parses a size in bytes, with an optional `K`, `M`, `G`, `T` or `P` suffix (powers of 1024),
+/// which may be followed by `iB`.
+///
+/// Without suffix, the number is returned as is.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.strip_suffix("iB").unwrap_or(s);
+    let (num, shift) = match s.as_bytes().last()? {
+        b'K' => (&s[..s.len() - 1], 10),
+        b'M' => (&s[..s.len() - 1], 20),
+        b'G' => (&s[..s.len() - 1], 30),
+        b'T' => (&s[..s.len() - 1], 40),
+        b'P' => (&s[..s.len() - 1], 50),
+        _ => (s, 0),
+    };
+    num.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+/// Prompts for the last unit of a span starting at `min`, until a valid value between `min`
+/// and `max` (inclusive) is given.
+///
+/// The input is either:
+/// - an absolute value
+/// - `+n`: the span has a length of `n`
+/// - `-n`: the span ends `n` before `max`
+///
+/// `n` is either a number of units, or a size with a suffix (see [`parse_size`]) which is
+/// converted into units of `unit` bytes.
+///
+/// `msg` is followed by the range and `default`, which is selected on empty input.
+pub fn end_in_range<M: fmt::Display>(
+    msg: M,
+    min: u64,
+    max: u64,
+    default: u64,
+    unit: u64,
+) -> io::Result<u64> {
+    // Parses a number of units
+    let units = |s: &str| {
+        if s.ends_with(|c: char| c.is_ascii_digit()) {
+            s.parse::<u64>().ok()
+        } else {
+            parse_size(s).map(|size| size / unit)
+        }
+    };
+    loop {
+        let answer = prompt_line(format_args!("{msg} ({min}-{max}, default {default}): "))?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        let val = if let Some(len) = answer.strip_prefix('+') {
+            units(len).and_then(|n| min.checked_add(n)?.checked_sub(1))
+        } else if let Some(len) = answer.strip_prefix('-') {
+            units(len).and_then(|n| max.checked_sub(n))
+        } else {
+            answer.parse::<u64>().ok()
+        };
+        match val {
+            Some(n) if (min..=max).contains(&n) => return Ok(n),
+            Some(_) => eprintln!("Value out of range."),
+            None => eprintln!("Invalid value `{answer}`."),
+        }
+    }
+}
+
+/// Tells whether the standard input is a terminal.
+fn is_tty() -> bool {
+    unsafe { libc::isatty(STDIN_FILENO) == 1 }
+}
+
+/// Unbuffered reader on the standard input.
+///
+/// Since no input is held in a buffer, `poll` on the standard input tells whether input is
+/// available.
+struct RawStdin;
+
+impl Read for RawStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = unsafe { libc::read(STDIN_FILENO, buf.as_mut_ptr() as _, buf.len()) };
+        if len >= 0 {
+            Ok(len as _)
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+/// Waits until input is available on the standard input.
+///
+/// If `deadline` is reached first, the function fails with [`ErrorKind::TimedOut`]. If `None`,
+/// the function returns immediately.
+fn wait_input(deadline: Option<Instant>) -> io::Result<()> {
+    let Some(deadline) = deadline else {
+        return Ok(());
+    };
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut fd = pollfd {
+            fd: STDIN_FILENO,
+            events: POLLIN,
+            revents: 0,
+        };
+        let timeout = remaining.as_millis().min(c_int::MAX as _) as c_int;
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            0 => return Err(ErrorKind::TimedOut.into()),
+            n if n > 0 => return Ok(()),
+            _ => {
+                let err = io::Error::last_os_error();
+                if err.kind() != ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// Reads a line from the standard input, without buffering past the newline.
+///
+/// If the end of input is reached before anything is read, the function returns `None`.
+fn read_line_until(deadline: Option<Instant>) -> io::Result<Option<String>> {
+    let mut buf = Vec::new();
+    loop {
+        wait_input(deadline)?;
+        match read_byte(&mut RawStdin)? {
+            Some(b'\n') => break,
+            Some(b) => buf.push(b),
+            None if buf.is_empty() => return Ok(None),
+            None => break,
         }
     }
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// Shows a prompt and reads a line using the terminal's canonical mode.
+///
+/// If `deadline` is reached before a line is received, the function fails with
+/// [`ErrorKind::TimedOut`].
+fn read_canonical<P: fmt::Display>(
+    prompt: P,
+    hidden: bool,
+    deadline: Option<Instant>,
+) -> io::Result<Option<String>> {
+    let guard = hidden.then(|| {
+        TermGuard::new(|termios| {
+            termios.c_lflag &= !(ICANON | ECHO | ECHOE);
+            termios.c_cc[VMIN] = 1;
+        })
+    });
     // Show prompt
     print!("{prompt}");
     let _ = io::stdout().flush();
     // Read input
-    let input = io::stdin().lock().lines().next()?.unwrap_or(String::new());
-    if hidden {
+    let input = match deadline {
+        Some(_) => read_line_until(deadline),
+        None => io::stdin().lock().lines().next().transpose(),
+    };
+    if guard.is_some() {
         println!();
-        // Restore termios state
-        unsafe {
-            tcsetattr(STDIN_FILENO, TCSANOW, &saved_termios);
+    }
+    input
+}
+
+/// A key press, as decoded from the terminal's input.
+#[derive(Debug, Eq, PartialEq)]
+enum Key {
+    /// A printable character.
+    Char(char),
+    /// Enter.
+    Enter,
+    /// Tab.
+    Tab,
+    /// Backspace.
+    Backspace,
+    /// Delete, or Ctrl-D.
+    Delete,
+    /// Left arrow, or Ctrl-B.
+    Left,
+    /// Right arrow, or Ctrl-F.
+    Right,
+    /// Up arrow, or Ctrl-P.
+    Up,
+    /// Down arrow, or Ctrl-N.
+    Down,
+    /// Home, or Ctrl-A.
+    Home,
+    /// End, or Ctrl-E.
+    End,
+    /// Ctrl-U: delete from the beginning of the line to the cursor.
+    KillStart,
+    /// Ctrl-K: delete from the cursor to the end of the line.
+    KillEnd,
+    /// Ctrl-W: delete the word before the cursor.
+    KillWord,
+    /// An unsupported key or sequence.
+    Ignored,
+}
+
+/// Reads one byte from `input`. If the end of input is reached, the function returns `None`.
+fn read_byte<R: Read>(input: &mut R) -> io::Result<Option<u8>> {
+    let mut b = [0u8];
+    loop {
+        match input.read(&mut b) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(b[0])),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads and decodes the next key press from `input`.
+///
+/// If the end of input is reached, the function returns `None`.
+fn read_key<R: Read>(input: &mut R) -> io::Result<Option<Key>> {
+    let Some(b) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match b {
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x04 => Key::Delete,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x08 | 0x7f => Key::Backspace,
+        b'\t' => Key::Tab,
+        b'\n' | b'\r' => Key::Enter,
+        0x0b => Key::KillEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x15 => Key::KillStart,
+        0x17 => Key::KillWord,
+        0x1b => read_escape(input)?,
+        0x00..0x20 => Key::Ignored,
+        0x20..0x80 => Key::Char(b as char),
+        _ => {
+            // Multi-byte UTF-8 character
+            let len = b.leading_ones() as usize;
+            if !(2..=4).contains(&len) {
+                return Ok(Some(Key::Ignored));
+            }
+            let mut buf = [b, 0, 0, 0];
+            input.read_exact(&mut buf[1..len])?;
+            str::from_utf8(&buf[..len])
+                .ok()
+                .and_then(|s| s.chars().next())
+                .map(Key::Char)
+                .unwrap_or(Key::Ignored)
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Decodes an escape sequence from `input`, after the escape character.
+fn read_escape<R: Read>(input: &mut R) -> io::Result<Key> {
+    let Some(b) = read_byte(input)? else {
+        return Ok(Key::Ignored);
+    };
+    if b != b'[' && b != b'O' {
+        return Ok(Key::Ignored);
+    }
+    // Read parameters until the final byte
+    let mut params = Vec::new();
+    let end = loop {
+        match read_byte(input)? {
+            Some(b @ b'0'..=b'9' | b @ b';') => params.push(b),
+            Some(b) => break b,
+            None => return Ok(Key::Ignored),
+        }
+    };
+    let key = match (end, params.as_slice()) {
+        (b'A', _) => Key::Up,
+        (b'B', _) => Key::Down,
+        (b'C', _) => Key::Right,
+        (b'D', _) => Key::Left,
+        (b'H', _) | (b'~', b"1" | b"7") => Key::Home,
+        (b'F', _) | (b'~', b"4" | b"8") => Key::End,
+        (b'~', b"3") => Key::Delete,
+        _ => Key::Ignored,
+    };
+    Ok(key)
+}
+
+/// Returns the longest common prefix of all the strings in `strs`.
+fn common_prefix(strs: &[String]) -> &str {
+    let Some((first, rest)) = strs.split_first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for s in rest {
+        len = first
+            .char_indices()
+            .zip(s.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    &first[..len]
+}
+
+/// The line being edited.
+struct Line {
+    /// The prompt's text.
+    prompt: String,
+    /// The content of the line.
+    buf: Vec<char>,
+    /// The position of the cursor in `buf`.
+    cursor: usize,
+}
+
+impl Line {
+    /// Replaces the content of the line with `s`, placing the cursor at the end.
+    fn set(&mut self, s: &str) {
+        self.buf = s.chars().collect();
+        self.cursor = self.buf.len();
+    }
+
+    /// Deletes the word before the cursor, along with the whitespaces following it.
+    fn kill_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.buf[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.buf[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.buf.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Redraws the line on `out`.
+    fn refresh<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let content: String = self.buf.iter().collect();
+        write!(out, "\r{}{content}\x1b[K", self.prompt)?;
+        let back = self.buf.len() - self.cursor;
+        if back > 0 {
+            write!(out, "\x1b[{back}D")?;
+        }
+        out.flush()
+    }
+}
+
+/// A completion callback.
+///
+/// The function is called with the content of the line before the cursor, and returns the list
+/// of candidates to replace it with.
+pub type Completer = Box<dyn Fn(&str) -> Vec<String>>;
+
+/// A line editor, keeping the history of the lines read with it.
+#[derive(Default)]
+pub struct Editor {
+    /// The previously read lines, from the oldest.
+    history: Vec<String>,
+    /// The completion callback.
+    completer: Option<Completer>,
+}
+
+impl Editor {
+    /// Creates a new editor with an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the callback used for completion when Tab is pressed.
+    pub fn set_completer<F: Fn(&str) -> Vec<String> + 'static>(&mut self, completer: F) {
+        self.completer = Some(Box::new(completer));
+    }
+
+    /// Appends `line` to the history, unless it is empty or the same as the last line.
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|l| l == line) {
+            return;
+        }
+        if self.history.len() >= HISTORY_MAX {
+            self.history.remove(0);
+        }
+        self.history.push(line.to_owned());
+    }
+
+    /// Shows a prompt and reads a line. The line is added to the history.
+    ///
+    /// If the standard input is not a terminal, the function falls back to the terminal's
+    /// canonical mode.
+    ///
+    /// If the end of input is reached, the function returns `None`.
+    pub fn read_line<P: fmt::Display>(&mut self, prompt: P) -> Option<String> {
+        self.read(prompt, None).ok().flatten()
+    }
+
+    /// Same as [`Self::read_line`], except the function fails with [`ErrorKind::TimedOut`] if
+    /// `deadline` is reached before a line is received.
+    fn read<P: fmt::Display>(
+        &mut self,
+        prompt: P,
+        deadline: Option<Instant>,
+    ) -> io::Result<Option<String>> {
+        if !is_tty() {
+            return read_canonical(prompt, false, deadline);
+        }
+        // Signals are kept so that Ctrl-C still works
+        let guard = TermGuard::new(|termios| {
+            termios.c_lflag &= !(ICANON | ECHO | ECHOE);
+            termios.c_cc[VMIN] = 1;
+            termios.c_cc[VTIME] = 0;
+        });
+        let line = self.edit(prompt.to_string(), deadline);
+        drop(guard);
+        if let Ok(Some(line)) = &line {
+            self.add_history(line);
         }
+        line
+    }
+
+    /// Runs the editor on the terminal, which must be in raw mode.
+    fn edit(&self, prompt: String, deadline: Option<Instant>) -> io::Result<Option<String>> {
+        let mut stdout = io::stdout().lock();
+        let mut line = Line {
+            prompt,
+            buf: Vec::new(),
+            cursor: 0,
+        };
+        // The position in history, and the edited line saved while browsing it
+        let mut index = self.history.len();
+        let mut scratch = String::new();
+        let _ = line.refresh(&mut stdout);
+        loop {
+            let key = wait_input(deadline).and_then(|_| read_key(&mut RawStdin));
+            let key = match key {
+                Ok(Some(key)) => key,
+                Ok(None) => {
+                    let _ = writeln!(stdout);
+                    return Ok(None);
+                }
+                Err(e) => {
+                    let _ = writeln!(stdout);
+                    return Err(e);
+                }
+            };
+            match key {
+                Key::Char(c) => {
+                    line.buf.insert(line.cursor, c);
+                    line.cursor += 1;
+                }
+                Key::Enter => {
+                    let _ = writeln!(stdout);
+                    return Ok(Some(line.buf.iter().collect()));
+                }
+                Key::Tab => self.complete(&mut line, &mut stdout),
+                Key::Backspace if line.cursor > 0 => {
+                    line.cursor -= 1;
+                    line.buf.remove(line.cursor);
+                }
+                // Ctrl-D on an empty line is the end of input
+                Key::Delete if line.buf.is_empty() => {
+                    let _ = writeln!(stdout);
+                    return Ok(None);
+                }
+                Key::Delete if line.cursor < line.buf.len() => {
+                    line.buf.remove(line.cursor);
+                }
+                Key::Left => line.cursor = line.cursor.saturating_sub(1),
+                Key::Right => line.cursor = (line.cursor + 1).min(line.buf.len()),
+                Key::Home => line.cursor = 0,
+                Key::End => line.cursor = line.buf.len(),
+                Key::Up if index > 0 => {
+                    if index == self.history.len() {
+                        scratch = line.buf.iter().collect();
+                    }
+                    index -= 1;
+                    line.set(&self.history[index]);
+                }
+                Key::Down if index < self.history.len() => {
+                    index += 1;
+                    match self.history.get(index) {
+                        Some(l) => line.set(l),
+                        None => line.set(&scratch),
+                    }
+                }
+                Key::KillStart => {
+                    line.buf.drain(..line.cursor);
+                    line.cursor = 0;
+                }
+                Key::KillEnd => line.buf.truncate(line.cursor),
+                Key::KillWord => line.kill_word(),
+                _ => {}
+            }
+            let _ = line.refresh(&mut stdout);
+        }
+    }
+
+    /// Completes the content of `line` before the cursor, using the completion callback.
+    ///
+    /// If several candidates remain, they are printed on `out`.
+    fn complete<W: Write>(&self, line: &mut Line, out: &mut W) {
+        let Some(completer) = &self.completer else {
+            return;
+        };
+        let before: String = line.buf[..line.cursor].iter().collect();
+        let candidates = completer(&before);
+        let prefix = common_prefix(&candidates);
+        if prefix.len() > before.len() {
+            let after = line.buf.split_off(line.cursor);
+            line.set(prefix);
+            line.buf.extend(after);
+        } else if candidates.len() > 1 {
+            let _ = write!(out, "\r\n{}\r\n", candidates.join("  "));
+        } else {
+            // Nothing to complete: ring the bell
+            let _ = write!(out, "\x07");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prompt_keys() {
+        let mut input: &[u8] = b"a\xc3\xa9\x1b[A\x1b[3~\x1bOH\x1b[4~\x17\r";
+        let keys: Vec<_> = std::iter::from_fn(|| read_key(&mut input).unwrap()).collect();
+        assert_eq!(
+            keys,
+            [
+                Key::Char('a'),
+                Key::Char('é'),
+                Key::Up,
+                Key::Delete,
+                Key::Home,
+                Key::End,
+                Key::KillWord,
+                Key::Enter
+            ]
+        );
+    }
+
+    #[test]
+    fn prompt_edit() {
+        let mut line = Line {
+            prompt: String::new(),
+            buf: Vec::new(),
+            cursor: 0,
+        };
+        line.set("foo bar  ");
+        line.kill_word();
+        assert_eq!(line.buf.iter().collect::<String>(), "foo ");
+        assert_eq!(line.cursor, 4);
+        let candidates = ["abc".to_owned(), "abd".to_owned(), "ab".to_owned()];
+        assert_eq!(common_prefix(&candidates), "ab");
+        assert_eq!(common_prefix(&candidates[..2]), "ab");
+        assert_eq!(common_prefix(&candidates[..1]), "abc");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn prompt_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("4K"), Some(4096));
+        assert_eq!(parse_size("1MiB"), Some(1 << 20));
+        assert_eq!(parse_size("2T"), Some(2 << 40));
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("1X"), None);
+        assert_eq!(parse_size("99999999P"), None);
     }
-    Some(input)
 }
@@ -0,0 +1,301 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Password quality policy.
+//!
+//! The policy is read from [`PWQUALITY_PATH`], which uses a subset of the `pwquality.conf`
+//! format: `key = value` lines, with `#` starting comments. Unknown keys are ignored.
+//!
+//! Supported keys:
+//! - `minlen`: the minimum number of characters
+//! - `minclass`: the minimum number of character classes (lowercase, uppercase, digits, others)
+//! - `difok`: the minimum number of character changes from the old password
+//! - `usercheck`: if non-zero, reject passwords containing the user's name
+//! - `gecoscheck`: if non-zero, reject passwords containing words of the user's GECOS field
+//! - `dictpath`: path to a dictionary of forbidden passwords, one per line
+//! - `enforce_for_root`: if present, violations are errors for root too instead of warnings
+
+use crate::user::get_uid;
+use std::fmt::Formatter;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::{fmt, fs, io};
+
+/// The path to the password quality configuration file.
+pub const PWQUALITY_PATH: &str = "/etc/security/pwquality.conf";
+
+/// Words shorter than this are not checked for in passwords.
+const MIN_WORD_LEN: usize = 3;
+
+/// A password quality policy violation.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Violation {
+    /// The password is shorter than the given minimum length.
+    TooShort(usize),
+    /// The password has less than the given minimum number of character classes.
+    TooFewClasses(usize),
+    /// The password is the same as the old one.
+    Unchanged,
+    /// The password is too similar to the old one.
+    TooSimilar,
+    /// The password contains the user's name.
+    UserName,
+    /// The password contains a word of the user's GECOS field.
+    Gecos,
+    /// The password is in the dictionary.
+    Dictionary,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort(min) => write!(f, "the password is shorter than {min} characters"),
+            Self::TooFewClasses(min) => {
+                write!(f, "the password contains less than {min} character classes")
+            }
+            Self::Unchanged => write!(f, "the password is the same as the old one"),
+            Self::TooSimilar => write!(f, "the password is too similar to the old one"),
+            Self::UserName => write!(f, "the password contains the user name"),
+            Self::Gecos => write!(f, "the password contains words from the user's information"),
+            Self::Dictionary => write!(f, "the password is based on a dictionary word"),
+        }
+    }
+}
+
+/// A password quality policy.
+pub struct Policy {
+    /// The minimum number of characters.
+    pub min_length: usize,
+    /// The minimum number of character classes.
+    pub min_class: usize,
+    /// The minimum number of character changes from the old password.
+    pub difok: usize,
+    /// Whether passwords containing the user's name are rejected.
+    pub user_check: bool,
+    /// Whether passwords containing words of the user's GECOS field are rejected.
+    pub gecos_check: bool,
+    /// The path to the dictionary of forbidden passwords.
+    pub dict_path: Option<PathBuf>,
+    /// Whether violations are errors for root too.
+    pub enforce_for_root: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            min_class: 0,
+            difok: 1,
+            user_check: true,
+            gecos_check: true,
+            dict_path: None,
+            enforce_for_root: false,
+        }
+    }
+}
+
+impl Policy {
+    /// Parses the policy from the content of a configuration file.
+    ///
+    /// On error, the function returns the line number and a description.
+    pub fn parse(buf: &str) -> Result<Self, (usize, String)> {
+        let mut policy = Self::default();
+        for (i, line) in buf.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, val) = match line.split_once('=') {
+                Some((key, val)) => (key.trim(), Some(val.trim())),
+                None => (line, None),
+            };
+            let number = || {
+                val.and_then(|v| v.parse::<usize>().ok())
+                    .ok_or_else(|| (i + 1, format!("invalid value for `{key}`")))
+            };
+            match key {
+                "minlen" => policy.min_length = number()?,
+                "minclass" => policy.min_class = number()?,
+                "difok" => policy.difok = number()?,
+                "usercheck" => policy.user_check = number()? != 0,
+                "gecoscheck" => policy.gecos_check = number()? != 0,
+                "dictpath" => policy.dict_path = val.filter(|v| !v.is_empty()).map(PathBuf::from),
+                "enforce_for_root" => policy.enforce_for_root = true,
+                _ => {}
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Loads the policy from [`PWQUALITY_PATH`]. If the file does not exist, the default policy
+    /// is returned.
+    pub fn load() -> io::Result<Self> {
+        let buf = match fs::read_to_string(PWQUALITY_PATH) {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        Self::parse(&buf).map_err(|(line, msg)| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{PWQUALITY_PATH}:{line}: {msg}"),
+            )
+        })
+    }
+
+    /// Tells whether violations must be treated as errors for the current user. Otherwise,
+    /// they are only warnings.
+    pub fn is_enforced(&self) -> bool {
+        self.enforce_for_root || get_uid() != 0
+    }
+
+    /// Checks the new password `pass` against the policy.
+    ///
+    /// Arguments:
+    /// - `old` is the old password, if known.
+    /// - `login_name` is the name of the user, or group, whose password is set.
+    /// - `gecos` is the comment field of the user.
+    pub fn check(
+        &self,
+        pass: &str,
+        old: Option<&str>,
+        login_name: &str,
+        gecos: &str,
+    ) -> Result<(), Violation> {
+        if pass.chars().count() < self.min_length {
+            return Err(Violation::TooShort(self.min_length));
+        }
+        let classes = [
+            pass.chars().any(|c| c.is_lowercase()),
+            pass.chars().any(|c| c.is_uppercase()),
+            pass.chars().any(|c| c.is_ascii_digit()),
+            pass.chars().any(|c| !c.is_alphanumeric()),
+        ];
+        if classes.into_iter().filter(|c| *c).count() < self.min_class {
+            return Err(Violation::TooFewClasses(self.min_class));
+        }
+        if let Some(old) = old {
+            if pass == old {
+                return Err(Violation::Unchanged);
+            }
+            if distance(pass, old) < self.difok {
+                return Err(Violation::TooSimilar);
+            }
+        }
+        let lower = pass.to_lowercase();
+        if self.user_check && contains_word(&lower, login_name) {
+            return Err(Violation::UserName);
+        }
+        if self.gecos_check
+            && gecos
+                .split([',', ' '])
+                .any(|word| contains_word(&lower, word))
+        {
+            return Err(Violation::Gecos);
+        }
+        if let Some(path) = &self.dict_path {
+            // A missing dictionary is not an error, as it may not be installed
+            let dict = fs::read_to_string(path).unwrap_or_default();
+            if dict
+                .lines()
+                .map(str::trim)
+                .any(|word| !word.is_empty() && word.to_lowercase() == lower)
+            {
+                return Err(Violation::Dictionary);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tells whether the lowercase password `pass` contains `word` or its reverse, ignoring case.
+///
+/// Words shorter than [`MIN_WORD_LEN`] are ignored.
+fn contains_word(pass: &str, word: &str) -> bool {
+    if word.chars().count() < MIN_WORD_LEN {
+        return false;
+    }
+    let word = word.to_lowercase();
+    let reversed: String = word.chars().rev().collect();
+    pass.contains(&word) || pass.contains(&reversed)
+}
+
+/// Returns the edit distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut prev: Vec<_> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = (ca != *cb) as usize;
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn policy_parse() {
+        let policy = Policy::parse(
+            "# comment\nminlen = 12\nminclass=3 # inline\ndictpath = /usr/share/dict\n\
+             enforce_for_root\nunknown = 1\n",
+        )
+        .unwrap();
+        assert_eq!(policy.min_length, 12);
+        assert_eq!(policy.min_class, 3);
+        assert_eq!(policy.dict_path, Some(PathBuf::from("/usr/share/dict")));
+        assert!(policy.enforce_for_root);
+        assert_eq!(Policy::parse("minlen = abc").err().unwrap().0, 1);
+    }
+
+    #[test]
+    fn policy_check() {
+        let policy = Policy {
+            min_class: 2,
+            difok: 3,
+            ..Default::default()
+        };
+        let check = |pass, old| policy.check(pass, old, "alice", "Alice Liddell,42");
+        assert_eq!(check("", None), Err(Violation::TooShort(8)));
+        assert_eq!(check("abcdefgh", None), Err(Violation::TooFewClasses(2)));
+        assert_eq!(check("xyzalice9", None), Err(Violation::UserName));
+        assert_eq!(check("9ecilaxyz", None), Err(Violation::UserName));
+        assert_eq!(check("liddell99", None), Err(Violation::Gecos));
+        assert_eq!(
+            check("Tr0ub4dor", Some("Tr0ub4dor")),
+            Err(Violation::Unchanged)
+        );
+        assert_eq!(
+            check("Tr0ub4dor1", Some("Tr0ub4dor")),
+            Err(Violation::TooSimilar)
+        );
+        assert_eq!(check("Tr0ub4dor", Some("correct horse")), Ok(()));
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Subordinate IDs are ranges of UIDs and GIDs that a user is allowed to map in user namespaces,
+//! which is required by rootless containers.
+
+use crate::user::InvalidEntry;
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::Range;
+
+/// The path to the file containing subordinate UIDs.
+pub const SUBUID_PATH: &str = "/etc/subuid";
+/// The path to the file containing subordinate GIDs.
+pub const SUBGID_PATH: &str = "/etc/subgid";
+
+/// The number of IDs allocated to each user.
+pub const SUBID_COUNT: u32 = 65536;
+/// The range in which subordinate IDs are allocated.
+pub const SUBID_RANGE: Range<u64> = 100000..600100000;
+
+/// A range of subordinate IDs, present in the `subuid` and `subgid` files.
+pub struct SubId<'s> {
+    /// The login name of the user owning the range.
+    pub owner: &'s str,
+    /// The first ID of the range.
+    pub start: u32,
+    /// The number of IDs in the range.
+    pub count: u32,
+}
+
+impl SubId<'_> {
+    /// Deserializes entries from the given buffer `buf`.
+    pub fn deserialize(buf: &str) -> impl Iterator<Item = Result<SubId<'_>, InvalidEntry>> {
+        buf.split('\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut vals = line.split(':');
+                let ent = SubId {
+                    owner: vals.next()?,
+                    start: vals.next()?.parse().ok()?,
+                    count: vals.next()?.parse().ok()?,
+                };
+                if vals.next().is_some() {
+                    return None;
+                }
+                Some(ent)
+            })
+            .map(|ent| ent.ok_or(InvalidEntry))
+    }
+
+    /// Returns the range of IDs covered by the entry.
+    ///
+    /// Bounds are 64 bits wide since the end of the range may not fit in 32 bits.
+    pub fn range(&self) -> Range<u64> {
+        let start = self.start as u64;
+        start..(start + self.count as u64)
+    }
+}
+
+impl fmt::Display for SubId<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:{}:{}", self.owner, self.start, self.count)
+    }
+}
+
+/// Tells whether the two given ranges overlap.
+fn overlaps(a: &Range<u64>, b: &Range<u64>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Returns the start of the lowest range of `count` IDs in [`SUBID_RANGE`] that does not
+/// overlap any of the ranges in `entries`.
+///
+/// If no range is available, the function returns `None`.
+pub fn allocate(entries: &[SubId], count: u32) -> Option<u32> {
+    let mut ranges: Vec<_> = entries.iter().map(SubId::range).collect();
+    ranges.sort_unstable_by_key(|r| r.start);
+    let mut start = SUBID_RANGE.start;
+    for r in ranges {
+        let candidate = start..(start + count as u64);
+        if overlaps(&candidate, &r) {
+            start = start.max(r.end);
+        }
+    }
+    (start + count as u64 <= SUBID_RANGE.end)
+        .then_some(start)
+        .and_then(|start| start.try_into().ok())
+}
+
+/// Removes the IDs in `range` from the ranges owned by `owner` in `entries`.
+///
+/// Ranges that are partially covered are shrunk or split.
+pub fn remove<'s>(entries: &mut Vec<SubId<'s>>, owner: &'s str, range: Range<u64>) {
+    let mut res = Vec::with_capacity(entries.len());
+    for ent in entries.drain(..) {
+        let r = ent.range();
+        if ent.owner != owner || !overlaps(&r, &range) {
+            res.push(ent);
+            continue;
+        }
+        // Keep the parts before and after the removed range
+        let parts = [
+            r.start..range.start.min(r.end),
+            range.end.max(r.start)..r.end,
+        ];
+        for part in parts.into_iter().filter(|p| !p.is_empty()) {
+            res.push(SubId {
+                owner,
+                start: part.start as _,
+                count: (part.end - part.start) as _,
+            });
+        }
+    }
+    *entries = res;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subid_deserialize() {
+        let entries: Vec<_> = SubId::deserialize("alice:100000:65536\n\nbob:165536:65536\n")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].owner, "bob");
+        assert_eq!(entries[1].range(), 165536..231072);
+        assert!(SubId::deserialize("alice:100000").next().unwrap().is_err());
+        assert!(SubId::deserialize("alice:1:2:3").next().unwrap().is_err());
+    }
+
+    #[test]
+    fn subid_allocate() {
+        assert_eq!(allocate(&[], SUBID_COUNT), Some(100000));
+        let entries = [
+            SubId {
+                owner: "alice",
+                start: 100000,
+                count: 65536,
+            },
+            SubId {
+                owner: "bob",
+                start: 300000,
+                count: 65536,
+            },
+        ];
+        assert_eq!(allocate(&entries, SUBID_COUNT), Some(165536));
+        assert_eq!(allocate(&entries, 200000), Some(365536));
+        assert_eq!(allocate(&entries, u32::MAX), None);
+    }
+
+    #[test]
+    fn subid_remove() {
+        let mut entries = vec![
+            SubId {
+                owner: "alice",
+                start: 100,
+                count: 100,
+            },
+            SubId {
+                owner: "bob",
+                start: 100,
+                count: 100,
+            },
+        ];
+        remove(&mut entries, "alice", 120..150);
+        let ranges: Vec<_> = entries.iter().map(|e| (e.owner, e.range())).collect();
+        assert_eq!(
+            ranges,
+            [("alice", 100..120), ("alice", 150..200), ("bob", 100..200)]
+        );
+        remove(&mut entries, "alice", 0..1000);
+        assert_eq!(entries.len(), 1);
+    }
+}
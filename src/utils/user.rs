@@ -17,17 +17,23 @@
  */
 
 //! The passwd, shadow and group files are mainly used to store respectively the users list, the
-//! passwords list and the groups list.
+//! passwords list and the groups list. The gshadow file stores the groups' passwords.
 
+use super::util::get_timestamp;
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use libc::{gid_t, uid_t};
 use rand_core::OsRng;
+use std::error::Error;
+use std::ffi::OsString;
 use std::fmt::Formatter;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::Path;
-use std::{fmt, io};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::ops::RangeInclusive;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io, ptr};
 
 /// The path to the passwd file.
 pub const PASSWD_PATH: &str = "/etc/passwd";
@@ -35,8 +41,17 @@ pub const PASSWD_PATH: &str = "/etc/passwd";
 pub const SHADOW_PATH: &str = "/etc/shadow";
 /// The path to the group file.
 pub const GROUP_PATH: &str = "/etc/group";
+/// The path to the gshadow file.
+pub const GSHADOW_PATH: &str = "/etc/gshadow";
+/// The path to the file listing valid login shells.
+pub const SHELLS_PATH: &str = "/etc/shells";
+/// The path to the lock file protecting the files above against concurrent modifications.
+pub const LOCK_PATH: &str = "/etc/.pwd.lock";
 
-// TODO For each files, use a backup file with the same path but with `-` appended at the end
+/// The range of IDs allocated to regular users and groups.
+pub const ID_RANGE: RangeInclusive<u32> = 1000..=60000;
+/// The range of IDs allocated to system users and groups.
+pub const SYSTEM_ID_RANGE: RangeInclusive<u32> = 100..=999;
 
 /// Hashes the given clear password and returns it with a generated salt, in the format
 /// required for the shadow file.
@@ -60,6 +75,14 @@ pub fn check_password(hash: &str, pass: &str) -> bool {
 #[derive(Debug)]
 pub struct InvalidEntry;
 
+impl fmt::Display for InvalidEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid entry")
+    }
+}
+
+impl Error for InvalidEntry {}
+
 /// Wrapper for [`Option`] allowing to display a value if [`Some`], or nothing if [`None`].
 struct OptionDisplay<T: fmt::Display>(Option<T>);
 
@@ -94,6 +117,7 @@ impl User<'_> {
     /// Deserializes entries from the given buffer `buf`.
     pub fn deserialize(buf: &str) -> impl Iterator<Item = Result<User<'_>, InvalidEntry>> {
         buf.split('\n')
+            .filter(|line| !line.is_empty())
             .map(|line| {
                 let mut vals = line.split(':');
                 let ent = User {
@@ -147,7 +171,10 @@ pub struct Shadow<'s> {
     /// The user's encrypted password.
     pub password: &'s str,
     /// The date of the last password change in number of days since the Unix Epoch.
-    pub last_change: u32,
+    ///
+    /// `Some(0)` means the password must be changed at next login, and `None` means password
+    /// aging is disabled.
+    pub last_change: Option<u32>,
     /// The minimum number of days to wait before the user becomes usable.
     pub minimum_age: Option<u32>,
     /// The maximum number of days to the password is valid. If this delay is exceeded, the user
@@ -170,12 +197,13 @@ impl Shadow<'_> {
     /// Deserializes entries from the given buffer `buf`.
     pub fn deserialize(buf: &str) -> impl Iterator<Item = Result<Shadow<'_>, InvalidEntry>> {
         buf.split('\n')
+            .filter(|line| !line.is_empty())
             .map(|line| {
                 let mut vals = line.split(':');
                 let ent = Shadow {
                     login_name: vals.next()?,
                     password: vals.next()?,
-                    last_change: vals.next()?.parse().unwrap_or(0),
+                    last_change: vals.next()?.parse().ok(),
                     minimum_age: vals.next()?.parse().ok(),
                     maximum_age: vals.next()?.parse().ok(),
                     warning_period: vals.next()?.parse().ok(),
@@ -204,7 +232,7 @@ impl fmt::Display for Shadow<'_> {
             "{}:{}:{}:{}:{}:{}:{}:{}:{}",
             self.login_name,
             self.password,
-            self.last_change,
+            OptionDisplay(self.last_change),
             OptionDisplay(self.minimum_age),
             OptionDisplay(self.maximum_age),
             OptionDisplay(self.warning_period),
@@ -231,6 +259,7 @@ impl Group<'_> {
     /// Deserializes entries from the given buffer `buf`.
     pub fn deserialize(buf: &str) -> impl Iterator<Item = Result<Group<'_>, InvalidEntry>> {
         buf.split('\n')
+            .filter(|line| !line.is_empty())
             .map(|line| {
                 let mut vals = line.split(':');
                 let ent = Group {
@@ -246,6 +275,11 @@ impl Group<'_> {
             })
             .map(|ent| ent.ok_or(InvalidEntry))
     }
+
+    /// Tells whether the user with the given login name is a member of the group.
+    pub fn is_member(&self, login_name: &str) -> bool {
+        list_contains(self.users_list, login_name)
+    }
 }
 
 impl fmt::Display for Group<'_> {
@@ -258,17 +292,219 @@ impl fmt::Display for Group<'_> {
     }
 }
 
-/// Writes the file at path `path` with data `data`.
-pub fn write<I: IntoIterator<Item = E>, E: fmt::Display>(path: &Path, data: I) -> io::Result<()> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(path)?;
-    for line in data {
-        write!(file, "{}", line)?;
+/// A gshadow entry, present in the `gshadow` file.
+pub struct GShadow<'s> {
+    /// The group's name.
+    pub group_name: &'s str,
+    /// The encrypted group's password.
+    pub password: &'s str,
+    /// The list of administrators of this group, comma-separated.
+    pub administrators: &'s str,
+    /// The list of user members of this group, comma-separated.
+    pub members: &'s str,
+}
+
+impl GShadow<'_> {
+    /// Deserializes entries from the given buffer `buf`.
+    pub fn deserialize(buf: &str) -> impl Iterator<Item = Result<GShadow<'_>, InvalidEntry>> {
+        buf.split('\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut vals = line.split(':');
+                let ent = GShadow {
+                    group_name: vals.next()?,
+                    password: vals.next()?,
+                    administrators: vals.next()?,
+                    members: vals.next()?,
+                };
+                if vals.next().is_some() {
+                    return None;
+                }
+                Some(ent)
+            })
+            .map(|ent| ent.ok_or(InvalidEntry))
+    }
+
+    /// Check the given (not hashed) password `pass` against `self`.
+    pub fn check_password(&self, pass: &str) -> bool {
+        check_password(self.password, pass)
+    }
+
+    /// Tells whether the user with the given login name is an administrator of the group.
+    pub fn is_admin(&self, login_name: &str) -> bool {
+        list_contains(self.administrators, login_name)
+    }
+
+    /// Tells whether the user with the given login name is a member of the group.
+    pub fn is_member(&self, login_name: &str) -> bool {
+        list_contains(self.members, login_name)
+    }
+}
+
+impl fmt::Display for GShadow<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}:{}:{}:{}",
+            self.group_name, self.password, self.administrators, self.members
+        )
     }
-    Ok(())
+}
+
+/// Tells whether the comma-separated list `list` contains `name`.
+///
+/// An empty name is never contained, since empty lists would otherwise contain it.
+fn list_contains(list: &str, name: &str) -> bool {
+    !name.is_empty() && list.split(',').any(|n| n == name)
+}
+
+/// Returns the comma-separated list `list` with `name` added, if not already present.
+pub fn list_add(list: &str, name: &str) -> String {
+    if list_contains(list, name) {
+        list.to_owned()
+    } else if list.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{list},{name}")
+    }
+}
+
+/// Returns the comma-separated list `list` with `name` removed.
+pub fn list_remove(list: &str, name: &str) -> String {
+    list.split(',')
+        .filter(|n| !n.is_empty() && *n != name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Tells whether `name` is a valid user or group name.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Tells whether `val` can be stored in a field of the account files, which are made of
+/// `:`-separated fields on a single line.
+pub fn is_valid_field(val: &str) -> bool {
+    !val.contains([':', '\n'])
+}
+
+/// Returns the current date in number of days since the Unix Epoch, as stored in the shadow
+/// file.
+pub fn current_day() -> u32 {
+    (get_timestamp().as_secs() / (24 * 3600)) as _
+}
+
+/// Returns the first ID in `range` for which `used` returns `false`.
+///
+/// If no ID is available, the function returns `None`.
+pub fn allocate_id<F: Fn(u32) -> bool>(range: RangeInclusive<u32>, used: F) -> Option<u32> {
+    range.into_iter().find(|id| !used(*id))
+}
+
+/// Returns the list of valid login shells.
+pub fn read_shells() -> io::Result<Vec<String>> {
+    let buff = fs::read_to_string(SHELLS_PATH)?;
+    let shells = buff
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_owned)
+        .collect();
+    Ok(shells)
+}
+
+/// Returns the absolute path `path` relative to the root directory `root`.
+pub fn root_path(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// Returns the path `path` with `suffix` appended to it.
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
+
+/// Exclusive lock over the passwd, shadow and group files.
+///
+/// Modifications of these files must be done while holding the lock, to avoid racing with
+/// other commands. The lock is released when dropped.
+pub struct Lock {
+    /// The open lock file. Closing it releases the lock.
+    _file: File,
+}
+
+impl Lock {
+    /// Acquires the lock, waiting for it to be released if another process holds it.
+    pub fn acquire() -> io::Result<Self> {
+        Self::acquire_in(Path::new("/"))
+    }
+
+    /// Same as [`Self::acquire`], for the system whose root directory is at `root`.
+    pub fn acquire_in(root: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .mode(0o600)
+            .open(root_path(root, LOCK_PATH))?;
+        let res = unsafe { libc::lockf(file.as_raw_fd(), libc::F_LOCK, 0) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { _file: file })
+    }
+
+    /// Replaces the content of the file at path `path` with the entries in `data`.
+    ///
+    /// The new content is first written to a temporary file, which then atomically replaces the
+    /// previous file. The previous version is kept as a backup, at the same path with `-`
+    /// appended.
+    pub fn write<I: IntoIterator<Item = E>, E: fmt::Display>(
+        &self,
+        path: &Path,
+        data: I,
+    ) -> io::Result<()> {
+        let tmp_path = path_with_suffix(path, "+");
+        let backup_path = path_with_suffix(path, "-");
+        let perms = match fs::metadata(path) {
+            Ok(m) => Some(m.permissions()),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .mode(0o600)
+            .open(&tmp_path)?;
+        let mut writer = BufWriter::new(file);
+        for line in data {
+            write!(writer, "{line}")?;
+        }
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        if let Some(perms) = perms {
+            file.set_permissions(perms)?;
+            fs::copy(path, backup_path)?;
+        }
+        file.sync_all()?;
+        fs::rename(tmp_path, path)
+    }
+}
+
+/// Returns the current real UID.
+pub fn get_uid() -> uid_t {
+    unsafe { libc::getuid() }
+}
+
+/// Returns the current real GID.
+pub fn get_gid() -> gid_t {
+    unsafe { libc::getgid() }
 }
 
 /// Returns the current effective UID.
@@ -281,13 +517,39 @@ pub fn get_egid() -> gid_t {
     unsafe { libc::getegid() }
 }
 
+/// Returns the current supplementary group IDs.
+pub fn get_groups() -> io::Result<Vec<gid_t>> {
+    let count = unsafe { libc::getgroups(0, ptr::null_mut()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut groups = vec![0; count as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    groups.truncate(count as usize);
+    Ok(groups)
+}
+
+/// Sets the supplementary group IDs of the current process.
+pub fn set_groups(groups: &[gid_t]) -> io::Result<()> {
+    let result = unsafe { libc::setgroups(groups.len(), groups.as_ptr()) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 /// Sets the current user.
+///
+/// The group is set first since changing the user drops the privileges required to change it.
 pub fn set(uid: u32, gid: u32) -> io::Result<()> {
-    let result = unsafe { libc::setuid(uid) };
+    let result = unsafe { libc::setgid(gid) };
     if result < 0 {
         return Err(io::Error::last_os_error());
     }
-    let result = unsafe { libc::setgid(gid) };
+    let result = unsafe { libc::setuid(uid) };
     if result < 0 {
         return Err(io::Error::last_os_error());
     }
@@ -0,0 +1,184 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `vipw` and `vigr` allow to safely edit respectively the passwd and group files (or their
+//! shadow counterparts) with a text editor.
+//!
+//! The account files lock is held during the whole edition, and the result is validated before
+//! being installed.
+
+use std::env::ArgsOs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
+use std::{env, fmt, fs, io, process};
+use utils::error;
+use utils::prompt::confirm;
+use utils::user::{
+    GROUP_PATH, GSHADOW_PATH, GShadow, Group, Lock, PASSWD_PATH, SHADOW_PATH, Shadow, User,
+};
+
+/// Prints command usage.
+fn print_usage(bin: &str) {
+    let (file, shadow) = match bin {
+        "vipw" => ("passwd", "shadow"),
+        _ => ("group", "gshadow"),
+    };
+    println!("Usage:");
+    println!(" {bin} [-s]");
+    println!();
+    println!("Edits the {file} file.");
+    println!();
+    println!("Options:");
+    println!(" -s, --shadow\tedit the {shadow} file instead");
+}
+
+/// Returns the line numbers of the invalid entries in `content`, which is the content of the
+/// file at `path`.
+fn invalid_lines(path: &str, content: &str) -> Vec<usize> {
+    let is_valid = |line: &str| match path {
+        PASSWD_PATH => User::deserialize(line).all(|e| e.is_ok()),
+        SHADOW_PATH => Shadow::deserialize(line).all(|e| e.is_ok()),
+        GROUP_PATH => Group::deserialize(line).all(|e| e.is_ok()),
+        _ => GShadow::deserialize(line).all(|e| e.is_ok()),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !is_valid(line))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Opens the editor on the file at `path` and waits for it to exit.
+fn edit(path: &Path) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+    // The editor may be specified with arguments
+    let mut editor = editor.split_whitespace();
+    let bin = editor.next().unwrap_or("vi");
+    let status = Command::new(bin).args(editor).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{bin} exited with {status}")));
+    }
+    Ok(())
+}
+
+/// Removes the temporary file at `tmp_path`, then exits with the given error.
+fn fail<M: fmt::Display>(bin: &str, tmp_path: &Path, msg: M) -> ! {
+    let _ = fs::remove_file(tmp_path);
+    error(bin, msg);
+}
+
+/// Edits the file at `path` through a temporary copy at `tmp_path`, then returns the new content
+/// if it has been modified.
+///
+/// On failure, the function exits.
+fn edit_copy(bin: &str, path: &str, tmp_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| {
+        error(bin, format_args!("cannot read {path}: {e}"));
+    });
+    // Copy to a temporary file only readable by the owner, since it may contain password hashes
+    let mut tmp = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(0o600)
+        .open(tmp_path)
+        .unwrap_or_else(|e| {
+            error(
+                bin,
+                format_args!("cannot create {}: {e}", tmp_path.display()),
+            );
+        });
+    tmp.write_all(content.as_bytes()).unwrap_or_else(|e| {
+        fail(
+            bin,
+            tmp_path,
+            format_args!("cannot write {}: {e}", tmp_path.display()),
+        );
+    });
+    drop(tmp);
+    loop {
+        if let Err(e) = edit(tmp_path) {
+            fail(bin, tmp_path, format_args!("cannot edit {path}: {e}"));
+        }
+        let new_content = fs::read_to_string(tmp_path).unwrap_or_else(|e| {
+            fail(
+                bin,
+                tmp_path,
+                format_args!("cannot read {}: {e}", tmp_path.display()),
+            );
+        });
+        let invalid = invalid_lines(path, &new_content);
+        if invalid.is_empty() {
+            return (new_content != content).then_some(new_content);
+        }
+        for line in invalid {
+            eprintln!("{bin}: {path}: invalid entry at line {line}");
+        }
+        let again = confirm("Edit again?", false).unwrap_or(false);
+        if !again {
+            fail(bin, tmp_path, format_args!("{path} is unchanged"));
+        }
+    }
+}
+
+pub fn main(bin: &str, args: ArgsOs) {
+    let mut shadow = false;
+    for arg in args {
+        match arg.to_str() {
+            Some("-s" | "--shadow") => shadow = true,
+            Some("-h" | "--help") => {
+                print_usage(bin);
+                exit(0);
+            }
+            _ => {
+                print_usage(bin);
+                exit(1);
+            }
+        }
+    }
+    let (path, counterpart) = match (bin, shadow) {
+        ("vipw", false) => (PASSWD_PATH, Some(("shadow", SHADOW_PATH))),
+        ("vipw", true) => (SHADOW_PATH, None),
+        (_, false) => (GROUP_PATH, Some(("gshadow", GSHADOW_PATH))),
+        (_, true) => (GSHADOW_PATH, None),
+    };
+    let lock = Lock::acquire().unwrap_or_else(|e| {
+        error(bin, format_args!("cannot lock account files: {e}"));
+    });
+    let tmp_path = PathBuf::from(format!("{path}.{bin}.{}", process::id()));
+    let new_content = edit_copy(bin, path, &tmp_path);
+    let _ = fs::remove_file(&tmp_path);
+    let Some(new_content) = new_content else {
+        println!("{path} is unchanged");
+        return;
+    };
+    lock.write(Path::new(path), [new_content])
+        .unwrap_or_else(|e| error(bin, format_args!("cannot write {path}: {e}")));
+    if let Some((name, counterpart)) = counterpart
+        && Path::new(counterpart).exists()
+    {
+        println!("You have modified {path}.");
+        println!("You may need to modify {counterpart} for consistency.");
+        println!("Please use the command '{bin} -s' to edit the {name} file.");
+    }
+}